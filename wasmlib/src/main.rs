@@ -5,6 +5,283 @@
 
 mod wasm;
 
-fn main() {
-	println!("Hello, world!");
+use std::collections::HashMap;
+use std::fmt;
+use std::process::ExitCode;
+
+use wasm::{section_name, DataType, Imports, Instance, Instr, Style, Trap, Value, Wasi, Wasm};
+
+const USAGE: &str = "\
+Usage: wasmlib <command> <module> [arguments]
+
+Commands:
+  dump <module>                    Print the sections and a disassembly
+  validate <module>                Type-check the module
+  run <module> <export> [args...]  Invoke an exported function and print its results
+  stats <module>                   Print function counts, section sizes and an opcode histogram
+
+Modules starting with the binary magic number are decoded, others are parsed as text.
+
+Exit codes:
+  0   success
+  64  invalid command line, export or arguments
+  65  the module could not be decoded, parsed or validated
+  66  the module could not be read
+  69  the module could not be instantiated, for example because of an unknown import
+  70  the function trapped
+
+Modules may import WASI preview1 functions. When `proc_exit` is called with a code from 0 to
+63, that code becomes the exit status. Other codes, including negative ones, exit with 63 and
+are printed, so a module can never be mistaken for a failure of wasmlib itself.";
+
+// Taken from sysexits.h, leaving 0 to 63 to modules calling `proc_exit`.
+const EXITED: u8 = 63;
+const MISUSED: u8 = 64;
+const INVALID: u8 = 65;
+const UNREADABLE: u8 = 66;
+const UNLINKED: u8 = 69;
+const TRAPPED: u8 = 70;
+
+struct Failure {
+	code: u8,
+	message: String,
+}
+
+fn fail<T>(code: u8, message: impl fmt::Display) -> Result<T, Failure> {
+	Err(Failure { code, message: message.to_string() })
+}
+
+fn main() -> ExitCode {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	match command(&args) {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(failure) => {
+			if !failure.message.is_empty() {
+				eprintln!("{}", failure.message);
+			}
+			ExitCode::from(failure.code)
+		}
+	}
+}
+
+fn command(args: &[String]) -> Result<(), Failure> {
+	let [command, path, rest @ ..] = args else {
+		return fail(MISUSED, USAGE);
+	};
+	match (command.as_str(), rest) {
+		("dump" | "validate" | "stats", []) | ("run", [_, ..]) => {}
+		_ => return fail(MISUSED, USAGE),
+	}
+	let (bytes, wasm) = load(path)?;
+	match (command.as_str(), rest) {
+		("dump", _) => dump(&bytes, &wasm),
+		("validate", _) => wasm.validate().or_else(|error| fail(INVALID, error)),
+		("run", [export, args @ ..]) => run(&wasm, path, export, args),
+		_ => stats(&bytes, &wasm),
+	}
+}
+
+// Returns the binary encoding along with the module, so that sections can be measured even
+// when the module was given as text.
+fn load(path: &str) -> Result<(Vec<u8>, Wasm), Failure> {
+	let bytes = match std::fs::read(path) {
+		Ok(bytes) => bytes,
+		Err(error) => return fail(UNREADABLE, format!("{}: {}", path, error)),
+	};
+	if bytes.starts_with(b"\0asm") {
+		return match Wasm::read_from(&mut &bytes[..]) {
+			Ok(wasm) => Ok((bytes, wasm)),
+			Err(error) => fail(INVALID, format!("{}: {}", path, error)),
+		};
+	}
+	let Ok(text) = String::from_utf8(bytes) else {
+		return fail(INVALID, format!("{}: Neither a binary nor a text module", path));
+	};
+	let wasm = match Wasm::from_wat(&text) {
+		Ok(wasm) => wasm,
+		Err(error) => return fail(INVALID, format!("{}:{}", path, error)),
+	};
+	let mut bytes = vec![];
+	match wasm.write_to(&mut bytes) {
+		Ok(()) => Ok((bytes, wasm)),
+		Err(error) => fail(INVALID, format!("{}: {}", path, error)),
+	}
+}
+
+fn sections(bytes: &[u8]) -> Result<Vec<(u8, usize, usize)>, Failure> {
+	Wasm::sections(bytes).or_else(|error| fail(INVALID, error))
+}
+
+fn dump(bytes: &[u8], wasm: &Wasm) -> Result<(), Failure> {
+	println!("{:<12} {:>8} {:>8}", "section", "offset", "size");
+	for (id, offset, size) in sections(bytes)? {
+		println!("{:<12} {:>#8x} {:>8}", section_name(id), offset, size);
+	}
+	println!();
+	print!("{}", wasm.to_wat(Style::Flat));
+	Ok(())
+}
+
+fn run(wasm: &Wasm, path: &str, export: &str, args: &[String]) -> Result<(), Failure> {
+	wasm.validate().or_else(|error| fail(INVALID, error))?;
+	let Some(&index) = wasm.exports.get(export) else {
+		return fail(MISUSED, format!("Unknown export: {}", export));
+	};
+	let Some(typ) = wasm.function_type(index) else {
+		return fail(INVALID, format!("Export {}: Unknown function: {}", export, index));
+	};
+	let params = &typ.params;
+	if args.len() != params.len() {
+		return fail(MISUSED, format!("{} takes {} arguments", export, params.len()));
+	}
+	let mut values = vec![];
+	for (arg, typ) in args.iter().zip(params) {
+		match parse_value(arg, typ) {
+			Some(value) => values.push(value),
+			None => return fail(MISUSED, format!("Invalid {} argument: {}", typ, arg)),
+		}
+	}
+	let mut imports = Imports::new();
+	Wasi::new().arg(path).link(&mut imports);
+	let result = match Instance::with_imports(wasm, imports) {
+		Ok(mut instance) => instance.invoke(export, &values),
+		Err(Trap::Exit(code)) => Err(Trap::Exit(code)),
+		Err(trap) => return fail(UNLINKED, trap),
+	};
+	match result {
+		Ok(results) => results.iter().for_each(|value| println!("{}", value)),
+		Err(Trap::Exit(code)) => return exit(code),
+		Err(trap) => return fail(TRAPPED, trap),
+	}
+	Ok(())
+}
+
+fn exit(code: i32) -> Result<(), Failure> {
+	match u8::try_from(code) {
+		Ok(code) if code <= EXITED => fail(code, ""),
+		_ => fail(EXITED, format!("exited with code {}", code)),
+	}
+}
+
+// Integers may be given signed or unsigned, and references only as `null`.
+fn parse_value(arg: &str, typ: &DataType) -> Option<Value> {
+	Some(match typ {
+		DataType::I32 => {
+			Value::I32(arg.parse().or_else(|_| arg.parse::<u32>().map(|x| x as i32)).ok()?)
+		}
+		DataType::I64 => {
+			Value::I64(arg.parse().or_else(|_| arg.parse::<u64>().map(|x| x as i64)).ok()?)
+		}
+		DataType::F32 => Value::F32(arg.parse().ok()?),
+		DataType::F64 => Value::F64(arg.parse().ok()?),
+		DataType::V128 => Value::V128(arg.parse().ok()?),
+		DataType::FuncRef | DataType::ExternRef if arg == "null" => Value::zero(typ),
+		DataType::FuncRef | DataType::ExternRef => return None,
+	})
+}
+
+fn stats(bytes: &[u8], wasm: &Wasm) -> Result<(), Failure> {
+	let (imported, defined) = (wasm.imports.len(), wasm.functions.len());
+	println!("functions: {} ({} imported, {} defined)", imported + defined, imported, defined);
+	println!();
+	println!("{:<12} {:>8}", "section", "size");
+	for (id, _, size) in sections(bytes)? {
+		println!("{:<12} {:>8}", section_name(id), size);
+	}
+	let mut histogram = HashMap::new();
+	for function in wasm.functions.iter() {
+		count(&function.body, &mut histogram);
+	}
+	let mut histogram: Vec<_> = histogram.into_iter().collect();
+	histogram.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.cmp(b)));
+	println!();
+	println!("{:<20} {:>8}", "opcode", "count");
+	for (name, count) in histogram {
+		println!("{:<20} {:>8}", name, count);
+	}
+	Ok(())
+}
+
+fn count(body: &[Instr], histogram: &mut HashMap<&'static str, usize>) {
+	for instr in body {
+		*histogram.entry(instr.name()).or_default() += 1;
+		match instr {
+			Instr::block(_, body) | Instr::looped(_, body) => count(body, histogram),
+			Instr::if_else(_, then, otherwise) => {
+				count(then, histogram);
+				count(otherwise, histogram);
+			}
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_arguments() {
+		assert_eq!(parse_value("-1", &DataType::I32), Some(Value::I32(-1)));
+		assert_eq!(parse_value("4294967295", &DataType::I32), Some(Value::I32(-1)));
+		assert_eq!(parse_value("2.5", &DataType::F64), Some(Value::F64(2.5)));
+		assert_eq!(parse_value("null", &DataType::ExternRef), Some(Value::ExternRef(None)));
+		assert_eq!(parse_value("x", &DataType::I64), None);
+		let args = ["run".to_string(), "/nonexistent.wasm".to_string()];
+		assert_eq!(command(&args).err().unwrap().code, MISUSED);
+		let args = ["stats".to_string(), "/nonexistent.wasm".to_string()];
+		assert_eq!(command(&args).err().unwrap().code, UNREADABLE);
+	}
+
+	#[test]
+	fn runs_wasi_modules() {
+		let run = |name: &str, wat: &str| {
+			let path = std::env::temp_dir().join(format!("wasmlib-{}.wat", name));
+			std::fs::write(&path, wat).unwrap();
+			let args = ["run", path.to_str().unwrap(), "_start"].map(String::from);
+			command(&args).err().map(|failure| failure.code)
+		};
+		let exit = |code: i32| {
+			format!(
+				r#"
+				(module
+					(import "wasi_snapshot_preview1" "proc_exit" (func $exit (param i32)))
+					(memory (export "memory") 1)
+					(func (export "_start") (call $exit (i32.const {}))))
+			"#,
+				code
+			)
+		};
+		assert_eq!(run("exit", &exit(7)), Some(7));
+		assert_eq!(run("exit-one", &exit(1)), Some(1));
+		assert_eq!(run("exit-invalid", &exit(INVALID as i32)), Some(EXITED));
+		assert_eq!(run("exit-large", &exit(256)), Some(EXITED));
+		assert_eq!(run("exit-negative", &exit(-1)), Some(EXITED));
+		assert_eq!(run("exit-zero", &exit(0)), Some(0));
+		let unlinked = r#"(module (import "env" "f" (func)) (func (export "_start")))"#;
+		assert_eq!(run("unlinked", unlinked), Some(UNLINKED));
+		assert_eq!(run("trap", r#"(module (func (export "_start") unreachable))"#), Some(TRAPPED));
+		assert_eq!(run("empty", r#"(module (func (export "_start")))"#), None);
+	}
+
+	#[test]
+	fn counts_opcodes() {
+		let wasm = Wasm::from_wat(
+			r#"
+			(module
+				(func (param i32) (result i32)
+					local.get 0
+					if (result i32)
+						local.get 0
+					else
+						i32.const 1
+					end))
+		"#,
+		)
+		.unwrap();
+		let mut histogram = HashMap::new();
+		count(&wasm.functions[0].body, &mut histogram);
+		assert_eq!(histogram.len(), 3);
+		assert_eq!((histogram["local.get"], histogram["if"], histogram["i32.const"]), (2, 1, 1));
+	}
 }
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+
+use super::instruction::Instr;
+use super::validator::ValidationError;
+use super::{
+	BlockType, Data, DataMode, DataType, Function, FunctionType, Global, GlobalType, Limits, Wasm,
+};
+
+#[derive(Default)]
+pub struct ModuleBuilder {
+	wasm: Wasm,
+	defined: Vec<bool>,
+	error: Option<ValidationError>,
+}
+
+pub struct FunctionBuilder {
+	index: usize,
+	signature: FunctionType,
+	locals: Vec<DataType>,
+	blocks: Vec<Block>,
+	error: Option<ValidationError>,
+}
+
+enum Kind {
+	Function,
+	Block,
+	Loop,
+	If,
+	Else,
+}
+
+struct Block {
+	kind: Kind,
+	label: String,
+	typ: BlockType,
+	then: Vec<Instr>,
+	body: Vec<Instr>,
+}
+
+impl ModuleBuilder {
+	pub fn new() -> ModuleBuilder {
+		ModuleBuilder::default()
+	}
+
+	pub fn name(&mut self, name: &str) -> &mut Self {
+		self.wasm.names.module = Some(name.to_string());
+		self
+	}
+
+	pub fn import_function(
+		&mut self,
+		module: &str,
+		name: &str,
+		params: &[DataType],
+		results: &[DataType],
+	) -> usize {
+		if !self.wasm.functions.is_empty() {
+			self.fail(None, "Function imports must be declared before functions".to_string());
+		}
+		let index = self.wasm.imports.len();
+		let typ = FunctionType { params: params.to_vec(), resuls: results.to_vec() };
+		self.wasm.imports.push((typ, module.to_string(), name.to_string()));
+		self.wasm.names.functions.insert(index, name.to_string());
+		index
+	}
+
+	pub fn function(
+		&mut self,
+		name: &str,
+		params: &[DataType],
+		results: &[DataType],
+	) -> FunctionBuilder {
+		let index = self.wasm.imports.len() + self.wasm.functions.len();
+		let signature = FunctionType { params: params.to_vec(), resuls: results.to_vec() };
+		let function = Function { signature: signature.clone(), locals: vec![], body: vec![] };
+		self.wasm.functions.push(function);
+		self.wasm.names.functions.insert(index, name.to_string());
+		self.defined.push(false);
+		let root = Block {
+			kind: Kind::Function,
+			label: String::new(),
+			typ: BlockType::Empty,
+			then: vec![],
+			body: vec![],
+		};
+		FunctionBuilder { index, signature, locals: vec![], blocks: vec![root], error: None }
+	}
+
+	pub fn define(&mut self, function: FunctionBuilder) -> &mut Self {
+		let index = function.index - self.wasm.imports.len();
+		match function.finish() {
+			Ok(function) => {
+				self.wasm.functions[index] = function;
+				self.defined[index] = true;
+			}
+			Err(error) => {
+				self.error.get_or_insert(error);
+			}
+		}
+		self
+	}
+
+	pub fn typ(&mut self, params: &[DataType], results: &[DataType]) -> usize {
+		self.wasm.type_index(FunctionType { params: params.to_vec(), resuls: results.to_vec() })
+	}
+
+	pub fn memory(&mut self, min: u32, max: Option<u32>) -> usize {
+		self.wasm.memories.push(Limits { min, max });
+		self.wasm.memory_imports.len() + self.wasm.memories.len() - 1
+	}
+
+	pub fn global(&mut self, typ: DataType, mutable: bool, init: Instr) -> usize {
+		let typ = GlobalType { typ, mutable };
+		self.wasm.globals.push(Global { typ, init: vec![init] });
+		self.wasm.global_imports.len() + self.wasm.globals.len() - 1
+	}
+
+	pub fn data(&mut self, offset: u32, bytes: &[u8]) -> &mut Self {
+		let mode = DataMode::Active(0, vec![Instr::i32_const(offset as i32)]);
+		self.wasm.data.push(Data { mode, bytes: bytes.to_vec() });
+		self
+	}
+
+	pub fn export_function(&mut self, name: &str, index: usize) -> &mut Self {
+		self.export(name, index, |wasm| &mut wasm.exports)
+	}
+
+	pub fn export_memory(&mut self, name: &str, index: usize) -> &mut Self {
+		self.export(name, index, |wasm| &mut wasm.memory_exports)
+	}
+
+	pub fn export_global(&mut self, name: &str, index: usize) -> &mut Self {
+		self.export(name, index, |wasm| &mut wasm.global_exports)
+	}
+
+	pub fn start(&mut self, index: usize) -> &mut Self {
+		self.wasm.start_function = Some(index);
+		self
+	}
+
+	pub fn build(self) -> Result<Wasm, ValidationError> {
+		if let Some(error) = self.error {
+			return Err(error);
+		}
+		if let Some(index) = self.defined.iter().position(|&defined| !defined) {
+			let message = "Function is declared but never defined".to_string();
+			let function = Some(self.wasm.imports.len() + index);
+			return Err(ValidationError { function, path: vec![], message });
+		}
+		self.wasm.validate()?;
+		Ok(self.wasm)
+	}
+
+	fn export(
+		&mut self,
+		name: &str,
+		index: usize,
+		exports: impl Fn(&mut Wasm) -> &mut HashMap<String, usize>,
+	) -> &mut Self {
+		let wasm = &self.wasm;
+		let all = [&wasm.exports, &wasm.table_exports, &wasm.memory_exports, &wasm.global_exports];
+		if all.iter().any(|exports| exports.contains_key(name)) {
+			self.fail(None, format!("Duplicate export: {}", name));
+		}
+		exports(&mut self.wasm).insert(name.to_string(), index);
+		self
+	}
+
+	fn fail(&mut self, function: Option<usize>, message: String) {
+		self.error.get_or_insert(ValidationError { function, path: vec![], message });
+	}
+}
+
+impl FunctionBuilder {
+	pub fn index(&self) -> usize {
+		self.index
+	}
+
+	pub fn local(&mut self, typ: DataType) -> u32 {
+		self.locals.push(typ);
+		(self.signature.params.len() + self.locals.len() - 1) as u32
+	}
+
+	pub fn instr(&mut self, instr: Instr) -> &mut Self {
+		self.blocks.last_mut().unwrap().body.push(instr);
+		self
+	}
+
+	pub fn block(&mut self, label: &str, typ: BlockType) -> &mut Self {
+		self.open(Kind::Block, label, typ)
+	}
+
+	pub fn looped(&mut self, label: &str, typ: BlockType) -> &mut Self {
+		self.open(Kind::Loop, label, typ)
+	}
+
+	pub fn if_else(&mut self, label: &str, typ: BlockType) -> &mut Self {
+		self.open(Kind::If, label, typ)
+	}
+
+	pub fn otherwise(&mut self) -> &mut Self {
+		let block = self.blocks.last_mut().unwrap();
+		match block.kind {
+			Kind::If => {
+				block.kind = Kind::Else;
+				block.then = std::mem::take(&mut block.body);
+			}
+			_ => self.fail("Else without if".to_string()),
+		}
+		self
+	}
+
+	pub fn end(&mut self) -> &mut Self {
+		if self.blocks.len() == 1 {
+			self.fail("End without block".to_string());
+			return self;
+		}
+		let block = self.blocks.pop().unwrap();
+		let instr = match block.kind {
+			Kind::Block => Instr::block(block.typ, block.body),
+			Kind::Loop => Instr::looped(block.typ, block.body),
+			Kind::If => Instr::if_else(block.typ, block.body, vec![]),
+			Kind::Else => Instr::if_else(block.typ, block.then, block.body),
+			Kind::Function => unreachable!(),
+		};
+		self.instr(instr)
+	}
+
+	pub fn br(&mut self, label: &str) -> &mut Self {
+		let depth = self.depth(label);
+		self.instr(Instr::br(depth))
+	}
+
+	pub fn br_if(&mut self, label: &str) -> &mut Self {
+		let depth = self.depth(label);
+		self.instr(Instr::br_if(depth))
+	}
+
+	pub fn br_table(&mut self, labels: &[&str], default: &str) -> &mut Self {
+		let mut depths: Vec<_> = labels.iter().map(|label| self.depth(label)).collect();
+		depths.push(self.depth(default));
+		self.instr(Instr::br_table(depths))
+	}
+
+	fn open(&mut self, kind: Kind, label: &str, typ: BlockType) -> &mut Self {
+		let label = label.to_string();
+		self.blocks.push(Block { kind, label, typ, then: vec![], body: vec![] });
+		self
+	}
+
+	fn depth(&mut self, label: &str) -> usize {
+		let labels = self.blocks[1..].iter().rev();
+		match labels.map(|block| &block.label).position(|name| name == label && !name.is_empty()) {
+			Some(depth) => depth,
+			None => {
+				self.fail(format!("Unknown label: {}", label));
+				0
+			}
+		}
+	}
+
+	fn fail(&mut self, message: String) {
+		let path = self.blocks.iter().map(|block| block.then.len() + block.body.len()).collect();
+		self.error.get_or_insert(ValidationError { function: Some(self.index), path, message });
+	}
+
+	fn finish(mut self) -> Result<Function, ValidationError> {
+		if self.blocks.len() > 1 {
+			let label = self.blocks.last().unwrap().label.clone();
+			self.fail(format!("Unclosed block: {}", label));
+		}
+		if let Some(error) = self.error {
+			return Err(error);
+		}
+		let body = self.blocks.pop().unwrap().body;
+		Ok(Function { signature: self.signature, locals: self.locals, body })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::interpreter::{Instance, Value};
+	use DataType::*;
+	use Instr::*;
+
+	#[test]
+	fn builds_module() {
+		let mut module = ModuleBuilder::new();
+		module.name("math");
+		let mut sum = module.function("sum", &[I32], &[I32]);
+		let total = sum.local(I32);
+		sum.block("done", BlockType::Empty)
+			.looped("next", BlockType::Empty)
+			.instr(local_get(0))
+			.instr(i32_eqz)
+			.br_if("done")
+			.instr(local_get(total))
+			.instr(local_get(0))
+			.instr(i32_add)
+			.instr(local_set(total))
+			.instr(local_get(0))
+			.instr(i32_const(1))
+			.instr(i32_sub)
+			.instr(local_set(0))
+			.br("next")
+			.end()
+			.end()
+			.instr(local_get(total));
+		let index = sum.index();
+		let mut sign = module.function("sign", &[I32], &[I32]);
+		sign.instr(local_get(0))
+			.instr(i32_const(0))
+			.instr(i32_lt_s)
+			.if_else("negative", BlockType::Value(I32))
+			.instr(i32_const(-1))
+			.otherwise()
+			.instr(i32_const(1))
+			.end();
+		let mut classify = module.function("classify", &[I32], &[I32]);
+		classify
+			.block("two", BlockType::Empty)
+			.block("one", BlockType::Empty)
+			.block("zero", BlockType::Empty)
+			.instr(local_get(0))
+			.br_table(&["zero", "one"], "two")
+			.end()
+			.instr(i32_const(100))
+			.instr(return_result)
+			.end()
+			.instr(i32_const(101))
+			.instr(return_result)
+			.end()
+			.instr(i32_const(102));
+		module.define(sum).define(sign).define(classify);
+		module
+			.export_function("sum", index)
+			.export_function("sign", 1)
+			.export_function("classify", 2);
+		let wasm = module.build().unwrap();
+		assert_eq!(wasm.names.functions[&0], "sum");
+		let body = &wasm.functions[0].body;
+		assert!(
+			matches!(&body[0], block(BlockType::Empty, body) if matches!(&body[0], looped(BlockType::Empty, body)
+			if body[2] == br_if(1) && body[11] == br(0)))
+		);
+
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("sum", &[Value::I32(4)]), Ok(vec![Value::I32(10)]));
+		assert_eq!(instance.invoke("sign", &[Value::I32(-5)]), Ok(vec![Value::I32(-1)]));
+		assert_eq!(instance.invoke("sign", &[Value::I32(0)]), Ok(vec![Value::I32(1)]));
+		let mut classify = |x| instance.invoke("classify", &[Value::I32(x)]).unwrap();
+		assert_eq!(classify(0), vec![Value::I32(100)]);
+		assert_eq!(classify(1), vec![Value::I32(101)]);
+		assert_eq!(classify(7), vec![Value::I32(102)]);
+	}
+
+	#[test]
+	fn builds_multi_value_blocks() {
+		let mut module = ModuleBuilder::new();
+		let pair = module.typ(&[I32], &[I32, I32]);
+		let mut divmod = module.function("divmod", &[I32, I32], &[I32, I32]);
+		divmod
+			.instr(local_get(0))
+			.block("split", BlockType::Type(pair))
+			.instr(local_get(1))
+			.instr(i32_div_u)
+			.instr(local_get(0))
+			.instr(local_get(1))
+			.instr(i32_rem_u)
+			.end();
+		module.define(divmod).export_function("divmod", 0);
+		assert_eq!(module.typ(&[I32], &[I32, I32]), pair);
+		let wasm = module.build().unwrap();
+		let mut instance = Instance::new(&wasm).unwrap();
+		let result = instance.invoke("divmod", &[Value::I32(17), Value::I32(5)]);
+		assert_eq!(result, Ok(vec![Value::I32(3), Value::I32(2)]));
+	}
+
+	#[test]
+	fn reports_errors() {
+		let error = |build: fn(&mut ModuleBuilder)| {
+			let mut module = ModuleBuilder::new();
+			build(&mut module);
+			module.build().err().unwrap().to_string()
+		};
+		assert_eq!(
+			error(|module| {
+				let mut f = module.function("f", &[], &[]);
+				f.block("a", BlockType::Empty).instr(nop).br("b").end();
+				module.define(f);
+			}),
+			"function 0, instruction 0.1: Unknown label: b"
+		);
+		assert_eq!(
+			error(|module| {
+				let mut f = module.function("f", &[], &[]);
+				f.looped("a", BlockType::Empty);
+				module.define(f);
+			}),
+			"function 0, instruction 0.0: Unclosed block: a"
+		);
+		assert_eq!(
+			error(|module| {
+				module.function("f", &[], &[]);
+				module.import_function("env", "g", &[], &[]);
+			}),
+			"Function imports must be declared before functions"
+		);
+		assert_eq!(
+			error(|module| {
+				module.function("f", &[], &[]);
+			}),
+			"function 0: Function is declared but never defined"
+		);
+		assert_eq!(
+			error(|module| {
+				let mut f = module.function("f", &[], &[I32]);
+				f.instr(i64_const(1));
+				module.define(f);
+			}),
+			"function 0: Expected i32 but found i64"
+		);
+	}
+}
@@ -0,0 +1,478 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::instruction::Instr;
+use super::{BlockType, Function, Wasm};
+
+/// A function body as basic blocks. Block 0 is the entry; every jump to a block keeps the
+/// values below `height - arity` and passes the top `arity` values along, dropping the rest.
+pub struct Cfg {
+	pub blocks: Vec<BasicBlock>,
+}
+
+pub struct BasicBlock {
+	pub body: Vec<Instr>,
+	pub terminator: Terminator,
+	pub height: usize,
+	pub arity: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Terminator {
+	Jump(usize),
+	/// Pops an `i32` and continues at the first block unless it is zero.
+	Branch(usize, usize),
+	/// Pops an `i32` and continues at the block it indexes, or at the last one.
+	Table(Vec<usize>),
+	Return,
+	Unreachable,
+}
+
+impl Terminator {
+	pub fn successors(&self) -> Vec<usize> {
+		match self {
+			Terminator::Jump(target) => vec![*target],
+			Terminator::Branch(then, otherwise) => vec![*then, *otherwise],
+			Terminator::Table(targets) => targets.clone(),
+			Terminator::Return | Terminator::Unreachable => vec![],
+		}
+	}
+
+	fn successors_mut(&mut self) -> Vec<&mut usize> {
+		match self {
+			Terminator::Jump(target) => vec![target],
+			Terminator::Branch(then, otherwise) => vec![then, otherwise],
+			Terminator::Table(targets) => targets.iter_mut().collect(),
+			Terminator::Return | Terminator::Unreachable => vec![],
+		}
+	}
+}
+
+pub struct Dominators {
+	pub immediate: Vec<Option<usize>>,
+}
+
+impl Dominators {
+	pub fn dominates(&self, a: usize, mut b: usize) -> bool {
+		loop {
+			if a == b {
+				return true;
+			}
+			match self.immediate[b] {
+				Some(dominator) => b = dominator,
+				None => return false,
+			}
+		}
+	}
+}
+
+pub struct Loop {
+	pub header: usize,
+	pub blocks: BTreeSet<usize>,
+	pub parent: Option<usize>,
+	pub depth: usize,
+}
+
+struct Lowering<'a> {
+	wasm: &'a Wasm,
+	blocks: Vec<BasicBlock>,
+	labels: Vec<usize>,
+	current: Option<usize>,
+	height: usize,
+}
+
+impl Lowering<'_> {
+	fn block(&mut self, height: usize, arity: usize) -> usize {
+		let terminator = Terminator::Unreachable;
+		self.blocks.push(BasicBlock { body: vec![], terminator, height, arity });
+		self.blocks.len() - 1
+	}
+
+	fn label(&self, depth: usize) -> usize {
+		self.labels[self.labels.len() - 1 - depth]
+	}
+
+	fn terminate(&mut self, terminator: Terminator, next: Option<usize>) {
+		if let Some(current) = self.current {
+			self.blocks[current].terminator = terminator;
+		}
+		self.current = next;
+	}
+
+	fn arity(&self, typ: &BlockType) -> (usize, usize) {
+		let typ = self.wasm.block_type(typ).unwrap();
+		(typ.params.len(), typ.resuls.len())
+	}
+
+	fn body(&mut self, body: &[Instr]) {
+		use Instr::*;
+		for instr in body {
+			let Some(current) = self.current else {
+				return;
+			};
+			match instr {
+				block(typ, body) => {
+					let (params, results) = self.arity(typ);
+					let base = self.height - params;
+					let join = self.block(base + results, results);
+					self.labels.push(join);
+					self.body(body);
+					self.labels.pop();
+					self.terminate(Terminator::Jump(join), Some(join));
+					self.height = base + results;
+				}
+				looped(typ, body) => {
+					let (params, results) = self.arity(typ);
+					let base = self.height - params;
+					let header = self.block(self.height, params);
+					self.terminate(Terminator::Jump(header), Some(header));
+					self.labels.push(header);
+					self.body(body);
+					self.labels.pop();
+					self.height = base + results;
+				}
+				if_else(typ, then, otherwise) => {
+					let (params, results) = self.arity(typ);
+					self.height -= 1;
+					let base = self.height - params;
+					let join = self.block(base + results, results);
+					let then_block = self.block(self.height, params);
+					let else_block = match otherwise.is_empty() {
+						true => join,
+						false => self.block(self.height, params),
+					};
+					self.terminate(Terminator::Branch(then_block, else_block), None);
+					self.labels.push(join);
+					for (entry, body) in [(then_block, then), (else_block, otherwise)] {
+						if entry != join {
+							(self.current, self.height) = (Some(entry), base + params);
+							self.body(body);
+							self.terminate(Terminator::Jump(join), None);
+						}
+					}
+					self.labels.pop();
+					(self.current, self.height) = (Some(join), base + results);
+				}
+				br(depth) => self.terminate(Terminator::Jump(self.label(*depth)), None),
+				br_if(depth) => {
+					self.height -= 1;
+					let next = self.block(self.height, 0);
+					self.terminate(Terminator::Branch(self.label(*depth), next), Some(next));
+				}
+				br_table(labels) => {
+					let targets = labels.iter().map(|&depth| self.label(depth)).collect();
+					self.terminate(Terminator::Table(targets), None);
+				}
+				return_result => self.terminate(Terminator::Jump(self.labels[0]), None),
+				unreachable => self.terminate(Terminator::Unreachable, None),
+				instr => {
+					let (pops, pushes) = stack_effect(self.wasm, instr);
+					self.height = self.height - pops + pushes;
+					self.blocks[current].body.push(instr.clone());
+				}
+			}
+		}
+	}
+}
+
+/// The number of values `instr` pops and pushes, for instructions other than blocks.
+pub(super) fn stack_effect(wasm: &Wasm, instr: &Instr) -> (usize, usize) {
+	match instr {
+		Instr::call(index) => {
+			let typ = wasm.function_type(*index).unwrap();
+			(typ.params.len(), typ.resuls.len())
+		}
+		Instr::call_indirect(typ, _) => {
+			let typ = &wasm.types[*typ];
+			(typ.params.len() + 1, typ.resuls.len())
+		}
+		instr => {
+			let (params, results) = instr.signature().split_once('>').unwrap();
+			(params.split_whitespace().count(), results.split_whitespace().count())
+		}
+	}
+}
+
+impl Function {
+	/// Lowers a valid function body, leaving out code that cannot be reached.
+	pub fn cfg(&self, wasm: &Wasm) -> Cfg {
+		let mut lowering =
+			Lowering { wasm, blocks: vec![], labels: vec![], current: None, height: 0 };
+		let entry = lowering.block(0, 0);
+		let results = self.signature.resuls.len();
+		let exit = lowering.block(results, results);
+		lowering.blocks[exit].terminator = Terminator::Return;
+		lowering.labels.push(exit);
+		lowering.current = Some(entry);
+		lowering.body(&self.body);
+		lowering.terminate(Terminator::Jump(exit), None);
+		let mut cfg = Cfg { blocks: lowering.blocks };
+		cfg.prune();
+		cfg
+	}
+}
+
+impl Cfg {
+	fn prune(&mut self) {
+		let mut reachable = self.postorder();
+		reachable.sort();
+		let mut remap = vec![None; self.blocks.len()];
+		for (i, &block) in reachable.iter().enumerate() {
+			remap[block] = Some(i);
+		}
+		let mut blocks: Vec<_> = std::mem::take(&mut self.blocks).into_iter().map(Some).collect();
+		for &block in reachable.iter() {
+			let mut block = blocks[block].take().unwrap();
+			for target in block.terminator.successors_mut() {
+				*target = remap[*target].unwrap();
+			}
+			self.blocks.push(block);
+		}
+	}
+
+	pub fn successors(&self) -> Vec<Vec<usize>> {
+		self.blocks.iter().map(|block| block.terminator.successors()).collect()
+	}
+
+	pub fn predecessors(&self) -> Vec<Vec<usize>> {
+		predecessors(&self.successors())
+	}
+
+	/// The blocks reachable from the entry, each after all of its successors except those
+	/// reached through back edges.
+	pub fn postorder(&self) -> Vec<usize> {
+		postorder(&self.successors())
+	}
+
+	pub fn dominators(&self) -> Dominators {
+		dominators(&self.successors())
+	}
+
+	/// Finds the natural loops, outer loops first.
+	pub fn loops(&self, dominators: &Dominators) -> Vec<Loop> {
+		let predecessors = self.predecessors();
+		let mut loops: Vec<Loop> = vec![];
+		for (block, sources) in predecessors.iter().enumerate() {
+			let latches: Vec<_> =
+				sources.iter().filter(|&&source| dominators.dominates(block, source)).collect();
+			if latches.is_empty() {
+				continue;
+			}
+			let mut blocks = BTreeSet::from([block]);
+			let mut stack: Vec<usize> = latches.into_iter().copied().collect();
+			while let Some(next) = stack.pop() {
+				if blocks.insert(next) {
+					stack.extend(&predecessors[next]);
+				}
+			}
+			loops.push(Loop { header: block, blocks, parent: None, depth: 1 });
+		}
+		loops.sort_by_key(|other| std::cmp::Reverse(other.blocks.len()));
+		for i in 0..loops.len() {
+			let header = loops[i].header;
+			let parent = (0..i).rev().find(|&j| loops[j].blocks.contains(&header));
+			loops[i].parent = parent;
+			loops[i].depth = parent.map_or(1, |parent| loops[parent].depth + 1);
+		}
+		loops
+	}
+
+	pub fn to_dot(&self) -> String {
+		let mut dot = String::from("digraph cfg {\n\tnode [shape=box fontname=monospace];\n");
+		for (i, block) in self.blocks.iter().enumerate() {
+			let mut label = format!("b{}\\l", i);
+			for instr in block.body.iter() {
+				label += &format!("{}\\l", instr).replace('"', "\\\"");
+			}
+			match &block.terminator {
+				Terminator::Jump(_) => {}
+				Terminator::Branch(..) => label += "br_if\\l",
+				Terminator::Table(_) => label += "br_table\\l",
+				Terminator::Return => label += "return\\l",
+				Terminator::Unreachable => label += "unreachable\\l",
+			}
+			writeln!(dot, "\tb{} [label=\"{}\"];", i, label).unwrap();
+			let successors = block.terminator.successors();
+			for (j, target) in successors.iter().enumerate() {
+				let label = match &block.terminator {
+					Terminator::Branch(..) => ["true", "false"][j].to_string(),
+					Terminator::Table(_) if j + 1 == successors.len() => "default".to_string(),
+					Terminator::Table(_) => j.to_string(),
+					_ => String::new(),
+				};
+				match label.is_empty() {
+					true => writeln!(dot, "\tb{} -> b{};", i, target).unwrap(),
+					false => {
+						writeln!(dot, "\tb{} -> b{} [label=\"{}\"];", i, target, label).unwrap()
+					}
+				}
+			}
+		}
+		dot + "}\n"
+	}
+}
+
+pub(super) fn predecessors(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+	let mut predecessors = vec![vec![]; successors.len()];
+	for (i, targets) in successors.iter().enumerate() {
+		for &target in targets {
+			if !predecessors[target].contains(&i) {
+				predecessors[target].push(i);
+			}
+		}
+	}
+	predecessors
+}
+
+pub(super) fn postorder(successors: &[Vec<usize>]) -> Vec<usize> {
+	let mut order = vec![];
+	let mut visited = vec![false; successors.len()];
+	let mut stack = vec![(0, successors[0].clone())];
+	visited[0] = true;
+	while let Some((block, remaining)) = stack.last_mut() {
+		match remaining.pop() {
+			Some(next) if !visited[next] => {
+				visited[next] = true;
+				stack.push((next, successors[next].clone()));
+			}
+			Some(_) => {}
+			None => {
+				order.push(*block);
+				stack.pop();
+			}
+		}
+	}
+	order
+}
+
+pub(super) fn dominators(successors: &[Vec<usize>]) -> Dominators {
+	let mut order = postorder(successors);
+	let mut position = vec![0; successors.len()];
+	for (i, &block) in order.iter().enumerate() {
+		position[block] = i;
+	}
+	order.reverse();
+	let predecessors = predecessors(successors);
+	let mut immediate = vec![None; successors.len()];
+	immediate[0] = Some(0);
+	let mut changed = true;
+	while changed {
+		changed = false;
+		for &block in order.iter().skip(1) {
+			let mut dominator = None;
+			for &predecessor in predecessors[block].iter() {
+				if immediate[predecessor].is_none() {
+					continue;
+				}
+				dominator = Some(match dominator {
+					None => predecessor,
+					Some(mut other) => {
+						let mut predecessor = predecessor;
+						while predecessor != other {
+							while position[predecessor] < position[other] {
+								predecessor = immediate[predecessor].unwrap();
+							}
+							while position[other] < position[predecessor] {
+								other = immediate[other].unwrap();
+							}
+						}
+						other
+					}
+				});
+			}
+			if dominator != immediate[block] {
+				immediate[block] = dominator;
+				changed = true;
+			}
+		}
+	}
+	immediate[0] = None;
+	Dominators { immediate }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cfg(wat: &str) -> Cfg {
+		let wasm = Wasm::from_wat(wat).unwrap();
+		wasm.validate().unwrap();
+		wasm.functions[0].cfg(&wasm)
+	}
+
+	#[test]
+	fn lowers_structured_control_flow() {
+		let cfg = cfg(r#"
+			(module
+				(func (param i32) (result i32)
+					local.get 0
+					if (result i32)
+						i32.const 1
+					else
+						local.get 0
+						local.get 0
+						br_if 0
+						drop
+						i32.const 2
+						return
+					end
+					i32.const 3
+					i32.add))
+		"#);
+		let terminators: Vec<_> = cfg.blocks.iter().map(|block| block.terminator.clone()).collect();
+		use Terminator::*;
+		assert_eq!(
+			terminators,
+			vec![Branch(3, 4), Return, Jump(1), Jump(2), Branch(2, 5), Jump(1)]
+		);
+		let heights: Vec<_> = cfg.blocks.iter().map(|block| (block.height, block.arity)).collect();
+		assert_eq!(heights, vec![(0, 0), (1, 1), (1, 1), (0, 0), (0, 0), (1, 0)]);
+		assert_eq!(cfg.blocks[2].body, vec![Instr::i32_const(3), Instr::i32_add]);
+		let dominators = cfg.dominators();
+		assert_eq!(dominators.immediate, vec![None, Some(0), Some(0), Some(0), Some(0), Some(4)]);
+		assert!(dominators.dominates(4, 5) && !dominators.dominates(3, 2));
+		assert!(cfg.loops(&dominators).is_empty());
+	}
+
+	#[test]
+	fn finds_nested_loops() {
+		let cfg = cfg(r#"
+			(module
+				(func (param i32)
+					(local i32)
+					loop
+						i32.const 10
+						local.set 1
+						loop
+							local.get 1
+							i32.const 1
+							i32.sub
+							local.tee 1
+							br_if 0
+						end
+						local.get 0
+						i32.const 1
+						i32.sub
+						local.tee 0
+						br_if 0
+					end
+					unreachable
+					nop))
+		"#);
+		let dominators = cfg.dominators();
+		let loops = cfg.loops(&dominators);
+		let summary: Vec<_> = loops
+			.iter()
+			.map(|l| (l.header, l.blocks.iter().copied().collect::<Vec<_>>(), l.parent, l.depth))
+			.collect();
+		assert_eq!(summary, vec![(1, vec![1, 2, 3], None, 1), (2, vec![2], Some(0), 2)]);
+		assert_eq!(cfg.blocks.len(), 5);
+		assert_eq!(cfg.blocks[4].terminator, Terminator::Unreachable);
+
+		let dot = cfg.to_dot();
+		assert!(dot.starts_with("digraph cfg {\n"));
+		assert!(dot.contains("\tb2 -> b2 [label=\"true\"];\n"));
+		assert!(dot.contains("\tb0 -> b1;\n"));
+		assert!(dot.contains("[label=\"b4\\lunreachable\\l\"]"));
+	}
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::io::Read;
+
+use super::section_name;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ErrorKind {
+	UnexpectedEof,
+	BadMagic,
+	UnsupportedVersion(u32),
+	UnknownOpcode(String),
+	MalformedLeb128,
+	SectionSizeMismatch,
+	BodySizeMismatch,
+	InvalidTypeCode(u8),
+	Malformed(String),
+	Io(String),
+}
+
+impl fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ErrorKind::UnexpectedEof => write!(f, "Unexpected end of input"),
+			ErrorKind::BadMagic => write!(f, "Bad magic number"),
+			ErrorKind::UnsupportedVersion(version) => write!(f, "Unsupported version: {}", version),
+			ErrorKind::UnknownOpcode(opcode) => write!(f, "Unknown opcode: {}", opcode),
+			ErrorKind::MalformedLeb128 => write!(f, "LEB128 value is too long"),
+			ErrorKind::SectionSizeMismatch => write!(f, "Section size mismatch"),
+			ErrorKind::BodySizeMismatch => write!(f, "Function body size mismatch"),
+			ErrorKind::InvalidTypeCode(code) => write!(f, "Invalid type code: {:#04x}", code),
+			ErrorKind::Malformed(message) | ErrorKind::Io(message) => write!(f, "{}", message),
+		}
+	}
+}
+
+impl std::error::Error for ErrorKind {}
+
+impl From<ErrorKind> for std::io::Error {
+	fn from(kind: ErrorKind) -> std::io::Error {
+		std::io::Error::other(kind)
+	}
+}
+
+impl From<std::io::Error> for ErrorKind {
+	fn from(error: std::io::Error) -> ErrorKind {
+		let message = error.to_string();
+		match error.kind() {
+			std::io::ErrorKind::UnexpectedEof => ErrorKind::UnexpectedEof,
+			std::io::ErrorKind::Other => match error.into_inner().map(|inner| inner.downcast()) {
+				Some(Ok(kind)) => *kind,
+				_ => ErrorKind::Malformed(message),
+			},
+			_ => ErrorKind::Io(message),
+		}
+	}
+}
+
+#[derive(PartialEq)]
+pub struct DecodeError {
+	pub kind: ErrorKind,
+	pub offset: usize,
+	pub section: Option<u8>,
+	pub function: Option<usize>,
+}
+
+impl fmt::Display for DecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "offset {:#x}", self.offset)?;
+		if let Some(section) = self.section {
+			write!(f, ", {} section", section_name(section))?;
+		}
+		if let Some(function) = self.function {
+			write!(f, ", function {}", function)?;
+		}
+		write!(f, ": {}", self.kind)
+	}
+}
+
+impl fmt::Debug for DecodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+impl std::error::Error for DecodeError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.kind)
+	}
+}
+
+#[derive(Default)]
+pub(super) struct Location {
+	pub section: Option<u8>,
+	pub function: Option<usize>,
+}
+
+// Counts the bytes read so far, and where the last read started, which is where a value that
+// fails to decode begins unless it spans several reads.
+pub(super) struct Counter<'a, R> {
+	reader: &'a mut R,
+	offset: usize,
+	last: usize,
+}
+
+impl<'a, R: Read> Counter<'a, R> {
+	pub fn new(reader: &'a mut R) -> Self {
+		Counter { reader, offset: 0, last: 0 }
+	}
+
+	pub fn offset(&self) -> usize {
+		self.offset
+	}
+
+	pub fn error(&self, error: std::io::Error, at: Location) -> DecodeError {
+		let kind = ErrorKind::from(error);
+		let offset = match kind {
+			ErrorKind::UnexpectedEof
+			| ErrorKind::SectionSizeMismatch
+			| ErrorKind::BodySizeMismatch => self.offset,
+			_ => self.last,
+		};
+		DecodeError { kind, offset, section: at.section, function: at.function }
+	}
+}
+
+impl<R: Read> Read for Counter<'_, R> {
+	fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+		let count = self.reader.read(buffer)?;
+		self.last = self.offset;
+		self.offset += count;
+		Ok(count)
+	}
+}
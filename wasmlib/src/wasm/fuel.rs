@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use super::instruction::Instr;
+use super::{BlockType, DataType, FunctionType, Global, GlobalType, Wasm};
+
+/// The fuel charged per instruction, keyed by instruction name as in `instructions.tsv`, by
+/// class such as `f64.*` or `memory.*`, or `*` for everything else.
+#[derive(Clone)]
+pub struct Costs {
+	default: u64,
+	costs: HashMap<String, u64>,
+}
+
+impl Costs {
+	pub fn new(default: u64) -> Costs {
+		Costs { default, costs: HashMap::new() }
+	}
+
+	pub fn set(&mut self, key: &str, cost: u64) -> &mut Self {
+		match key {
+			"*" => self.default = cost,
+			_ => _ = self.costs.insert(key.to_string(), cost),
+		}
+		self
+	}
+
+	/// Reads one tab separated `key cost` pair per line, skipping blank lines and `#` comments.
+	pub fn from_tsv(text: &str) -> Result<Costs, String> {
+		let mut costs = Costs::new(1);
+		for (i, line) in text.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			let cost = match line.split_once('\t') {
+				Some((key, cost)) => cost.trim().parse().ok().map(|cost| (key.trim(), cost)),
+				None => None,
+			};
+			let Some((key, cost)) = cost else {
+				return Err(format!("Invalid cost on line {}: {}", i + 1, line));
+			};
+			if key != "*" && !key.ends_with(".*") && Instr::from_name(key).is_none() {
+				return Err(format!("Unknown instruction on line {}: {}", i + 1, key));
+			}
+			costs.set(key, cost);
+		}
+		Ok(costs)
+	}
+
+	pub fn cost(&self, instr: &Instr) -> u64 {
+		let name = instr.name();
+		let class = name.split_once('.').map(|(class, _)| format!("{}.*", class));
+		let cost = self.costs.get(name).or_else(|| self.costs.get(&class?));
+		*cost.unwrap_or(&self.default)
+	}
+}
+
+struct Meter<'a> {
+	costs: &'a Costs,
+	fuel: u32,
+	handler: usize,
+}
+
+impl Wasm {
+	/// Charges the exported `fuel` global, starting at `budget`, at the start of every basic
+	/// block, and calls the imported `env.out_of_fuel` before trapping once it drops below zero.
+	/// An existing `env.out_of_fuel` import is reused. The module must be valid beforehand,
+	/// since renumbering globals could otherwise make an invalid module valid.
+	pub fn meter(&mut self, costs: &Costs, budget: i64) -> Result<(), String> {
+		self.validate().map_err(|error| error.to_string())?;
+		if self.global_exports.contains_key("fuel") {
+			return Err("Duplicate export: fuel".to_string());
+		}
+		let signature = FunctionType { params: vec![], resuls: vec![] };
+		let existing = self
+			.imports
+			.iter()
+			.position(|(_, module, name)| module == "env" && name == "out_of_fuel");
+		let handler = match existing {
+			Some(index) if self.imports[index].0 == signature => index,
+			Some(_) => return Err("Incompatible import: env.out_of_fuel".to_string()),
+			None => {
+				let handler = self.imports.len();
+				self.renumber_functions(|index| if index >= handler { index + 1 } else { index });
+				self.imports.push((signature, "env".to_string(), "out_of_fuel".to_string()));
+				self.names.functions.insert(handler, "out_of_fuel".to_string());
+				handler
+			}
+		};
+		let fuel = self.global_imports.len() + self.globals.len();
+		let typ = GlobalType { typ: DataType::I64, mutable: true };
+		self.globals.push(Global { typ, init: vec![Instr::i64_const(budget)] });
+		self.global_exports.insert("fuel".to_string(), fuel);
+		let meter = Meter { costs, fuel: fuel as u32, handler };
+		for function in self.functions.iter_mut() {
+			function.body = meter.body(std::mem::take(&mut function.body));
+		}
+		Ok(())
+	}
+}
+
+impl Meter<'_> {
+	fn body(&self, body: Vec<Instr>) -> Vec<Instr> {
+		use Instr::*;
+		let mut result = vec![];
+		let mut segment = vec![];
+		let mut cost = 0;
+		for instr in body {
+			cost += self.costs.cost(&instr);
+			let (instr, ends) = match instr {
+				block(typ, body) => (block(typ, self.body(body)), true),
+				looped(typ, body) => (looped(typ, self.body(body)), true),
+				if_else(typ, then, otherwise) => {
+					(if_else(typ, self.body(then), self.body(otherwise)), true)
+				}
+				instr @ (br(_) | br_if(_) | br_table(_) | return_result) => (instr, true),
+				instr => (instr, false),
+			};
+			segment.push(instr);
+			if ends {
+				self.charge(&mut result, &mut segment, std::mem::take(&mut cost));
+			}
+		}
+		self.charge(&mut result, &mut segment, cost);
+		result
+	}
+
+	fn charge(&self, result: &mut Vec<Instr>, segment: &mut Vec<Instr>, cost: u64) {
+		use Instr::*;
+		if cost > 0 {
+			result.extend([
+				global_get(self.fuel),
+				i64_const(cost as i64),
+				i64_sub,
+				global_set(self.fuel),
+				global_get(self.fuel),
+				i64_const(0),
+				i64_lt_s,
+				if_else(BlockType::Empty, vec![call(self.handler), unreachable], vec![]),
+			]);
+		}
+		result.append(segment);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::interpreter::{Imports, Instance, Trap, Value};
+	use std::cell::Cell;
+	use std::rc::Rc;
+
+	const SUM: &str = r#"
+		(module
+			(func $add (param i32 i32) (result i32)
+				local.get 0
+				local.get 1
+				i32.add)
+			(func (export "sum") (param $n i32) (result i32) (local $total i32)
+				block
+					loop
+						local.get $n
+						i32.eqz
+						br_if 1
+						local.get $total
+						local.get $n
+						call $add
+						local.set $total
+						local.get $n
+						i32.const 1
+						i32.sub
+						local.set $n
+						br 0
+					end
+				end
+				local.get $total)
+			(elem declare func $add))
+	"#;
+
+	fn run(wasm: &Wasm, n: i32) -> (Result<Vec<Value>, Trap>, Option<Value>, bool) {
+		let called = Rc::new(Cell::new(false));
+		let flag = called.clone();
+		let mut imports = Imports::new();
+		imports.function("env", "out_of_fuel", &[], &[], move |_, _| {
+			flag.set(true);
+			Ok(vec![])
+		});
+		let mut instance = Instance::with_imports(wasm, imports).unwrap();
+		let result = instance.invoke("sum", &[Value::I32(n)]);
+		(result, instance.global("fuel"), called.get())
+	}
+
+	#[test]
+	fn reads_cost_tables() {
+		let costs = Costs::from_tsv("# costs\n*\t2\ncall\t10\ni32.*\t0\n\ni32.div_s\t8\n").unwrap();
+		assert_eq!(costs.cost(&Instr::call(0)), 10);
+		assert_eq!(costs.cost(&Instr::i32_add), 0);
+		assert_eq!(costs.cost(&Instr::i32_div_s), 8);
+		assert_eq!(costs.cost(&Instr::local_get(0)), 2);
+		assert_eq!(Costs::from_tsv("call 10").err().unwrap(), "Invalid cost on line 1: call 10");
+		let error = Costs::from_tsv("i32.plus\t1").err().unwrap();
+		assert_eq!(error, "Unknown instruction on line 1: i32.plus");
+	}
+
+	#[test]
+	fn meters_functions() {
+		let mut wasm = Wasm::from_wat(SUM).unwrap();
+		let mut costs = Costs::new(1);
+		costs.set("call", 5);
+		wasm.meter(&costs, 1000).unwrap();
+		wasm.validate().unwrap();
+		assert_eq!(wasm.imports.len(), 1);
+		assert_eq!(wasm.elements[0].functions, vec![1]);
+		assert_eq!(wasm.names.functions[&1], "add");
+		assert_eq!(wasm.functions[0].body[1], Instr::i64_const(3));
+
+		let (result, fuel, called) = run(&wasm, 10);
+		assert_eq!(result, Ok(vec![Value::I32(55)]));
+		assert_eq!(fuel, Some(Value::I64(1000 - 2 - 1 - 10 * (16 + 3) - 3)));
+		assert!(!called);
+		let (result, fuel, called) = run(&wasm, 100);
+		assert_eq!(result, Err(Trap::Unreachable));
+		assert!(matches!(fuel, Some(Value::I64(fuel)) if fuel < 0));
+		assert!(called);
+
+		assert_eq!(wasm.meter(&costs, 1000).err().unwrap(), "Duplicate export: fuel");
+		wasm.global_exports.clear();
+		wasm.meter(&costs, 1000).unwrap();
+		assert_eq!(wasm.imports.len(), 1);
+		let incompatible = r#"(module (import "env" "out_of_fuel" (func (param i32))))"#;
+		let error = Wasm::from_wat(incompatible).unwrap().meter(&costs, 1000).err().unwrap();
+		assert_eq!(error, "Incompatible import: env.out_of_fuel");
+
+		// Without validation, the fuel global would become the missing global 0.
+		let invalid = r#"(module (func i64.const 1000000 global.set 0))"#;
+		let error = Wasm::from_wat(invalid).unwrap().meter(&costs, 1000).err().unwrap();
+		assert!(error.starts_with("function 0"), "{}", error);
+	}
+}
@@ -1,16 +1,19 @@
-use std::io::{Error, Result};
+use std::io::Result;
 
+use super::error::ErrorKind;
 use super::leb128::{Reader, Writer};
-use super::DataType;
+use super::{read_vec, write_vec, BlockType, DataType};
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[allow(non_camel_case_types)]
 pub enum Instr {
 	unreachable,
 	nop,
-	block(Option<DataType>, Vec<Instr>),
-	looped(Option<DataType>, Vec<Instr>),
-	if_else(Option<DataType>, Vec<Instr>, Vec<Instr>),
+	block(BlockType, Vec<Instr>),
+	looped(BlockType, Vec<Instr>),
+	if_else(BlockType, Vec<Instr>, Vec<Instr>),
+	then_end,
+	end,
 	br(usize),
 	br_if(usize),
 	br_table(Vec<usize>),
@@ -19,11 +22,14 @@ pub enum Instr {
 	call_indirect(usize, usize),
 	drop,
 	select,
+	select_t(Vec<DataType>),
 	local_get(u32),
 	local_set(u32),
 	local_tee(u32),
 	global_get(u32),
 	global_set(u32),
+	table_get(usize),
+	table_set(usize),
 	i32_load(u32, u32),
 	i64_load(u32, u32),
 	f32_load(u32, u32),
@@ -181,6 +187,9 @@ pub enum Instr {
 	i64_extend8_s,
 	i64_extend16_s,
 	i64_extend32_s,
+	ref_null(DataType),
+	ref_is_null,
+	ref_func(usize),
 	i32_trunc_sat_f32_s,
 	i32_trunc_sat_f32_u,
 	i32_trunc_sat_f64_s,
@@ -189,23 +198,267 @@ pub enum Instr {
 	i64_trunc_sat_f32_u,
 	i64_trunc_sat_f64_s,
 	i64_trunc_sat_f64_u,
-	end,
-	then_end,
+	memory_init(usize, usize),
+	data_drop(usize),
+	memory_copy(usize, usize),
+	memory_fill(usize),
+	table_init(usize, usize),
+	elem_drop(usize),
+	table_copy(usize, usize),
+	table_grow(usize),
+	table_size(usize),
+	table_fill(usize),
+	v128_load(u32, u32),
+	v128_load8x8_s(u32, u32),
+	v128_load8x8_u(u32, u32),
+	v128_load16x4_s(u32, u32),
+	v128_load16x4_u(u32, u32),
+	v128_load32x2_s(u32, u32),
+	v128_load32x2_u(u32, u32),
+	v128_load8_splat(u32, u32),
+	v128_load16_splat(u32, u32),
+	v128_load32_splat(u32, u32),
+	v128_load64_splat(u32, u32),
+	v128_store(u32, u32),
+	v128_const(u128),
+	i8x16_shuffle([u8; 16]),
+	i8x16_swizzle,
+	i8x16_splat,
+	i16x8_splat,
+	i32x4_splat,
+	i64x2_splat,
+	f32x4_splat,
+	f64x2_splat,
+	i8x16_extract_lane_s(u8),
+	i8x16_extract_lane_u(u8),
+	i8x16_replace_lane(u8),
+	i16x8_extract_lane_s(u8),
+	i16x8_extract_lane_u(u8),
+	i16x8_replace_lane(u8),
+	i32x4_extract_lane(u8),
+	i32x4_replace_lane(u8),
+	i64x2_extract_lane(u8),
+	i64x2_replace_lane(u8),
+	f32x4_extract_lane(u8),
+	f32x4_replace_lane(u8),
+	f64x2_extract_lane(u8),
+	f64x2_replace_lane(u8),
+	i8x16_eq,
+	i8x16_ne,
+	i8x16_lt_s,
+	i8x16_lt_u,
+	i8x16_gt_s,
+	i8x16_gt_u,
+	i8x16_le_s,
+	i8x16_le_u,
+	i8x16_ge_s,
+	i8x16_ge_u,
+	i16x8_eq,
+	i16x8_ne,
+	i16x8_lt_s,
+	i16x8_lt_u,
+	i16x8_gt_s,
+	i16x8_gt_u,
+	i16x8_le_s,
+	i16x8_le_u,
+	i16x8_ge_s,
+	i16x8_ge_u,
+	i32x4_eq,
+	i32x4_ne,
+	i32x4_lt_s,
+	i32x4_lt_u,
+	i32x4_gt_s,
+	i32x4_gt_u,
+	i32x4_le_s,
+	i32x4_le_u,
+	i32x4_ge_s,
+	i32x4_ge_u,
+	f32x4_eq,
+	f32x4_ne,
+	f32x4_lt,
+	f32x4_gt,
+	f32x4_le,
+	f32x4_ge,
+	f64x2_eq,
+	f64x2_ne,
+	f64x2_lt,
+	f64x2_gt,
+	f64x2_le,
+	f64x2_ge,
+	v128_not,
+	v128_and,
+	v128_andnot,
+	v128_or,
+	v128_xor,
+	v128_bitselect,
+	v128_any_true,
+	v128_load8_lane(u32, u32, u8),
+	v128_load16_lane(u32, u32, u8),
+	v128_load32_lane(u32, u32, u8),
+	v128_load64_lane(u32, u32, u8),
+	v128_store8_lane(u32, u32, u8),
+	v128_store16_lane(u32, u32, u8),
+	v128_store32_lane(u32, u32, u8),
+	v128_store64_lane(u32, u32, u8),
+	v128_load32_zero(u32, u32),
+	v128_load64_zero(u32, u32),
+	f32x4_demote_f64x2_zero,
+	f64x2_promote_low_f32x4,
+	i8x16_abs,
+	i8x16_neg,
+	i8x16_popcnt,
+	i8x16_all_true,
+	i8x16_bitmask,
+	i8x16_narrow_i16x8_s,
+	i8x16_narrow_i16x8_u,
+	f32x4_ceil,
+	f32x4_floor,
+	f32x4_trunc,
+	f32x4_nearest,
+	i8x16_shl,
+	i8x16_shr_s,
+	i8x16_shr_u,
+	i8x16_add,
+	i8x16_add_sat_s,
+	i8x16_add_sat_u,
+	i8x16_sub,
+	i8x16_sub_sat_s,
+	i8x16_sub_sat_u,
+	f64x2_ceil,
+	f64x2_floor,
+	i8x16_min_s,
+	i8x16_min_u,
+	i8x16_max_s,
+	i8x16_max_u,
+	f64x2_trunc,
+	i8x16_avgr_u,
+	i16x8_extadd_pairwise_i8x16_s,
+	i16x8_extadd_pairwise_i8x16_u,
+	i32x4_extadd_pairwise_i16x8_s,
+	i32x4_extadd_pairwise_i16x8_u,
+	i16x8_abs,
+	i16x8_neg,
+	i16x8_q15mulr_sat_s,
+	i16x8_all_true,
+	i16x8_bitmask,
+	i16x8_narrow_i32x4_s,
+	i16x8_narrow_i32x4_u,
+	i16x8_extend_low_i8x16_s,
+	i16x8_extend_high_i8x16_s,
+	i16x8_extend_low_i8x16_u,
+	i16x8_extend_high_i8x16_u,
+	i16x8_shl,
+	i16x8_shr_s,
+	i16x8_shr_u,
+	i16x8_add,
+	i16x8_add_sat_s,
+	i16x8_add_sat_u,
+	i16x8_sub,
+	i16x8_sub_sat_s,
+	i16x8_sub_sat_u,
+	f64x2_nearest,
+	i16x8_mul,
+	i16x8_min_s,
+	i16x8_min_u,
+	i16x8_max_s,
+	i16x8_max_u,
+	i16x8_avgr_u,
+	i16x8_extmul_low_i8x16_s,
+	i16x8_extmul_high_i8x16_s,
+	i16x8_extmul_low_i8x16_u,
+	i16x8_extmul_high_i8x16_u,
+	i32x4_abs,
+	i32x4_neg,
+	i32x4_all_true,
+	i32x4_bitmask,
+	i32x4_extend_low_i16x8_s,
+	i32x4_extend_high_i16x8_s,
+	i32x4_extend_low_i16x8_u,
+	i32x4_extend_high_i16x8_u,
+	i32x4_shl,
+	i32x4_shr_s,
+	i32x4_shr_u,
+	i32x4_add,
+	i32x4_sub,
+	i32x4_mul,
+	i32x4_min_s,
+	i32x4_min_u,
+	i32x4_max_s,
+	i32x4_max_u,
+	i32x4_dot_i16x8_s,
+	i32x4_extmul_low_i16x8_s,
+	i32x4_extmul_high_i16x8_s,
+	i32x4_extmul_low_i16x8_u,
+	i32x4_extmul_high_i16x8_u,
+	i64x2_abs,
+	i64x2_neg,
+	i64x2_all_true,
+	i64x2_bitmask,
+	i64x2_extend_low_i32x4_s,
+	i64x2_extend_high_i32x4_s,
+	i64x2_extend_low_i32x4_u,
+	i64x2_extend_high_i32x4_u,
+	i64x2_shl,
+	i64x2_shr_s,
+	i64x2_shr_u,
+	i64x2_add,
+	i64x2_sub,
+	i64x2_mul,
+	i64x2_eq,
+	i64x2_ne,
+	i64x2_lt_s,
+	i64x2_gt_s,
+	i64x2_le_s,
+	i64x2_ge_s,
+	i64x2_extmul_low_i32x4_s,
+	i64x2_extmul_high_i32x4_s,
+	i64x2_extmul_low_i32x4_u,
+	i64x2_extmul_high_i32x4_u,
+	f32x4_abs,
+	f32x4_neg,
+	f32x4_sqrt,
+	f32x4_add,
+	f32x4_sub,
+	f32x4_mul,
+	f32x4_div,
+	f32x4_min,
+	f32x4_max,
+	f32x4_pmin,
+	f32x4_pmax,
+	f64x2_abs,
+	f64x2_neg,
+	f64x2_sqrt,
+	f64x2_add,
+	f64x2_sub,
+	f64x2_mul,
+	f64x2_div,
+	f64x2_min,
+	f64x2_max,
+	f64x2_pmin,
+	f64x2_pmax,
+	i32x4_trunc_sat_f32x4_s,
+	i32x4_trunc_sat_f32x4_u,
+	f32x4_convert_i32x4_s,
+	f32x4_convert_i32x4_u,
+	i32x4_trunc_sat_f64x2_s_zero,
+	i32x4_trunc_sat_f64x2_u_zero,
+	f64x2_convert_low_i32x4_s,
+	f64x2_convert_low_i32x4_u,
 }
 
 impl Instr {
 	pub fn read_from<R: std::io::Read>(reader: &mut R) -> Result<Self> {
-		let err = |x: u8| Err(Error::other(format!("Unsupported opcode: {}", x)));
+		let err = |x: String| Err(ErrorKind::UnknownOpcode(x).into());
 		use Instr::*;
 		Ok(match reader.u8()? {
 			0x00 => unreachable,
 			0x01 => nop,
-			0x02 => block(DataType::from(reader.u8()?), Instr::read_block_from(reader)?.0),
-			0x03 => looped(DataType::from(reader.u8()?), Instr::read_block_from(reader)?.0),
+			0x02 => block(BlockType::read_from(reader)?, Instr::read_block_from(reader)?.0),
+			0x03 => looped(BlockType::read_from(reader)?, Instr::read_block_from(reader)?.0),
 			0x04 => {
-				let typ = DataType::from(reader.u8()?);
+				let typ = BlockType::read_from(reader)?;
 				let blck = Instr::read_block_from(reader)?;
-				let otherwise = if blck.1 { vec![] } else { Instr::read_block_from(reader)?.0 };
+				let otherwise = if blck.1 { Instr::read_block_from(reader)?.0 } else { vec![] };
 				if_else(typ, blck.0, otherwise)
 			}
 			0x05 => then_end,
@@ -215,8 +468,8 @@ impl Instr {
 			0x0E => {
 				let size = reader.u32()? as usize;
 				let mut labels = vec![0usize; size + 1];
-				for i in 0..=size {
-					labels[i] = reader.u32()? as usize;
+				for label in labels.iter_mut() {
+					*label = reader.u32()? as usize;
 				}
 				br_table(labels)
 			}
@@ -225,11 +478,14 @@ impl Instr {
 			0x11 => call_indirect(reader.u32()? as usize, reader.u32()? as usize),
 			0x1A => drop,
 			0x1B => select,
+			0x1C => select_t(read_vec(reader, DataType::read_from)?),
 			0x20 => local_get(reader.u32()?),
 			0x21 => local_set(reader.u32()?),
 			0x22 => local_tee(reader.u32()?),
 			0x23 => global_get(reader.u32()?),
 			0x24 => global_set(reader.u32()?),
+			0x25 => table_get(reader.u32()? as usize),
+			0x26 => table_set(reader.u32()? as usize),
 			0x28 => i32_load(reader.u32()?, reader.u32()?),
 			0x29 => i64_load(reader.u32()?, reader.u32()?),
 			0x2A => f32_load(reader.u32()?, reader.u32()?),
@@ -253,13 +509,13 @@ impl Instr {
 			0x3C => i64_store8(reader.u32()?, reader.u32()?),
 			0x3D => i64_store16(reader.u32()?, reader.u32()?),
 			0x3E => i64_store32(reader.u32()?, reader.u32()?),
-			0x3F => match reader.u8()? {
+			0x3F => match reader.u32()? {
 				0x00 => memory_size,
-				unknown_opcode => return err(unknown_opcode),
+				unknown_opcode => return err(format!("0x3F {}", unknown_opcode)),
 			},
-			0x40 => match reader.u8()? {
+			0x40 => match reader.u32()? {
 				0x00 => memory_grow,
-				unknown_opcode => return err(unknown_opcode),
+				unknown_opcode => return err(format!("0x40 {}", unknown_opcode)),
 			},
 			0x41 => i32_const(reader.i32()?),
 			0x42 => i64_const(reader.i64()?),
@@ -393,7 +649,10 @@ impl Instr {
 			0xC2 => i64_extend8_s,
 			0xC3 => i64_extend16_s,
 			0xC4 => i64_extend32_s,
-			0xFC => match reader.u8()? {
+			0xD0 => ref_null(DataType::read_from(reader)?),
+			0xD1 => ref_is_null,
+			0xD2 => ref_func(reader.u32()? as usize),
+			0xFC => match reader.u32()? {
 				0x00 => i32_trunc_sat_f32_s,
 				0x01 => i32_trunc_sat_f32_u,
 				0x02 => i32_trunc_sat_f64_s,
@@ -402,9 +661,258 @@ impl Instr {
 				0x05 => i64_trunc_sat_f32_u,
 				0x06 => i64_trunc_sat_f64_s,
 				0x07 => i64_trunc_sat_f64_u,
-				unknown_opcode => return err(unknown_opcode),
+				0x08 => memory_init(reader.u32()? as usize, reader.u32()? as usize),
+				0x09 => data_drop(reader.u32()? as usize),
+				0x0A => memory_copy(reader.u32()? as usize, reader.u32()? as usize),
+				0x0B => memory_fill(reader.u32()? as usize),
+				0x0C => table_init(reader.u32()? as usize, reader.u32()? as usize),
+				0x0D => elem_drop(reader.u32()? as usize),
+				0x0E => table_copy(reader.u32()? as usize, reader.u32()? as usize),
+				0x0F => table_grow(reader.u32()? as usize),
+				0x10 => table_size(reader.u32()? as usize),
+				0x11 => table_fill(reader.u32()? as usize),
+				unknown_opcode => return err(format!("0xFC {}", unknown_opcode)),
+			},
+			0xFD => match reader.u32()? {
+				0x00 => v128_load(reader.u32()?, reader.u32()?),
+				0x01 => v128_load8x8_s(reader.u32()?, reader.u32()?),
+				0x02 => v128_load8x8_u(reader.u32()?, reader.u32()?),
+				0x03 => v128_load16x4_s(reader.u32()?, reader.u32()?),
+				0x04 => v128_load16x4_u(reader.u32()?, reader.u32()?),
+				0x05 => v128_load32x2_s(reader.u32()?, reader.u32()?),
+				0x06 => v128_load32x2_u(reader.u32()?, reader.u32()?),
+				0x07 => v128_load8_splat(reader.u32()?, reader.u32()?),
+				0x08 => v128_load16_splat(reader.u32()?, reader.u32()?),
+				0x09 => v128_load32_splat(reader.u32()?, reader.u32()?),
+				0x0A => v128_load64_splat(reader.u32()?, reader.u32()?),
+				0x0B => v128_store(reader.u32()?, reader.u32()?),
+				0x0C => v128_const(u128::from_le_bytes(reader.array()?)),
+				0x0D => i8x16_shuffle(reader.array()?),
+				0x0E => i8x16_swizzle,
+				0x0F => i8x16_splat,
+				0x10 => i16x8_splat,
+				0x11 => i32x4_splat,
+				0x12 => i64x2_splat,
+				0x13 => f32x4_splat,
+				0x14 => f64x2_splat,
+				0x15 => i8x16_extract_lane_s(reader.u8()?),
+				0x16 => i8x16_extract_lane_u(reader.u8()?),
+				0x17 => i8x16_replace_lane(reader.u8()?),
+				0x18 => i16x8_extract_lane_s(reader.u8()?),
+				0x19 => i16x8_extract_lane_u(reader.u8()?),
+				0x1A => i16x8_replace_lane(reader.u8()?),
+				0x1B => i32x4_extract_lane(reader.u8()?),
+				0x1C => i32x4_replace_lane(reader.u8()?),
+				0x1D => i64x2_extract_lane(reader.u8()?),
+				0x1E => i64x2_replace_lane(reader.u8()?),
+				0x1F => f32x4_extract_lane(reader.u8()?),
+				0x20 => f32x4_replace_lane(reader.u8()?),
+				0x21 => f64x2_extract_lane(reader.u8()?),
+				0x22 => f64x2_replace_lane(reader.u8()?),
+				0x23 => i8x16_eq,
+				0x24 => i8x16_ne,
+				0x25 => i8x16_lt_s,
+				0x26 => i8x16_lt_u,
+				0x27 => i8x16_gt_s,
+				0x28 => i8x16_gt_u,
+				0x29 => i8x16_le_s,
+				0x2A => i8x16_le_u,
+				0x2B => i8x16_ge_s,
+				0x2C => i8x16_ge_u,
+				0x2D => i16x8_eq,
+				0x2E => i16x8_ne,
+				0x2F => i16x8_lt_s,
+				0x30 => i16x8_lt_u,
+				0x31 => i16x8_gt_s,
+				0x32 => i16x8_gt_u,
+				0x33 => i16x8_le_s,
+				0x34 => i16x8_le_u,
+				0x35 => i16x8_ge_s,
+				0x36 => i16x8_ge_u,
+				0x37 => i32x4_eq,
+				0x38 => i32x4_ne,
+				0x39 => i32x4_lt_s,
+				0x3A => i32x4_lt_u,
+				0x3B => i32x4_gt_s,
+				0x3C => i32x4_gt_u,
+				0x3D => i32x4_le_s,
+				0x3E => i32x4_le_u,
+				0x3F => i32x4_ge_s,
+				0x40 => i32x4_ge_u,
+				0x41 => f32x4_eq,
+				0x42 => f32x4_ne,
+				0x43 => f32x4_lt,
+				0x44 => f32x4_gt,
+				0x45 => f32x4_le,
+				0x46 => f32x4_ge,
+				0x47 => f64x2_eq,
+				0x48 => f64x2_ne,
+				0x49 => f64x2_lt,
+				0x4A => f64x2_gt,
+				0x4B => f64x2_le,
+				0x4C => f64x2_ge,
+				0x4D => v128_not,
+				0x4E => v128_and,
+				0x4F => v128_andnot,
+				0x50 => v128_or,
+				0x51 => v128_xor,
+				0x52 => v128_bitselect,
+				0x53 => v128_any_true,
+				0x54 => v128_load8_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x55 => v128_load16_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x56 => v128_load32_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x57 => v128_load64_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x58 => v128_store8_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x59 => v128_store16_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x5A => v128_store32_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x5B => v128_store64_lane(reader.u32()?, reader.u32()?, reader.u8()?),
+				0x5C => v128_load32_zero(reader.u32()?, reader.u32()?),
+				0x5D => v128_load64_zero(reader.u32()?, reader.u32()?),
+				0x5E => f32x4_demote_f64x2_zero,
+				0x5F => f64x2_promote_low_f32x4,
+				0x60 => i8x16_abs,
+				0x61 => i8x16_neg,
+				0x62 => i8x16_popcnt,
+				0x63 => i8x16_all_true,
+				0x64 => i8x16_bitmask,
+				0x65 => i8x16_narrow_i16x8_s,
+				0x66 => i8x16_narrow_i16x8_u,
+				0x67 => f32x4_ceil,
+				0x68 => f32x4_floor,
+				0x69 => f32x4_trunc,
+				0x6A => f32x4_nearest,
+				0x6B => i8x16_shl,
+				0x6C => i8x16_shr_s,
+				0x6D => i8x16_shr_u,
+				0x6E => i8x16_add,
+				0x6F => i8x16_add_sat_s,
+				0x70 => i8x16_add_sat_u,
+				0x71 => i8x16_sub,
+				0x72 => i8x16_sub_sat_s,
+				0x73 => i8x16_sub_sat_u,
+				0x74 => f64x2_ceil,
+				0x75 => f64x2_floor,
+				0x76 => i8x16_min_s,
+				0x77 => i8x16_min_u,
+				0x78 => i8x16_max_s,
+				0x79 => i8x16_max_u,
+				0x7A => f64x2_trunc,
+				0x7B => i8x16_avgr_u,
+				0x7C => i16x8_extadd_pairwise_i8x16_s,
+				0x7D => i16x8_extadd_pairwise_i8x16_u,
+				0x7E => i32x4_extadd_pairwise_i16x8_s,
+				0x7F => i32x4_extadd_pairwise_i16x8_u,
+				0x80 => i16x8_abs,
+				0x81 => i16x8_neg,
+				0x82 => i16x8_q15mulr_sat_s,
+				0x83 => i16x8_all_true,
+				0x84 => i16x8_bitmask,
+				0x85 => i16x8_narrow_i32x4_s,
+				0x86 => i16x8_narrow_i32x4_u,
+				0x87 => i16x8_extend_low_i8x16_s,
+				0x88 => i16x8_extend_high_i8x16_s,
+				0x89 => i16x8_extend_low_i8x16_u,
+				0x8A => i16x8_extend_high_i8x16_u,
+				0x8B => i16x8_shl,
+				0x8C => i16x8_shr_s,
+				0x8D => i16x8_shr_u,
+				0x8E => i16x8_add,
+				0x8F => i16x8_add_sat_s,
+				0x90 => i16x8_add_sat_u,
+				0x91 => i16x8_sub,
+				0x92 => i16x8_sub_sat_s,
+				0x93 => i16x8_sub_sat_u,
+				0x94 => f64x2_nearest,
+				0x95 => i16x8_mul,
+				0x96 => i16x8_min_s,
+				0x97 => i16x8_min_u,
+				0x98 => i16x8_max_s,
+				0x99 => i16x8_max_u,
+				0x9B => i16x8_avgr_u,
+				0x9C => i16x8_extmul_low_i8x16_s,
+				0x9D => i16x8_extmul_high_i8x16_s,
+				0x9E => i16x8_extmul_low_i8x16_u,
+				0x9F => i16x8_extmul_high_i8x16_u,
+				0xA0 => i32x4_abs,
+				0xA1 => i32x4_neg,
+				0xA3 => i32x4_all_true,
+				0xA4 => i32x4_bitmask,
+				0xA7 => i32x4_extend_low_i16x8_s,
+				0xA8 => i32x4_extend_high_i16x8_s,
+				0xA9 => i32x4_extend_low_i16x8_u,
+				0xAA => i32x4_extend_high_i16x8_u,
+				0xAB => i32x4_shl,
+				0xAC => i32x4_shr_s,
+				0xAD => i32x4_shr_u,
+				0xAE => i32x4_add,
+				0xB1 => i32x4_sub,
+				0xB5 => i32x4_mul,
+				0xB6 => i32x4_min_s,
+				0xB7 => i32x4_min_u,
+				0xB8 => i32x4_max_s,
+				0xB9 => i32x4_max_u,
+				0xBA => i32x4_dot_i16x8_s,
+				0xBC => i32x4_extmul_low_i16x8_s,
+				0xBD => i32x4_extmul_high_i16x8_s,
+				0xBE => i32x4_extmul_low_i16x8_u,
+				0xBF => i32x4_extmul_high_i16x8_u,
+				0xC0 => i64x2_abs,
+				0xC1 => i64x2_neg,
+				0xC3 => i64x2_all_true,
+				0xC4 => i64x2_bitmask,
+				0xC7 => i64x2_extend_low_i32x4_s,
+				0xC8 => i64x2_extend_high_i32x4_s,
+				0xC9 => i64x2_extend_low_i32x4_u,
+				0xCA => i64x2_extend_high_i32x4_u,
+				0xCB => i64x2_shl,
+				0xCC => i64x2_shr_s,
+				0xCD => i64x2_shr_u,
+				0xCE => i64x2_add,
+				0xD1 => i64x2_sub,
+				0xD5 => i64x2_mul,
+				0xD6 => i64x2_eq,
+				0xD7 => i64x2_ne,
+				0xD8 => i64x2_lt_s,
+				0xD9 => i64x2_gt_s,
+				0xDA => i64x2_le_s,
+				0xDB => i64x2_ge_s,
+				0xDC => i64x2_extmul_low_i32x4_s,
+				0xDD => i64x2_extmul_high_i32x4_s,
+				0xDE => i64x2_extmul_low_i32x4_u,
+				0xDF => i64x2_extmul_high_i32x4_u,
+				0xE0 => f32x4_abs,
+				0xE1 => f32x4_neg,
+				0xE3 => f32x4_sqrt,
+				0xE4 => f32x4_add,
+				0xE5 => f32x4_sub,
+				0xE6 => f32x4_mul,
+				0xE7 => f32x4_div,
+				0xE8 => f32x4_min,
+				0xE9 => f32x4_max,
+				0xEA => f32x4_pmin,
+				0xEB => f32x4_pmax,
+				0xEC => f64x2_abs,
+				0xED => f64x2_neg,
+				0xEF => f64x2_sqrt,
+				0xF0 => f64x2_add,
+				0xF1 => f64x2_sub,
+				0xF2 => f64x2_mul,
+				0xF3 => f64x2_div,
+				0xF4 => f64x2_min,
+				0xF5 => f64x2_max,
+				0xF6 => f64x2_pmin,
+				0xF7 => f64x2_pmax,
+				0xF8 => i32x4_trunc_sat_f32x4_s,
+				0xF9 => i32x4_trunc_sat_f32x4_u,
+				0xFA => f32x4_convert_i32x4_s,
+				0xFB => f32x4_convert_i32x4_u,
+				0xFC => i32x4_trunc_sat_f64x2_s_zero,
+				0xFD => i32x4_trunc_sat_f64x2_u_zero,
+				0xFE => f64x2_convert_low_i32x4_s,
+				0xFF => f64x2_convert_low_i32x4_u,
+				unknown_opcode => return err(format!("0xFD {}", unknown_opcode)),
 			},
-			unknown_opcode => return err(unknown_opcode),
+			unknown_opcode => return err(format!("{:#04X}", unknown_opcode)),
 		})
 	}
 
@@ -418,4 +926,2892 @@ impl Instr {
 			}
 		}
 	}
+
+	pub fn write_to<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+		use Instr::*;
+		match self {
+			unreachable => writer.u8(0x00)?,
+			nop => writer.u8(0x01)?,
+			block(x0, x1) => {
+				writer.u8(0x02)?;
+				x0.write_to(writer)?;
+				Instr::write_block_to(x1, writer)?;
+			}
+			looped(x0, x1) => {
+				writer.u8(0x03)?;
+				x0.write_to(writer)?;
+				Instr::write_block_to(x1, writer)?;
+			}
+			if_else(typ, then, otherwise) => {
+				writer.u8(0x04)?;
+				typ.write_to(writer)?;
+				for instr in then {
+					instr.write_to(writer)?;
+				}
+				if !otherwise.is_empty() {
+					writer.u8(0x05)?;
+					for instr in otherwise {
+						instr.write_to(writer)?;
+					}
+				}
+				writer.u8(0x0B)?;
+			}
+			then_end => writer.u8(0x05)?,
+			end => writer.u8(0x0B)?,
+			br(x0) => {
+				writer.u8(0x0C)?;
+				writer.u32(*x0 as u32)?;
+			}
+			br_if(x0) => {
+				writer.u8(0x0D)?;
+				writer.u32(*x0 as u32)?;
+			}
+			br_table(labels) => {
+				writer.u8(0x0E)?;
+				let Some(count) = labels.len().checked_sub(1) else {
+					return Err(std::io::Error::other("br_table without default label"));
+				};
+				writer.u32(count as u32)?;
+				for &label in labels {
+					writer.u32(label as u32)?;
+				}
+			}
+			return_result => writer.u8(0x0F)?,
+			call(x0) => {
+				writer.u8(0x10)?;
+				writer.u32(*x0 as u32)?;
+			}
+			call_indirect(x0, x1) => {
+				writer.u8(0x11)?;
+				writer.u32(*x0 as u32)?;
+				writer.u32(*x1 as u32)?;
+			}
+			drop => writer.u8(0x1A)?,
+			select => writer.u8(0x1B)?,
+			select_t(x0) => {
+				writer.u8(0x1C)?;
+				write_vec(writer, x0, |writer, typ| writer.u8(typ.code()))?;
+			}
+			local_get(x0) => {
+				writer.u8(0x20)?;
+				writer.u32(*x0)?;
+			}
+			local_set(x0) => {
+				writer.u8(0x21)?;
+				writer.u32(*x0)?;
+			}
+			local_tee(x0) => {
+				writer.u8(0x22)?;
+				writer.u32(*x0)?;
+			}
+			global_get(x0) => {
+				writer.u8(0x23)?;
+				writer.u32(*x0)?;
+			}
+			global_set(x0) => {
+				writer.u8(0x24)?;
+				writer.u32(*x0)?;
+			}
+			table_get(x0) => {
+				writer.u8(0x25)?;
+				writer.u32(*x0 as u32)?;
+			}
+			table_set(x0) => {
+				writer.u8(0x26)?;
+				writer.u32(*x0 as u32)?;
+			}
+			i32_load(x0, x1) => {
+				writer.u8(0x28)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_load(x0, x1) => {
+				writer.u8(0x29)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			f32_load(x0, x1) => {
+				writer.u8(0x2A)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			f64_load(x0, x1) => {
+				writer.u8(0x2B)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i32_load8_s(x0, x1) => {
+				writer.u8(0x2C)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i32_load8_u(x0, x1) => {
+				writer.u8(0x2D)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i32_load16_s(x0, x1) => {
+				writer.u8(0x2E)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i32_load16_u(x0, x1) => {
+				writer.u8(0x2F)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_load8_s(x0, x1) => {
+				writer.u8(0x30)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_load8_u(x0, x1) => {
+				writer.u8(0x31)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_load16_s(x0, x1) => {
+				writer.u8(0x32)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_load16_u(x0, x1) => {
+				writer.u8(0x33)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_load32_s(x0, x1) => {
+				writer.u8(0x34)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_load32_u(x0, x1) => {
+				writer.u8(0x35)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i32_store(x0, x1) => {
+				writer.u8(0x36)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_store(x0, x1) => {
+				writer.u8(0x37)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			f32_store(x0, x1) => {
+				writer.u8(0x38)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			f64_store(x0, x1) => {
+				writer.u8(0x39)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i32_store8(x0, x1) => {
+				writer.u8(0x3A)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i32_store16(x0, x1) => {
+				writer.u8(0x3B)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_store8(x0, x1) => {
+				writer.u8(0x3C)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_store16(x0, x1) => {
+				writer.u8(0x3D)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			i64_store32(x0, x1) => {
+				writer.u8(0x3E)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			memory_size => {
+				writer.u8(0x3F)?;
+				writer.u32(0x00)?;
+			}
+			memory_grow => {
+				writer.u8(0x40)?;
+				writer.u32(0x00)?;
+			}
+			i32_const(x0) => {
+				writer.u8(0x41)?;
+				writer.i32(*x0)?;
+			}
+			i64_const(x0) => {
+				writer.u8(0x42)?;
+				writer.i64(*x0)?;
+			}
+			f32_const(x0) => {
+				writer.u8(0x43)?;
+				writer.f32(*x0)?;
+			}
+			f64_const(x0) => {
+				writer.u8(0x44)?;
+				writer.f64(*x0)?;
+			}
+			i32_eqz => writer.u8(0x45)?,
+			i32_eq => writer.u8(0x46)?,
+			i32_ne => writer.u8(0x47)?,
+			i32_lt_s => writer.u8(0x48)?,
+			i32_lt_u => writer.u8(0x49)?,
+			i32_gt_s => writer.u8(0x4A)?,
+			i32_gt_u => writer.u8(0x4B)?,
+			i32_le_s => writer.u8(0x4C)?,
+			i32_le_u => writer.u8(0x4D)?,
+			i32_ge_s => writer.u8(0x4E)?,
+			i32_ge_u => writer.u8(0x4F)?,
+			i64_eqz => writer.u8(0x50)?,
+			i64_eq => writer.u8(0x51)?,
+			i64_ne => writer.u8(0x52)?,
+			i64_lt_s => writer.u8(0x53)?,
+			i64_lt_u => writer.u8(0x54)?,
+			i64_gt_s => writer.u8(0x55)?,
+			i64_gt_u => writer.u8(0x56)?,
+			i64_le_s => writer.u8(0x57)?,
+			i64_le_u => writer.u8(0x58)?,
+			i64_ge_s => writer.u8(0x59)?,
+			i64_ge_u => writer.u8(0x5A)?,
+			f32_eq => writer.u8(0x5B)?,
+			f32_ne => writer.u8(0x5C)?,
+			f32_lt => writer.u8(0x5D)?,
+			f32_gt => writer.u8(0x5E)?,
+			f32_le => writer.u8(0x5F)?,
+			f32_ge => writer.u8(0x60)?,
+			f64_eq => writer.u8(0x61)?,
+			f64_ne => writer.u8(0x62)?,
+			f64_lt => writer.u8(0x63)?,
+			f64_gt => writer.u8(0x64)?,
+			f64_le => writer.u8(0x65)?,
+			f64_ge => writer.u8(0x66)?,
+			i32_clz => writer.u8(0x67)?,
+			i32_ctz => writer.u8(0x68)?,
+			i32_popcnt => writer.u8(0x69)?,
+			i32_add => writer.u8(0x6A)?,
+			i32_sub => writer.u8(0x6B)?,
+			i32_mul => writer.u8(0x6C)?,
+			i32_div_s => writer.u8(0x6D)?,
+			i32_div_u => writer.u8(0x6E)?,
+			i32_rem_s => writer.u8(0x6F)?,
+			i32_rem_u => writer.u8(0x70)?,
+			i32_and => writer.u8(0x71)?,
+			i32_or => writer.u8(0x72)?,
+			i32_xor => writer.u8(0x73)?,
+			i32_shl => writer.u8(0x74)?,
+			i32_shr_s => writer.u8(0x75)?,
+			i32_shr_u => writer.u8(0x76)?,
+			i32_rotl => writer.u8(0x77)?,
+			i32_rotr => writer.u8(0x78)?,
+			i64_clz => writer.u8(0x79)?,
+			i64_ctz => writer.u8(0x7A)?,
+			i64_popcnt => writer.u8(0x7B)?,
+			i64_add => writer.u8(0x7C)?,
+			i64_sub => writer.u8(0x7D)?,
+			i64_mul => writer.u8(0x7E)?,
+			i64_div_s => writer.u8(0x7F)?,
+			i64_div_u => writer.u8(0x80)?,
+			i64_rem_s => writer.u8(0x81)?,
+			i64_rem_u => writer.u8(0x82)?,
+			i64_and => writer.u8(0x83)?,
+			i64_or => writer.u8(0x84)?,
+			i64_xor => writer.u8(0x85)?,
+			i64_shl => writer.u8(0x86)?,
+			i64_shr_s => writer.u8(0x87)?,
+			i64_shr_u => writer.u8(0x88)?,
+			i64_rotl => writer.u8(0x89)?,
+			i64_rotr => writer.u8(0x8A)?,
+			f32_abs => writer.u8(0x8B)?,
+			f32_neg => writer.u8(0x8C)?,
+			f32_ceil => writer.u8(0x8D)?,
+			f32_floor => writer.u8(0x8E)?,
+			f32_trunc => writer.u8(0x8F)?,
+			f32_nearest => writer.u8(0x90)?,
+			f32_sqrt => writer.u8(0x91)?,
+			f32_add => writer.u8(0x92)?,
+			f32_sub => writer.u8(0x93)?,
+			f32_mul => writer.u8(0x94)?,
+			f32_div => writer.u8(0x95)?,
+			f32_min => writer.u8(0x96)?,
+			f32_max => writer.u8(0x97)?,
+			f32_copysign => writer.u8(0x98)?,
+			f64_abs => writer.u8(0x99)?,
+			f64_neg => writer.u8(0x9A)?,
+			f64_ceil => writer.u8(0x9B)?,
+			f64_floor => writer.u8(0x9C)?,
+			f64_trunc => writer.u8(0x9D)?,
+			f64_nearest => writer.u8(0x9E)?,
+			f64_sqrt => writer.u8(0x9F)?,
+			f64_add => writer.u8(0xA0)?,
+			f64_sub => writer.u8(0xA1)?,
+			f64_mul => writer.u8(0xA2)?,
+			f64_div => writer.u8(0xA3)?,
+			f64_min => writer.u8(0xA4)?,
+			f64_max => writer.u8(0xA5)?,
+			f64_copysign => writer.u8(0xA6)?,
+			i32_wrap_i64 => writer.u8(0xA7)?,
+			i32_trunc_f32_s => writer.u8(0xA8)?,
+			i32_trunc_f32_u => writer.u8(0xA9)?,
+			i32_trunc_f64_s => writer.u8(0xAA)?,
+			i32_trunc_f64_u => writer.u8(0xAB)?,
+			i64_extend_i32_s => writer.u8(0xAC)?,
+			i64_extend_i32_u => writer.u8(0xAD)?,
+			i64_trunc_f32_s => writer.u8(0xAE)?,
+			i64_trunc_f32_u => writer.u8(0xAF)?,
+			i64_trunc_f64_s => writer.u8(0xB0)?,
+			i64_trunc_f64_u => writer.u8(0xB1)?,
+			f32_convert_i32_s => writer.u8(0xB2)?,
+			f32_convert_i32_u => writer.u8(0xB3)?,
+			f32_convert_i64_s => writer.u8(0xB4)?,
+			f32_convert_i64_u => writer.u8(0xB5)?,
+			f32_demote_f64 => writer.u8(0xB6)?,
+			f64_convert_i32_s => writer.u8(0xB7)?,
+			f64_convert_i32_u => writer.u8(0xB8)?,
+			f64_convert_i64_s => writer.u8(0xB9)?,
+			f64_convert_i64_u => writer.u8(0xBA)?,
+			f64_promote_f32 => writer.u8(0xBB)?,
+			i32_reinterpret_f32 => writer.u8(0xBC)?,
+			i64_reinterpret_f64 => writer.u8(0xBD)?,
+			f32_reinterpret_i32 => writer.u8(0xBE)?,
+			f64_reinterpret_i64 => writer.u8(0xBF)?,
+			i32_extend8_s => writer.u8(0xC0)?,
+			i32_extend16_s => writer.u8(0xC1)?,
+			i64_extend8_s => writer.u8(0xC2)?,
+			i64_extend16_s => writer.u8(0xC3)?,
+			i64_extend32_s => writer.u8(0xC4)?,
+			ref_null(x0) => {
+				writer.u8(0xD0)?;
+				writer.u8(x0.code())?;
+			}
+			ref_is_null => writer.u8(0xD1)?,
+			ref_func(x0) => {
+				writer.u8(0xD2)?;
+				writer.u32(*x0 as u32)?;
+			}
+			i32_trunc_sat_f32_s => {
+				writer.u8(0xFC)?;
+				writer.u32(0x00)?;
+			}
+			i32_trunc_sat_f32_u => {
+				writer.u8(0xFC)?;
+				writer.u32(0x01)?;
+			}
+			i32_trunc_sat_f64_s => {
+				writer.u8(0xFC)?;
+				writer.u32(0x02)?;
+			}
+			i32_trunc_sat_f64_u => {
+				writer.u8(0xFC)?;
+				writer.u32(0x03)?;
+			}
+			i64_trunc_sat_f32_s => {
+				writer.u8(0xFC)?;
+				writer.u32(0x04)?;
+			}
+			i64_trunc_sat_f32_u => {
+				writer.u8(0xFC)?;
+				writer.u32(0x05)?;
+			}
+			i64_trunc_sat_f64_s => {
+				writer.u8(0xFC)?;
+				writer.u32(0x06)?;
+			}
+			i64_trunc_sat_f64_u => {
+				writer.u8(0xFC)?;
+				writer.u32(0x07)?;
+			}
+			memory_init(x0, x1) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x08)?;
+				writer.u32(*x0 as u32)?;
+				writer.u32(*x1 as u32)?;
+			}
+			data_drop(x0) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x09)?;
+				writer.u32(*x0 as u32)?;
+			}
+			memory_copy(x0, x1) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x0A)?;
+				writer.u32(*x0 as u32)?;
+				writer.u32(*x1 as u32)?;
+			}
+			memory_fill(x0) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x0B)?;
+				writer.u32(*x0 as u32)?;
+			}
+			table_init(x0, x1) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x0C)?;
+				writer.u32(*x0 as u32)?;
+				writer.u32(*x1 as u32)?;
+			}
+			elem_drop(x0) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x0D)?;
+				writer.u32(*x0 as u32)?;
+			}
+			table_copy(x0, x1) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x0E)?;
+				writer.u32(*x0 as u32)?;
+				writer.u32(*x1 as u32)?;
+			}
+			table_grow(x0) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x0F)?;
+				writer.u32(*x0 as u32)?;
+			}
+			table_size(x0) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x10)?;
+				writer.u32(*x0 as u32)?;
+			}
+			table_fill(x0) => {
+				writer.u8(0xFC)?;
+				writer.u32(0x11)?;
+				writer.u32(*x0 as u32)?;
+			}
+			v128_load(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x00)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load8x8_s(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x01)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load8x8_u(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x02)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load16x4_s(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x03)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load16x4_u(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x04)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load32x2_s(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x05)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load32x2_u(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x06)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load8_splat(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x07)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load16_splat(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x08)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load32_splat(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x09)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load64_splat(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x0A)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_store(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x0B)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_const(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x0C)?;
+				writer.array(&x0.to_le_bytes())?;
+			}
+			i8x16_shuffle(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x0D)?;
+				writer.array(x0)?;
+			}
+			i8x16_swizzle => {
+				writer.u8(0xFD)?;
+				writer.u32(0x0E)?;
+			}
+			i8x16_splat => {
+				writer.u8(0xFD)?;
+				writer.u32(0x0F)?;
+			}
+			i16x8_splat => {
+				writer.u8(0xFD)?;
+				writer.u32(0x10)?;
+			}
+			i32x4_splat => {
+				writer.u8(0xFD)?;
+				writer.u32(0x11)?;
+			}
+			i64x2_splat => {
+				writer.u8(0xFD)?;
+				writer.u32(0x12)?;
+			}
+			f32x4_splat => {
+				writer.u8(0xFD)?;
+				writer.u32(0x13)?;
+			}
+			f64x2_splat => {
+				writer.u8(0xFD)?;
+				writer.u32(0x14)?;
+			}
+			i8x16_extract_lane_s(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x15)?;
+				writer.u8(*x0)?;
+			}
+			i8x16_extract_lane_u(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x16)?;
+				writer.u8(*x0)?;
+			}
+			i8x16_replace_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x17)?;
+				writer.u8(*x0)?;
+			}
+			i16x8_extract_lane_s(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x18)?;
+				writer.u8(*x0)?;
+			}
+			i16x8_extract_lane_u(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x19)?;
+				writer.u8(*x0)?;
+			}
+			i16x8_replace_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x1A)?;
+				writer.u8(*x0)?;
+			}
+			i32x4_extract_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x1B)?;
+				writer.u8(*x0)?;
+			}
+			i32x4_replace_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x1C)?;
+				writer.u8(*x0)?;
+			}
+			i64x2_extract_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x1D)?;
+				writer.u8(*x0)?;
+			}
+			i64x2_replace_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x1E)?;
+				writer.u8(*x0)?;
+			}
+			f32x4_extract_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x1F)?;
+				writer.u8(*x0)?;
+			}
+			f32x4_replace_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x20)?;
+				writer.u8(*x0)?;
+			}
+			f64x2_extract_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x21)?;
+				writer.u8(*x0)?;
+			}
+			f64x2_replace_lane(x0) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x22)?;
+				writer.u8(*x0)?;
+			}
+			i8x16_eq => {
+				writer.u8(0xFD)?;
+				writer.u32(0x23)?;
+			}
+			i8x16_ne => {
+				writer.u8(0xFD)?;
+				writer.u32(0x24)?;
+			}
+			i8x16_lt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x25)?;
+			}
+			i8x16_lt_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x26)?;
+			}
+			i8x16_gt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x27)?;
+			}
+			i8x16_gt_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x28)?;
+			}
+			i8x16_le_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x29)?;
+			}
+			i8x16_le_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x2A)?;
+			}
+			i8x16_ge_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x2B)?;
+			}
+			i8x16_ge_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x2C)?;
+			}
+			i16x8_eq => {
+				writer.u8(0xFD)?;
+				writer.u32(0x2D)?;
+			}
+			i16x8_ne => {
+				writer.u8(0xFD)?;
+				writer.u32(0x2E)?;
+			}
+			i16x8_lt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x2F)?;
+			}
+			i16x8_lt_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x30)?;
+			}
+			i16x8_gt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x31)?;
+			}
+			i16x8_gt_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x32)?;
+			}
+			i16x8_le_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x33)?;
+			}
+			i16x8_le_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x34)?;
+			}
+			i16x8_ge_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x35)?;
+			}
+			i16x8_ge_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x36)?;
+			}
+			i32x4_eq => {
+				writer.u8(0xFD)?;
+				writer.u32(0x37)?;
+			}
+			i32x4_ne => {
+				writer.u8(0xFD)?;
+				writer.u32(0x38)?;
+			}
+			i32x4_lt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x39)?;
+			}
+			i32x4_lt_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x3A)?;
+			}
+			i32x4_gt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x3B)?;
+			}
+			i32x4_gt_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x3C)?;
+			}
+			i32x4_le_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x3D)?;
+			}
+			i32x4_le_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x3E)?;
+			}
+			i32x4_ge_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x3F)?;
+			}
+			i32x4_ge_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x40)?;
+			}
+			f32x4_eq => {
+				writer.u8(0xFD)?;
+				writer.u32(0x41)?;
+			}
+			f32x4_ne => {
+				writer.u8(0xFD)?;
+				writer.u32(0x42)?;
+			}
+			f32x4_lt => {
+				writer.u8(0xFD)?;
+				writer.u32(0x43)?;
+			}
+			f32x4_gt => {
+				writer.u8(0xFD)?;
+				writer.u32(0x44)?;
+			}
+			f32x4_le => {
+				writer.u8(0xFD)?;
+				writer.u32(0x45)?;
+			}
+			f32x4_ge => {
+				writer.u8(0xFD)?;
+				writer.u32(0x46)?;
+			}
+			f64x2_eq => {
+				writer.u8(0xFD)?;
+				writer.u32(0x47)?;
+			}
+			f64x2_ne => {
+				writer.u8(0xFD)?;
+				writer.u32(0x48)?;
+			}
+			f64x2_lt => {
+				writer.u8(0xFD)?;
+				writer.u32(0x49)?;
+			}
+			f64x2_gt => {
+				writer.u8(0xFD)?;
+				writer.u32(0x4A)?;
+			}
+			f64x2_le => {
+				writer.u8(0xFD)?;
+				writer.u32(0x4B)?;
+			}
+			f64x2_ge => {
+				writer.u8(0xFD)?;
+				writer.u32(0x4C)?;
+			}
+			v128_not => {
+				writer.u8(0xFD)?;
+				writer.u32(0x4D)?;
+			}
+			v128_and => {
+				writer.u8(0xFD)?;
+				writer.u32(0x4E)?;
+			}
+			v128_andnot => {
+				writer.u8(0xFD)?;
+				writer.u32(0x4F)?;
+			}
+			v128_or => {
+				writer.u8(0xFD)?;
+				writer.u32(0x50)?;
+			}
+			v128_xor => {
+				writer.u8(0xFD)?;
+				writer.u32(0x51)?;
+			}
+			v128_bitselect => {
+				writer.u8(0xFD)?;
+				writer.u32(0x52)?;
+			}
+			v128_any_true => {
+				writer.u8(0xFD)?;
+				writer.u32(0x53)?;
+			}
+			v128_load8_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x54)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_load16_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x55)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_load32_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x56)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_load64_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x57)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_store8_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x58)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_store16_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x59)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_store32_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x5A)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_store64_lane(x0, x1, x2) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x5B)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+				writer.u8(*x2)?;
+			}
+			v128_load32_zero(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x5C)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			v128_load64_zero(x0, x1) => {
+				writer.u8(0xFD)?;
+				writer.u32(0x5D)?;
+				writer.u32(*x0)?;
+				writer.u32(*x1)?;
+			}
+			f32x4_demote_f64x2_zero => {
+				writer.u8(0xFD)?;
+				writer.u32(0x5E)?;
+			}
+			f64x2_promote_low_f32x4 => {
+				writer.u8(0xFD)?;
+				writer.u32(0x5F)?;
+			}
+			i8x16_abs => {
+				writer.u8(0xFD)?;
+				writer.u32(0x60)?;
+			}
+			i8x16_neg => {
+				writer.u8(0xFD)?;
+				writer.u32(0x61)?;
+			}
+			i8x16_popcnt => {
+				writer.u8(0xFD)?;
+				writer.u32(0x62)?;
+			}
+			i8x16_all_true => {
+				writer.u8(0xFD)?;
+				writer.u32(0x63)?;
+			}
+			i8x16_bitmask => {
+				writer.u8(0xFD)?;
+				writer.u32(0x64)?;
+			}
+			i8x16_narrow_i16x8_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x65)?;
+			}
+			i8x16_narrow_i16x8_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x66)?;
+			}
+			f32x4_ceil => {
+				writer.u8(0xFD)?;
+				writer.u32(0x67)?;
+			}
+			f32x4_floor => {
+				writer.u8(0xFD)?;
+				writer.u32(0x68)?;
+			}
+			f32x4_trunc => {
+				writer.u8(0xFD)?;
+				writer.u32(0x69)?;
+			}
+			f32x4_nearest => {
+				writer.u8(0xFD)?;
+				writer.u32(0x6A)?;
+			}
+			i8x16_shl => {
+				writer.u8(0xFD)?;
+				writer.u32(0x6B)?;
+			}
+			i8x16_shr_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x6C)?;
+			}
+			i8x16_shr_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x6D)?;
+			}
+			i8x16_add => {
+				writer.u8(0xFD)?;
+				writer.u32(0x6E)?;
+			}
+			i8x16_add_sat_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x6F)?;
+			}
+			i8x16_add_sat_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x70)?;
+			}
+			i8x16_sub => {
+				writer.u8(0xFD)?;
+				writer.u32(0x71)?;
+			}
+			i8x16_sub_sat_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x72)?;
+			}
+			i8x16_sub_sat_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x73)?;
+			}
+			f64x2_ceil => {
+				writer.u8(0xFD)?;
+				writer.u32(0x74)?;
+			}
+			f64x2_floor => {
+				writer.u8(0xFD)?;
+				writer.u32(0x75)?;
+			}
+			i8x16_min_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x76)?;
+			}
+			i8x16_min_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x77)?;
+			}
+			i8x16_max_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x78)?;
+			}
+			i8x16_max_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x79)?;
+			}
+			f64x2_trunc => {
+				writer.u8(0xFD)?;
+				writer.u32(0x7A)?;
+			}
+			i8x16_avgr_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x7B)?;
+			}
+			i16x8_extadd_pairwise_i8x16_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x7C)?;
+			}
+			i16x8_extadd_pairwise_i8x16_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x7D)?;
+			}
+			i32x4_extadd_pairwise_i16x8_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x7E)?;
+			}
+			i32x4_extadd_pairwise_i16x8_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x7F)?;
+			}
+			i16x8_abs => {
+				writer.u8(0xFD)?;
+				writer.u32(0x80)?;
+			}
+			i16x8_neg => {
+				writer.u8(0xFD)?;
+				writer.u32(0x81)?;
+			}
+			i16x8_q15mulr_sat_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x82)?;
+			}
+			i16x8_all_true => {
+				writer.u8(0xFD)?;
+				writer.u32(0x83)?;
+			}
+			i16x8_bitmask => {
+				writer.u8(0xFD)?;
+				writer.u32(0x84)?;
+			}
+			i16x8_narrow_i32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x85)?;
+			}
+			i16x8_narrow_i32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x86)?;
+			}
+			i16x8_extend_low_i8x16_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x87)?;
+			}
+			i16x8_extend_high_i8x16_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x88)?;
+			}
+			i16x8_extend_low_i8x16_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x89)?;
+			}
+			i16x8_extend_high_i8x16_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x8A)?;
+			}
+			i16x8_shl => {
+				writer.u8(0xFD)?;
+				writer.u32(0x8B)?;
+			}
+			i16x8_shr_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x8C)?;
+			}
+			i16x8_shr_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x8D)?;
+			}
+			i16x8_add => {
+				writer.u8(0xFD)?;
+				writer.u32(0x8E)?;
+			}
+			i16x8_add_sat_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x8F)?;
+			}
+			i16x8_add_sat_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x90)?;
+			}
+			i16x8_sub => {
+				writer.u8(0xFD)?;
+				writer.u32(0x91)?;
+			}
+			i16x8_sub_sat_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x92)?;
+			}
+			i16x8_sub_sat_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x93)?;
+			}
+			f64x2_nearest => {
+				writer.u8(0xFD)?;
+				writer.u32(0x94)?;
+			}
+			i16x8_mul => {
+				writer.u8(0xFD)?;
+				writer.u32(0x95)?;
+			}
+			i16x8_min_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x96)?;
+			}
+			i16x8_min_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x97)?;
+			}
+			i16x8_max_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x98)?;
+			}
+			i16x8_max_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x99)?;
+			}
+			i16x8_avgr_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x9B)?;
+			}
+			i16x8_extmul_low_i8x16_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x9C)?;
+			}
+			i16x8_extmul_high_i8x16_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0x9D)?;
+			}
+			i16x8_extmul_low_i8x16_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x9E)?;
+			}
+			i16x8_extmul_high_i8x16_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0x9F)?;
+			}
+			i32x4_abs => {
+				writer.u8(0xFD)?;
+				writer.u32(0xA0)?;
+			}
+			i32x4_neg => {
+				writer.u8(0xFD)?;
+				writer.u32(0xA1)?;
+			}
+			i32x4_all_true => {
+				writer.u8(0xFD)?;
+				writer.u32(0xA3)?;
+			}
+			i32x4_bitmask => {
+				writer.u8(0xFD)?;
+				writer.u32(0xA4)?;
+			}
+			i32x4_extend_low_i16x8_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xA7)?;
+			}
+			i32x4_extend_high_i16x8_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xA8)?;
+			}
+			i32x4_extend_low_i16x8_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xA9)?;
+			}
+			i32x4_extend_high_i16x8_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xAA)?;
+			}
+			i32x4_shl => {
+				writer.u8(0xFD)?;
+				writer.u32(0xAB)?;
+			}
+			i32x4_shr_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xAC)?;
+			}
+			i32x4_shr_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xAD)?;
+			}
+			i32x4_add => {
+				writer.u8(0xFD)?;
+				writer.u32(0xAE)?;
+			}
+			i32x4_sub => {
+				writer.u8(0xFD)?;
+				writer.u32(0xB1)?;
+			}
+			i32x4_mul => {
+				writer.u8(0xFD)?;
+				writer.u32(0xB5)?;
+			}
+			i32x4_min_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xB6)?;
+			}
+			i32x4_min_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xB7)?;
+			}
+			i32x4_max_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xB8)?;
+			}
+			i32x4_max_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xB9)?;
+			}
+			i32x4_dot_i16x8_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xBA)?;
+			}
+			i32x4_extmul_low_i16x8_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xBC)?;
+			}
+			i32x4_extmul_high_i16x8_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xBD)?;
+			}
+			i32x4_extmul_low_i16x8_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xBE)?;
+			}
+			i32x4_extmul_high_i16x8_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xBF)?;
+			}
+			i64x2_abs => {
+				writer.u8(0xFD)?;
+				writer.u32(0xC0)?;
+			}
+			i64x2_neg => {
+				writer.u8(0xFD)?;
+				writer.u32(0xC1)?;
+			}
+			i64x2_all_true => {
+				writer.u8(0xFD)?;
+				writer.u32(0xC3)?;
+			}
+			i64x2_bitmask => {
+				writer.u8(0xFD)?;
+				writer.u32(0xC4)?;
+			}
+			i64x2_extend_low_i32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xC7)?;
+			}
+			i64x2_extend_high_i32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xC8)?;
+			}
+			i64x2_extend_low_i32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xC9)?;
+			}
+			i64x2_extend_high_i32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xCA)?;
+			}
+			i64x2_shl => {
+				writer.u8(0xFD)?;
+				writer.u32(0xCB)?;
+			}
+			i64x2_shr_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xCC)?;
+			}
+			i64x2_shr_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xCD)?;
+			}
+			i64x2_add => {
+				writer.u8(0xFD)?;
+				writer.u32(0xCE)?;
+			}
+			i64x2_sub => {
+				writer.u8(0xFD)?;
+				writer.u32(0xD1)?;
+			}
+			i64x2_mul => {
+				writer.u8(0xFD)?;
+				writer.u32(0xD5)?;
+			}
+			i64x2_eq => {
+				writer.u8(0xFD)?;
+				writer.u32(0xD6)?;
+			}
+			i64x2_ne => {
+				writer.u8(0xFD)?;
+				writer.u32(0xD7)?;
+			}
+			i64x2_lt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xD8)?;
+			}
+			i64x2_gt_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xD9)?;
+			}
+			i64x2_le_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xDA)?;
+			}
+			i64x2_ge_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xDB)?;
+			}
+			i64x2_extmul_low_i32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xDC)?;
+			}
+			i64x2_extmul_high_i32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xDD)?;
+			}
+			i64x2_extmul_low_i32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xDE)?;
+			}
+			i64x2_extmul_high_i32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xDF)?;
+			}
+			f32x4_abs => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE0)?;
+			}
+			f32x4_neg => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE1)?;
+			}
+			f32x4_sqrt => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE3)?;
+			}
+			f32x4_add => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE4)?;
+			}
+			f32x4_sub => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE5)?;
+			}
+			f32x4_mul => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE6)?;
+			}
+			f32x4_div => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE7)?;
+			}
+			f32x4_min => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE8)?;
+			}
+			f32x4_max => {
+				writer.u8(0xFD)?;
+				writer.u32(0xE9)?;
+			}
+			f32x4_pmin => {
+				writer.u8(0xFD)?;
+				writer.u32(0xEA)?;
+			}
+			f32x4_pmax => {
+				writer.u8(0xFD)?;
+				writer.u32(0xEB)?;
+			}
+			f64x2_abs => {
+				writer.u8(0xFD)?;
+				writer.u32(0xEC)?;
+			}
+			f64x2_neg => {
+				writer.u8(0xFD)?;
+				writer.u32(0xED)?;
+			}
+			f64x2_sqrt => {
+				writer.u8(0xFD)?;
+				writer.u32(0xEF)?;
+			}
+			f64x2_add => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF0)?;
+			}
+			f64x2_sub => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF1)?;
+			}
+			f64x2_mul => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF2)?;
+			}
+			f64x2_div => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF3)?;
+			}
+			f64x2_min => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF4)?;
+			}
+			f64x2_max => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF5)?;
+			}
+			f64x2_pmin => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF6)?;
+			}
+			f64x2_pmax => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF7)?;
+			}
+			i32x4_trunc_sat_f32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF8)?;
+			}
+			i32x4_trunc_sat_f32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xF9)?;
+			}
+			f32x4_convert_i32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xFA)?;
+			}
+			f32x4_convert_i32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xFB)?;
+			}
+			i32x4_trunc_sat_f64x2_s_zero => {
+				writer.u8(0xFD)?;
+				writer.u32(0xFC)?;
+			}
+			i32x4_trunc_sat_f64x2_u_zero => {
+				writer.u8(0xFD)?;
+				writer.u32(0xFD)?;
+			}
+			f64x2_convert_low_i32x4_s => {
+				writer.u8(0xFD)?;
+				writer.u32(0xFE)?;
+			}
+			f64x2_convert_low_i32x4_u => {
+				writer.u8(0xFD)?;
+				writer.u32(0xFF)?;
+			}
+		}
+		Ok(())
+	}
+
+	pub fn write_block_to<W: std::io::Write>(block: &[Self], writer: &mut W) -> Result<()> {
+		for instr in block {
+			instr.write_to(writer)?;
+		}
+		writer.u8(0x0B)
+	}
+
+	pub fn signature(&self) -> &'static str {
+		use Instr::*;
+		match self {
+			unreachable => ">",
+			nop => ">",
+			block(..) => ">",
+			looped(..) => ">",
+			if_else(..) => ">",
+			then_end => ">",
+			end => ">",
+			br(..) => ">",
+			br_if(..) => ">",
+			br_table(..) => ">",
+			return_result => ">",
+			call(..) => ">",
+			call_indirect(..) => ">",
+			drop => "any >",
+			select => "num num i32 > num",
+			select_t(..) => "any any i32 > any",
+			local_get(..) => "> any",
+			local_set(..) => "any >",
+			local_tee(..) => "any > any",
+			global_get(..) => "> any",
+			global_set(..) => "any >",
+			table_get(..) => "i32 > ref",
+			table_set(..) => "i32 ref >",
+			i32_load(..) => "i32 > i32",
+			i64_load(..) => "i32 > i64",
+			f32_load(..) => "i32 > f32",
+			f64_load(..) => "i32 > f64",
+			i32_load8_s(..) => "i32 > i32",
+			i32_load8_u(..) => "i32 > i32",
+			i32_load16_s(..) => "i32 > i32",
+			i32_load16_u(..) => "i32 > i32",
+			i64_load8_s(..) => "i32 > i64",
+			i64_load8_u(..) => "i32 > i64",
+			i64_load16_s(..) => "i32 > i64",
+			i64_load16_u(..) => "i32 > i64",
+			i64_load32_s(..) => "i32 > i64",
+			i64_load32_u(..) => "i32 > i64",
+			i32_store(..) => "i32 i32 >",
+			i64_store(..) => "i32 i64 >",
+			f32_store(..) => "i32 f32 >",
+			f64_store(..) => "i32 f64 >",
+			i32_store8(..) => "i32 i32 >",
+			i32_store16(..) => "i32 i32 >",
+			i64_store8(..) => "i32 i64 >",
+			i64_store16(..) => "i32 i64 >",
+			i64_store32(..) => "i32 i64 >",
+			memory_size => "> i32",
+			memory_grow => "i32 > i32",
+			i32_const(..) => "> i32",
+			i64_const(..) => "> i64",
+			f32_const(..) => "> f32",
+			f64_const(..) => "> f64",
+			i32_eqz => "i32 > i32",
+			i32_eq => "i32 i32 > i32",
+			i32_ne => "i32 i32 > i32",
+			i32_lt_s => "i32 i32 > i32",
+			i32_lt_u => "i32 i32 > i32",
+			i32_gt_s => "i32 i32 > i32",
+			i32_gt_u => "i32 i32 > i32",
+			i32_le_s => "i32 i32 > i32",
+			i32_le_u => "i32 i32 > i32",
+			i32_ge_s => "i32 i32 > i32",
+			i32_ge_u => "i32 i32 > i32",
+			i64_eqz => "i64 > i32",
+			i64_eq => "i64 i64 > i32",
+			i64_ne => "i64 i64 > i32",
+			i64_lt_s => "i64 i64 > i32",
+			i64_lt_u => "i64 i64 > i32",
+			i64_gt_s => "i64 i64 > i32",
+			i64_gt_u => "i64 i64 > i32",
+			i64_le_s => "i64 i64 > i32",
+			i64_le_u => "i64 i64 > i32",
+			i64_ge_s => "i64 i64 > i32",
+			i64_ge_u => "i64 i64 > i32",
+			f32_eq => "f32 f32 > i32",
+			f32_ne => "f32 f32 > i32",
+			f32_lt => "f32 f32 > i32",
+			f32_gt => "f32 f32 > i32",
+			f32_le => "f32 f32 > i32",
+			f32_ge => "f32 f32 > i32",
+			f64_eq => "f64 f64 > i32",
+			f64_ne => "f64 f64 > i32",
+			f64_lt => "f64 f64 > i32",
+			f64_gt => "f64 f64 > i32",
+			f64_le => "f64 f64 > i32",
+			f64_ge => "f64 f64 > i32",
+			i32_clz => "i32 > i32",
+			i32_ctz => "i32 > i32",
+			i32_popcnt => "i32 > i32",
+			i32_add => "i32 i32 > i32",
+			i32_sub => "i32 i32 > i32",
+			i32_mul => "i32 i32 > i32",
+			i32_div_s => "i32 i32 > i32",
+			i32_div_u => "i32 i32 > i32",
+			i32_rem_s => "i32 i32 > i32",
+			i32_rem_u => "i32 i32 > i32",
+			i32_and => "i32 i32 > i32",
+			i32_or => "i32 i32 > i32",
+			i32_xor => "i32 i32 > i32",
+			i32_shl => "i32 i32 > i32",
+			i32_shr_s => "i32 i32 > i32",
+			i32_shr_u => "i32 i32 > i32",
+			i32_rotl => "i32 i32 > i32",
+			i32_rotr => "i32 i32 > i32",
+			i64_clz => "i64 > i64",
+			i64_ctz => "i64 > i64",
+			i64_popcnt => "i64 > i64",
+			i64_add => "i64 i64 > i64",
+			i64_sub => "i64 i64 > i64",
+			i64_mul => "i64 i64 > i64",
+			i64_div_s => "i64 i64 > i64",
+			i64_div_u => "i64 i64 > i64",
+			i64_rem_s => "i64 i64 > i64",
+			i64_rem_u => "i64 i64 > i64",
+			i64_and => "i64 i64 > i64",
+			i64_or => "i64 i64 > i64",
+			i64_xor => "i64 i64 > i64",
+			i64_shl => "i64 i64 > i64",
+			i64_shr_s => "i64 i64 > i64",
+			i64_shr_u => "i64 i64 > i64",
+			i64_rotl => "i64 i64 > i64",
+			i64_rotr => "i64 i64 > i64",
+			f32_abs => "f32 > f32",
+			f32_neg => "f32 > f32",
+			f32_ceil => "f32 > f32",
+			f32_floor => "f32 > f32",
+			f32_trunc => "f32 > f32",
+			f32_nearest => "f32 > f32",
+			f32_sqrt => "f32 > f32",
+			f32_add => "f32 f32 > f32",
+			f32_sub => "f32 f32 > f32",
+			f32_mul => "f32 f32 > f32",
+			f32_div => "f32 f32 > f32",
+			f32_min => "f32 f32 > f32",
+			f32_max => "f32 f32 > f32",
+			f32_copysign => "f32 f32 > f32",
+			f64_abs => "f64 > f64",
+			f64_neg => "f64 > f64",
+			f64_ceil => "f64 > f64",
+			f64_floor => "f64 > f64",
+			f64_trunc => "f64 > f64",
+			f64_nearest => "f64 > f64",
+			f64_sqrt => "f64 > f64",
+			f64_add => "f64 f64 > f64",
+			f64_sub => "f64 f64 > f64",
+			f64_mul => "f64 f64 > f64",
+			f64_div => "f64 f64 > f64",
+			f64_min => "f64 f64 > f64",
+			f64_max => "f64 f64 > f64",
+			f64_copysign => "f64 f64 > f64",
+			i32_wrap_i64 => "i64 > i32",
+			i32_trunc_f32_s => "f32 > i32",
+			i32_trunc_f32_u => "f32 > i32",
+			i32_trunc_f64_s => "f64 > i32",
+			i32_trunc_f64_u => "f64 > i32",
+			i64_extend_i32_s => "i32 > i64",
+			i64_extend_i32_u => "i32 > i64",
+			i64_trunc_f32_s => "f32 > i64",
+			i64_trunc_f32_u => "f32 > i64",
+			i64_trunc_f64_s => "f64 > i64",
+			i64_trunc_f64_u => "f64 > i64",
+			f32_convert_i32_s => "i32 > f32",
+			f32_convert_i32_u => "i32 > f32",
+			f32_convert_i64_s => "i64 > f32",
+			f32_convert_i64_u => "i64 > f32",
+			f32_demote_f64 => "f64 > f32",
+			f64_convert_i32_s => "i32 > f64",
+			f64_convert_i32_u => "i32 > f64",
+			f64_convert_i64_s => "i64 > f64",
+			f64_convert_i64_u => "i64 > f64",
+			f64_promote_f32 => "f32 > f64",
+			i32_reinterpret_f32 => "f32 > i32",
+			i64_reinterpret_f64 => "f64 > i64",
+			f32_reinterpret_i32 => "i32 > f32",
+			f64_reinterpret_i64 => "i64 > f64",
+			i32_extend8_s => "i32 > i32",
+			i32_extend16_s => "i32 > i32",
+			i64_extend8_s => "i64 > i64",
+			i64_extend16_s => "i64 > i64",
+			i64_extend32_s => "i64 > i64",
+			ref_null(..) => "> ref",
+			ref_is_null => "ref > i32",
+			ref_func(..) => "> funcref",
+			i32_trunc_sat_f32_s => "f32 > i32",
+			i32_trunc_sat_f32_u => "f32 > i32",
+			i32_trunc_sat_f64_s => "f64 > i32",
+			i32_trunc_sat_f64_u => "f64 > i32",
+			i64_trunc_sat_f32_s => "f32 > i64",
+			i64_trunc_sat_f32_u => "f32 > i64",
+			i64_trunc_sat_f64_s => "f64 > i64",
+			i64_trunc_sat_f64_u => "f64 > i64",
+			memory_init(..) => "i32 i32 i32 >",
+			data_drop(..) => ">",
+			memory_copy(..) => "i32 i32 i32 >",
+			memory_fill(..) => "i32 i32 i32 >",
+			table_init(..) => "i32 i32 i32 >",
+			elem_drop(..) => ">",
+			table_copy(..) => "i32 i32 i32 >",
+			table_grow(..) => "ref i32 > i32",
+			table_size(..) => "> i32",
+			table_fill(..) => "i32 ref i32 >",
+			v128_load(..) => "i32 > v128",
+			v128_load8x8_s(..) => "i32 > v128",
+			v128_load8x8_u(..) => "i32 > v128",
+			v128_load16x4_s(..) => "i32 > v128",
+			v128_load16x4_u(..) => "i32 > v128",
+			v128_load32x2_s(..) => "i32 > v128",
+			v128_load32x2_u(..) => "i32 > v128",
+			v128_load8_splat(..) => "i32 > v128",
+			v128_load16_splat(..) => "i32 > v128",
+			v128_load32_splat(..) => "i32 > v128",
+			v128_load64_splat(..) => "i32 > v128",
+			v128_store(..) => "i32 v128 >",
+			v128_const(..) => "> v128",
+			i8x16_shuffle(..) => "v128 v128 > v128",
+			i8x16_swizzle => "v128 v128 > v128",
+			i8x16_splat => "i32 > v128",
+			i16x8_splat => "i32 > v128",
+			i32x4_splat => "i32 > v128",
+			i64x2_splat => "i64 > v128",
+			f32x4_splat => "f32 > v128",
+			f64x2_splat => "f64 > v128",
+			i8x16_extract_lane_s(..) => "v128 > i32",
+			i8x16_extract_lane_u(..) => "v128 > i32",
+			i8x16_replace_lane(..) => "v128 i32 > v128",
+			i16x8_extract_lane_s(..) => "v128 > i32",
+			i16x8_extract_lane_u(..) => "v128 > i32",
+			i16x8_replace_lane(..) => "v128 i32 > v128",
+			i32x4_extract_lane(..) => "v128 > i32",
+			i32x4_replace_lane(..) => "v128 i32 > v128",
+			i64x2_extract_lane(..) => "v128 > i64",
+			i64x2_replace_lane(..) => "v128 i64 > v128",
+			f32x4_extract_lane(..) => "v128 > f32",
+			f32x4_replace_lane(..) => "v128 f32 > v128",
+			f64x2_extract_lane(..) => "v128 > f64",
+			f64x2_replace_lane(..) => "v128 f64 > v128",
+			i8x16_eq => "v128 v128 > v128",
+			i8x16_ne => "v128 v128 > v128",
+			i8x16_lt_s => "v128 v128 > v128",
+			i8x16_lt_u => "v128 v128 > v128",
+			i8x16_gt_s => "v128 v128 > v128",
+			i8x16_gt_u => "v128 v128 > v128",
+			i8x16_le_s => "v128 v128 > v128",
+			i8x16_le_u => "v128 v128 > v128",
+			i8x16_ge_s => "v128 v128 > v128",
+			i8x16_ge_u => "v128 v128 > v128",
+			i16x8_eq => "v128 v128 > v128",
+			i16x8_ne => "v128 v128 > v128",
+			i16x8_lt_s => "v128 v128 > v128",
+			i16x8_lt_u => "v128 v128 > v128",
+			i16x8_gt_s => "v128 v128 > v128",
+			i16x8_gt_u => "v128 v128 > v128",
+			i16x8_le_s => "v128 v128 > v128",
+			i16x8_le_u => "v128 v128 > v128",
+			i16x8_ge_s => "v128 v128 > v128",
+			i16x8_ge_u => "v128 v128 > v128",
+			i32x4_eq => "v128 v128 > v128",
+			i32x4_ne => "v128 v128 > v128",
+			i32x4_lt_s => "v128 v128 > v128",
+			i32x4_lt_u => "v128 v128 > v128",
+			i32x4_gt_s => "v128 v128 > v128",
+			i32x4_gt_u => "v128 v128 > v128",
+			i32x4_le_s => "v128 v128 > v128",
+			i32x4_le_u => "v128 v128 > v128",
+			i32x4_ge_s => "v128 v128 > v128",
+			i32x4_ge_u => "v128 v128 > v128",
+			f32x4_eq => "v128 v128 > v128",
+			f32x4_ne => "v128 v128 > v128",
+			f32x4_lt => "v128 v128 > v128",
+			f32x4_gt => "v128 v128 > v128",
+			f32x4_le => "v128 v128 > v128",
+			f32x4_ge => "v128 v128 > v128",
+			f64x2_eq => "v128 v128 > v128",
+			f64x2_ne => "v128 v128 > v128",
+			f64x2_lt => "v128 v128 > v128",
+			f64x2_gt => "v128 v128 > v128",
+			f64x2_le => "v128 v128 > v128",
+			f64x2_ge => "v128 v128 > v128",
+			v128_not => "v128 > v128",
+			v128_and => "v128 v128 > v128",
+			v128_andnot => "v128 v128 > v128",
+			v128_or => "v128 v128 > v128",
+			v128_xor => "v128 v128 > v128",
+			v128_bitselect => "v128 v128 v128 > v128",
+			v128_any_true => "v128 > i32",
+			v128_load8_lane(..) => "i32 v128 > v128",
+			v128_load16_lane(..) => "i32 v128 > v128",
+			v128_load32_lane(..) => "i32 v128 > v128",
+			v128_load64_lane(..) => "i32 v128 > v128",
+			v128_store8_lane(..) => "i32 v128 >",
+			v128_store16_lane(..) => "i32 v128 >",
+			v128_store32_lane(..) => "i32 v128 >",
+			v128_store64_lane(..) => "i32 v128 >",
+			v128_load32_zero(..) => "i32 > v128",
+			v128_load64_zero(..) => "i32 > v128",
+			f32x4_demote_f64x2_zero => "v128 > v128",
+			f64x2_promote_low_f32x4 => "v128 > v128",
+			i8x16_abs => "v128 > v128",
+			i8x16_neg => "v128 > v128",
+			i8x16_popcnt => "v128 > v128",
+			i8x16_all_true => "v128 > i32",
+			i8x16_bitmask => "v128 > i32",
+			i8x16_narrow_i16x8_s => "v128 v128 > v128",
+			i8x16_narrow_i16x8_u => "v128 v128 > v128",
+			f32x4_ceil => "v128 > v128",
+			f32x4_floor => "v128 > v128",
+			f32x4_trunc => "v128 > v128",
+			f32x4_nearest => "v128 > v128",
+			i8x16_shl => "v128 i32 > v128",
+			i8x16_shr_s => "v128 i32 > v128",
+			i8x16_shr_u => "v128 i32 > v128",
+			i8x16_add => "v128 v128 > v128",
+			i8x16_add_sat_s => "v128 v128 > v128",
+			i8x16_add_sat_u => "v128 v128 > v128",
+			i8x16_sub => "v128 v128 > v128",
+			i8x16_sub_sat_s => "v128 v128 > v128",
+			i8x16_sub_sat_u => "v128 v128 > v128",
+			f64x2_ceil => "v128 > v128",
+			f64x2_floor => "v128 > v128",
+			i8x16_min_s => "v128 v128 > v128",
+			i8x16_min_u => "v128 v128 > v128",
+			i8x16_max_s => "v128 v128 > v128",
+			i8x16_max_u => "v128 v128 > v128",
+			f64x2_trunc => "v128 > v128",
+			i8x16_avgr_u => "v128 v128 > v128",
+			i16x8_extadd_pairwise_i8x16_s => "v128 > v128",
+			i16x8_extadd_pairwise_i8x16_u => "v128 > v128",
+			i32x4_extadd_pairwise_i16x8_s => "v128 > v128",
+			i32x4_extadd_pairwise_i16x8_u => "v128 > v128",
+			i16x8_abs => "v128 > v128",
+			i16x8_neg => "v128 > v128",
+			i16x8_q15mulr_sat_s => "v128 v128 > v128",
+			i16x8_all_true => "v128 > i32",
+			i16x8_bitmask => "v128 > i32",
+			i16x8_narrow_i32x4_s => "v128 v128 > v128",
+			i16x8_narrow_i32x4_u => "v128 v128 > v128",
+			i16x8_extend_low_i8x16_s => "v128 > v128",
+			i16x8_extend_high_i8x16_s => "v128 > v128",
+			i16x8_extend_low_i8x16_u => "v128 > v128",
+			i16x8_extend_high_i8x16_u => "v128 > v128",
+			i16x8_shl => "v128 i32 > v128",
+			i16x8_shr_s => "v128 i32 > v128",
+			i16x8_shr_u => "v128 i32 > v128",
+			i16x8_add => "v128 v128 > v128",
+			i16x8_add_sat_s => "v128 v128 > v128",
+			i16x8_add_sat_u => "v128 v128 > v128",
+			i16x8_sub => "v128 v128 > v128",
+			i16x8_sub_sat_s => "v128 v128 > v128",
+			i16x8_sub_sat_u => "v128 v128 > v128",
+			f64x2_nearest => "v128 > v128",
+			i16x8_mul => "v128 v128 > v128",
+			i16x8_min_s => "v128 v128 > v128",
+			i16x8_min_u => "v128 v128 > v128",
+			i16x8_max_s => "v128 v128 > v128",
+			i16x8_max_u => "v128 v128 > v128",
+			i16x8_avgr_u => "v128 v128 > v128",
+			i16x8_extmul_low_i8x16_s => "v128 v128 > v128",
+			i16x8_extmul_high_i8x16_s => "v128 v128 > v128",
+			i16x8_extmul_low_i8x16_u => "v128 v128 > v128",
+			i16x8_extmul_high_i8x16_u => "v128 v128 > v128",
+			i32x4_abs => "v128 > v128",
+			i32x4_neg => "v128 > v128",
+			i32x4_all_true => "v128 > i32",
+			i32x4_bitmask => "v128 > i32",
+			i32x4_extend_low_i16x8_s => "v128 > v128",
+			i32x4_extend_high_i16x8_s => "v128 > v128",
+			i32x4_extend_low_i16x8_u => "v128 > v128",
+			i32x4_extend_high_i16x8_u => "v128 > v128",
+			i32x4_shl => "v128 i32 > v128",
+			i32x4_shr_s => "v128 i32 > v128",
+			i32x4_shr_u => "v128 i32 > v128",
+			i32x4_add => "v128 v128 > v128",
+			i32x4_sub => "v128 v128 > v128",
+			i32x4_mul => "v128 v128 > v128",
+			i32x4_min_s => "v128 v128 > v128",
+			i32x4_min_u => "v128 v128 > v128",
+			i32x4_max_s => "v128 v128 > v128",
+			i32x4_max_u => "v128 v128 > v128",
+			i32x4_dot_i16x8_s => "v128 v128 > v128",
+			i32x4_extmul_low_i16x8_s => "v128 v128 > v128",
+			i32x4_extmul_high_i16x8_s => "v128 v128 > v128",
+			i32x4_extmul_low_i16x8_u => "v128 v128 > v128",
+			i32x4_extmul_high_i16x8_u => "v128 v128 > v128",
+			i64x2_abs => "v128 > v128",
+			i64x2_neg => "v128 > v128",
+			i64x2_all_true => "v128 > i32",
+			i64x2_bitmask => "v128 > i32",
+			i64x2_extend_low_i32x4_s => "v128 > v128",
+			i64x2_extend_high_i32x4_s => "v128 > v128",
+			i64x2_extend_low_i32x4_u => "v128 > v128",
+			i64x2_extend_high_i32x4_u => "v128 > v128",
+			i64x2_shl => "v128 i32 > v128",
+			i64x2_shr_s => "v128 i32 > v128",
+			i64x2_shr_u => "v128 i32 > v128",
+			i64x2_add => "v128 v128 > v128",
+			i64x2_sub => "v128 v128 > v128",
+			i64x2_mul => "v128 v128 > v128",
+			i64x2_eq => "v128 v128 > v128",
+			i64x2_ne => "v128 v128 > v128",
+			i64x2_lt_s => "v128 v128 > v128",
+			i64x2_gt_s => "v128 v128 > v128",
+			i64x2_le_s => "v128 v128 > v128",
+			i64x2_ge_s => "v128 v128 > v128",
+			i64x2_extmul_low_i32x4_s => "v128 v128 > v128",
+			i64x2_extmul_high_i32x4_s => "v128 v128 > v128",
+			i64x2_extmul_low_i32x4_u => "v128 v128 > v128",
+			i64x2_extmul_high_i32x4_u => "v128 v128 > v128",
+			f32x4_abs => "v128 > v128",
+			f32x4_neg => "v128 > v128",
+			f32x4_sqrt => "v128 > v128",
+			f32x4_add => "v128 v128 > v128",
+			f32x4_sub => "v128 v128 > v128",
+			f32x4_mul => "v128 v128 > v128",
+			f32x4_div => "v128 v128 > v128",
+			f32x4_min => "v128 v128 > v128",
+			f32x4_max => "v128 v128 > v128",
+			f32x4_pmin => "v128 v128 > v128",
+			f32x4_pmax => "v128 v128 > v128",
+			f64x2_abs => "v128 > v128",
+			f64x2_neg => "v128 > v128",
+			f64x2_sqrt => "v128 > v128",
+			f64x2_add => "v128 v128 > v128",
+			f64x2_sub => "v128 v128 > v128",
+			f64x2_mul => "v128 v128 > v128",
+			f64x2_div => "v128 v128 > v128",
+			f64x2_min => "v128 v128 > v128",
+			f64x2_max => "v128 v128 > v128",
+			f64x2_pmin => "v128 v128 > v128",
+			f64x2_pmax => "v128 v128 > v128",
+			i32x4_trunc_sat_f32x4_s => "v128 > v128",
+			i32x4_trunc_sat_f32x4_u => "v128 > v128",
+			f32x4_convert_i32x4_s => "v128 > v128",
+			f32x4_convert_i32x4_u => "v128 > v128",
+			i32x4_trunc_sat_f64x2_s_zero => "v128 > v128",
+			i32x4_trunc_sat_f64x2_u_zero => "v128 > v128",
+			f64x2_convert_low_i32x4_s => "v128 > v128",
+			f64x2_convert_low_i32x4_u => "v128 > v128",
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		use Instr::*;
+		match self {
+			unreachable => "unreachable",
+			nop => "nop",
+			block(..) => "block",
+			looped(..) => "loop",
+			if_else(..) => "if",
+			then_end => "else",
+			end => "end",
+			br(..) => "br",
+			br_if(..) => "br_if",
+			br_table(..) => "br_table",
+			return_result => "return",
+			call(..) => "call",
+			call_indirect(..) => "call_indirect",
+			drop => "drop",
+			select => "select",
+			select_t(..) => "select",
+			local_get(..) => "local.get",
+			local_set(..) => "local.set",
+			local_tee(..) => "local.tee",
+			global_get(..) => "global.get",
+			global_set(..) => "global.set",
+			table_get(..) => "table.get",
+			table_set(..) => "table.set",
+			i32_load(..) => "i32.load",
+			i64_load(..) => "i64.load",
+			f32_load(..) => "f32.load",
+			f64_load(..) => "f64.load",
+			i32_load8_s(..) => "i32.load8_s",
+			i32_load8_u(..) => "i32.load8_u",
+			i32_load16_s(..) => "i32.load16_s",
+			i32_load16_u(..) => "i32.load16_u",
+			i64_load8_s(..) => "i64.load8_s",
+			i64_load8_u(..) => "i64.load8_u",
+			i64_load16_s(..) => "i64.load16_s",
+			i64_load16_u(..) => "i64.load16_u",
+			i64_load32_s(..) => "i64.load32_s",
+			i64_load32_u(..) => "i64.load32_u",
+			i32_store(..) => "i32.store",
+			i64_store(..) => "i64.store",
+			f32_store(..) => "f32.store",
+			f64_store(..) => "f64.store",
+			i32_store8(..) => "i32.store8",
+			i32_store16(..) => "i32.store16",
+			i64_store8(..) => "i64.store8",
+			i64_store16(..) => "i64.store16",
+			i64_store32(..) => "i64.store32",
+			memory_size => "memory.size",
+			memory_grow => "memory.grow",
+			i32_const(..) => "i32.const",
+			i64_const(..) => "i64.const",
+			f32_const(..) => "f32.const",
+			f64_const(..) => "f64.const",
+			i32_eqz => "i32.eqz",
+			i32_eq => "i32.eq",
+			i32_ne => "i32.ne",
+			i32_lt_s => "i32.lt_s",
+			i32_lt_u => "i32.lt_u",
+			i32_gt_s => "i32.gt_s",
+			i32_gt_u => "i32.gt_u",
+			i32_le_s => "i32.le_s",
+			i32_le_u => "i32.le_u",
+			i32_ge_s => "i32.ge_s",
+			i32_ge_u => "i32.ge_u",
+			i64_eqz => "i64.eqz",
+			i64_eq => "i64.eq",
+			i64_ne => "i64.ne",
+			i64_lt_s => "i64.lt_s",
+			i64_lt_u => "i64.lt_u",
+			i64_gt_s => "i64.gt_s",
+			i64_gt_u => "i64.gt_u",
+			i64_le_s => "i64.le_s",
+			i64_le_u => "i64.le_u",
+			i64_ge_s => "i64.ge_s",
+			i64_ge_u => "i64.ge_u",
+			f32_eq => "f32.eq",
+			f32_ne => "f32.ne",
+			f32_lt => "f32.lt",
+			f32_gt => "f32.gt",
+			f32_le => "f32.le",
+			f32_ge => "f32.ge",
+			f64_eq => "f64.eq",
+			f64_ne => "f64.ne",
+			f64_lt => "f64.lt",
+			f64_gt => "f64.gt",
+			f64_le => "f64.le",
+			f64_ge => "f64.ge",
+			i32_clz => "i32.clz",
+			i32_ctz => "i32.ctz",
+			i32_popcnt => "i32.popcnt",
+			i32_add => "i32.add",
+			i32_sub => "i32.sub",
+			i32_mul => "i32.mul",
+			i32_div_s => "i32.div_s",
+			i32_div_u => "i32.div_u",
+			i32_rem_s => "i32.rem_s",
+			i32_rem_u => "i32.rem_u",
+			i32_and => "i32.and",
+			i32_or => "i32.or",
+			i32_xor => "i32.xor",
+			i32_shl => "i32.shl",
+			i32_shr_s => "i32.shr_s",
+			i32_shr_u => "i32.shr_u",
+			i32_rotl => "i32.rotl",
+			i32_rotr => "i32.rotr",
+			i64_clz => "i64.clz",
+			i64_ctz => "i64.ctz",
+			i64_popcnt => "i64.popcnt",
+			i64_add => "i64.add",
+			i64_sub => "i64.sub",
+			i64_mul => "i64.mul",
+			i64_div_s => "i64.div_s",
+			i64_div_u => "i64.div_u",
+			i64_rem_s => "i64.rem_s",
+			i64_rem_u => "i64.rem_u",
+			i64_and => "i64.and",
+			i64_or => "i64.or",
+			i64_xor => "i64.xor",
+			i64_shl => "i64.shl",
+			i64_shr_s => "i64.shr_s",
+			i64_shr_u => "i64.shr_u",
+			i64_rotl => "i64.rotl",
+			i64_rotr => "i64.rotr",
+			f32_abs => "f32.abs",
+			f32_neg => "f32.neg",
+			f32_ceil => "f32.ceil",
+			f32_floor => "f32.floor",
+			f32_trunc => "f32.trunc",
+			f32_nearest => "f32.nearest",
+			f32_sqrt => "f32.sqrt",
+			f32_add => "f32.add",
+			f32_sub => "f32.sub",
+			f32_mul => "f32.mul",
+			f32_div => "f32.div",
+			f32_min => "f32.min",
+			f32_max => "f32.max",
+			f32_copysign => "f32.copysign",
+			f64_abs => "f64.abs",
+			f64_neg => "f64.neg",
+			f64_ceil => "f64.ceil",
+			f64_floor => "f64.floor",
+			f64_trunc => "f64.trunc",
+			f64_nearest => "f64.nearest",
+			f64_sqrt => "f64.sqrt",
+			f64_add => "f64.add",
+			f64_sub => "f64.sub",
+			f64_mul => "f64.mul",
+			f64_div => "f64.div",
+			f64_min => "f64.min",
+			f64_max => "f64.max",
+			f64_copysign => "f64.copysign",
+			i32_wrap_i64 => "i32.wrap_i64",
+			i32_trunc_f32_s => "i32.trunc_f32_s",
+			i32_trunc_f32_u => "i32.trunc_f32_u",
+			i32_trunc_f64_s => "i32.trunc_f64_s",
+			i32_trunc_f64_u => "i32.trunc_f64_u",
+			i64_extend_i32_s => "i64.extend_i32_s",
+			i64_extend_i32_u => "i64.extend_i32_u",
+			i64_trunc_f32_s => "i64.trunc_f32_s",
+			i64_trunc_f32_u => "i64.trunc_f32_u",
+			i64_trunc_f64_s => "i64.trunc_f64_s",
+			i64_trunc_f64_u => "i64.trunc_f64_u",
+			f32_convert_i32_s => "f32.convert_i32_s",
+			f32_convert_i32_u => "f32.convert_i32_u",
+			f32_convert_i64_s => "f32.convert_i64_s",
+			f32_convert_i64_u => "f32.convert_i64_u",
+			f32_demote_f64 => "f32.demote_f64",
+			f64_convert_i32_s => "f64.convert_i32_s",
+			f64_convert_i32_u => "f64.convert_i32_u",
+			f64_convert_i64_s => "f64.convert_i64_s",
+			f64_convert_i64_u => "f64.convert_i64_u",
+			f64_promote_f32 => "f64.promote_f32",
+			i32_reinterpret_f32 => "i32.reinterpret_f32",
+			i64_reinterpret_f64 => "i64.reinterpret_f64",
+			f32_reinterpret_i32 => "f32.reinterpret_i32",
+			f64_reinterpret_i64 => "f64.reinterpret_i64",
+			i32_extend8_s => "i32.extend8_s",
+			i32_extend16_s => "i32.extend16_s",
+			i64_extend8_s => "i64.extend8_s",
+			i64_extend16_s => "i64.extend16_s",
+			i64_extend32_s => "i64.extend32_s",
+			ref_null(..) => "ref.null",
+			ref_is_null => "ref.is_null",
+			ref_func(..) => "ref.func",
+			i32_trunc_sat_f32_s => "i32.trunc_sat_f32_s",
+			i32_trunc_sat_f32_u => "i32.trunc_sat_f32_u",
+			i32_trunc_sat_f64_s => "i32.trunc_sat_f64_s",
+			i32_trunc_sat_f64_u => "i32.trunc_sat_f64_u",
+			i64_trunc_sat_f32_s => "i64.trunc_sat_f32_s",
+			i64_trunc_sat_f32_u => "i64.trunc_sat_f32_u",
+			i64_trunc_sat_f64_s => "i64.trunc_sat_f64_s",
+			i64_trunc_sat_f64_u => "i64.trunc_sat_f64_u",
+			memory_init(..) => "memory.init",
+			data_drop(..) => "data.drop",
+			memory_copy(..) => "memory.copy",
+			memory_fill(..) => "memory.fill",
+			table_init(..) => "table.init",
+			elem_drop(..) => "elem.drop",
+			table_copy(..) => "table.copy",
+			table_grow(..) => "table.grow",
+			table_size(..) => "table.size",
+			table_fill(..) => "table.fill",
+			v128_load(..) => "v128.load",
+			v128_load8x8_s(..) => "v128.load8x8_s",
+			v128_load8x8_u(..) => "v128.load8x8_u",
+			v128_load16x4_s(..) => "v128.load16x4_s",
+			v128_load16x4_u(..) => "v128.load16x4_u",
+			v128_load32x2_s(..) => "v128.load32x2_s",
+			v128_load32x2_u(..) => "v128.load32x2_u",
+			v128_load8_splat(..) => "v128.load8_splat",
+			v128_load16_splat(..) => "v128.load16_splat",
+			v128_load32_splat(..) => "v128.load32_splat",
+			v128_load64_splat(..) => "v128.load64_splat",
+			v128_store(..) => "v128.store",
+			v128_const(..) => "v128.const",
+			i8x16_shuffle(..) => "i8x16.shuffle",
+			i8x16_swizzle => "i8x16.swizzle",
+			i8x16_splat => "i8x16.splat",
+			i16x8_splat => "i16x8.splat",
+			i32x4_splat => "i32x4.splat",
+			i64x2_splat => "i64x2.splat",
+			f32x4_splat => "f32x4.splat",
+			f64x2_splat => "f64x2.splat",
+			i8x16_extract_lane_s(..) => "i8x16.extract_lane_s",
+			i8x16_extract_lane_u(..) => "i8x16.extract_lane_u",
+			i8x16_replace_lane(..) => "i8x16.replace_lane",
+			i16x8_extract_lane_s(..) => "i16x8.extract_lane_s",
+			i16x8_extract_lane_u(..) => "i16x8.extract_lane_u",
+			i16x8_replace_lane(..) => "i16x8.replace_lane",
+			i32x4_extract_lane(..) => "i32x4.extract_lane",
+			i32x4_replace_lane(..) => "i32x4.replace_lane",
+			i64x2_extract_lane(..) => "i64x2.extract_lane",
+			i64x2_replace_lane(..) => "i64x2.replace_lane",
+			f32x4_extract_lane(..) => "f32x4.extract_lane",
+			f32x4_replace_lane(..) => "f32x4.replace_lane",
+			f64x2_extract_lane(..) => "f64x2.extract_lane",
+			f64x2_replace_lane(..) => "f64x2.replace_lane",
+			i8x16_eq => "i8x16.eq",
+			i8x16_ne => "i8x16.ne",
+			i8x16_lt_s => "i8x16.lt_s",
+			i8x16_lt_u => "i8x16.lt_u",
+			i8x16_gt_s => "i8x16.gt_s",
+			i8x16_gt_u => "i8x16.gt_u",
+			i8x16_le_s => "i8x16.le_s",
+			i8x16_le_u => "i8x16.le_u",
+			i8x16_ge_s => "i8x16.ge_s",
+			i8x16_ge_u => "i8x16.ge_u",
+			i16x8_eq => "i16x8.eq",
+			i16x8_ne => "i16x8.ne",
+			i16x8_lt_s => "i16x8.lt_s",
+			i16x8_lt_u => "i16x8.lt_u",
+			i16x8_gt_s => "i16x8.gt_s",
+			i16x8_gt_u => "i16x8.gt_u",
+			i16x8_le_s => "i16x8.le_s",
+			i16x8_le_u => "i16x8.le_u",
+			i16x8_ge_s => "i16x8.ge_s",
+			i16x8_ge_u => "i16x8.ge_u",
+			i32x4_eq => "i32x4.eq",
+			i32x4_ne => "i32x4.ne",
+			i32x4_lt_s => "i32x4.lt_s",
+			i32x4_lt_u => "i32x4.lt_u",
+			i32x4_gt_s => "i32x4.gt_s",
+			i32x4_gt_u => "i32x4.gt_u",
+			i32x4_le_s => "i32x4.le_s",
+			i32x4_le_u => "i32x4.le_u",
+			i32x4_ge_s => "i32x4.ge_s",
+			i32x4_ge_u => "i32x4.ge_u",
+			f32x4_eq => "f32x4.eq",
+			f32x4_ne => "f32x4.ne",
+			f32x4_lt => "f32x4.lt",
+			f32x4_gt => "f32x4.gt",
+			f32x4_le => "f32x4.le",
+			f32x4_ge => "f32x4.ge",
+			f64x2_eq => "f64x2.eq",
+			f64x2_ne => "f64x2.ne",
+			f64x2_lt => "f64x2.lt",
+			f64x2_gt => "f64x2.gt",
+			f64x2_le => "f64x2.le",
+			f64x2_ge => "f64x2.ge",
+			v128_not => "v128.not",
+			v128_and => "v128.and",
+			v128_andnot => "v128.andnot",
+			v128_or => "v128.or",
+			v128_xor => "v128.xor",
+			v128_bitselect => "v128.bitselect",
+			v128_any_true => "v128.any_true",
+			v128_load8_lane(..) => "v128.load8_lane",
+			v128_load16_lane(..) => "v128.load16_lane",
+			v128_load32_lane(..) => "v128.load32_lane",
+			v128_load64_lane(..) => "v128.load64_lane",
+			v128_store8_lane(..) => "v128.store8_lane",
+			v128_store16_lane(..) => "v128.store16_lane",
+			v128_store32_lane(..) => "v128.store32_lane",
+			v128_store64_lane(..) => "v128.store64_lane",
+			v128_load32_zero(..) => "v128.load32_zero",
+			v128_load64_zero(..) => "v128.load64_zero",
+			f32x4_demote_f64x2_zero => "f32x4.demote_f64x2_zero",
+			f64x2_promote_low_f32x4 => "f64x2.promote_low_f32x4",
+			i8x16_abs => "i8x16.abs",
+			i8x16_neg => "i8x16.neg",
+			i8x16_popcnt => "i8x16.popcnt",
+			i8x16_all_true => "i8x16.all_true",
+			i8x16_bitmask => "i8x16.bitmask",
+			i8x16_narrow_i16x8_s => "i8x16.narrow_i16x8_s",
+			i8x16_narrow_i16x8_u => "i8x16.narrow_i16x8_u",
+			f32x4_ceil => "f32x4.ceil",
+			f32x4_floor => "f32x4.floor",
+			f32x4_trunc => "f32x4.trunc",
+			f32x4_nearest => "f32x4.nearest",
+			i8x16_shl => "i8x16.shl",
+			i8x16_shr_s => "i8x16.shr_s",
+			i8x16_shr_u => "i8x16.shr_u",
+			i8x16_add => "i8x16.add",
+			i8x16_add_sat_s => "i8x16.add_sat_s",
+			i8x16_add_sat_u => "i8x16.add_sat_u",
+			i8x16_sub => "i8x16.sub",
+			i8x16_sub_sat_s => "i8x16.sub_sat_s",
+			i8x16_sub_sat_u => "i8x16.sub_sat_u",
+			f64x2_ceil => "f64x2.ceil",
+			f64x2_floor => "f64x2.floor",
+			i8x16_min_s => "i8x16.min_s",
+			i8x16_min_u => "i8x16.min_u",
+			i8x16_max_s => "i8x16.max_s",
+			i8x16_max_u => "i8x16.max_u",
+			f64x2_trunc => "f64x2.trunc",
+			i8x16_avgr_u => "i8x16.avgr_u",
+			i16x8_extadd_pairwise_i8x16_s => "i16x8.extadd_pairwise_i8x16_s",
+			i16x8_extadd_pairwise_i8x16_u => "i16x8.extadd_pairwise_i8x16_u",
+			i32x4_extadd_pairwise_i16x8_s => "i32x4.extadd_pairwise_i16x8_s",
+			i32x4_extadd_pairwise_i16x8_u => "i32x4.extadd_pairwise_i16x8_u",
+			i16x8_abs => "i16x8.abs",
+			i16x8_neg => "i16x8.neg",
+			i16x8_q15mulr_sat_s => "i16x8.q15mulr_sat_s",
+			i16x8_all_true => "i16x8.all_true",
+			i16x8_bitmask => "i16x8.bitmask",
+			i16x8_narrow_i32x4_s => "i16x8.narrow_i32x4_s",
+			i16x8_narrow_i32x4_u => "i16x8.narrow_i32x4_u",
+			i16x8_extend_low_i8x16_s => "i16x8.extend_low_i8x16_s",
+			i16x8_extend_high_i8x16_s => "i16x8.extend_high_i8x16_s",
+			i16x8_extend_low_i8x16_u => "i16x8.extend_low_i8x16_u",
+			i16x8_extend_high_i8x16_u => "i16x8.extend_high_i8x16_u",
+			i16x8_shl => "i16x8.shl",
+			i16x8_shr_s => "i16x8.shr_s",
+			i16x8_shr_u => "i16x8.shr_u",
+			i16x8_add => "i16x8.add",
+			i16x8_add_sat_s => "i16x8.add_sat_s",
+			i16x8_add_sat_u => "i16x8.add_sat_u",
+			i16x8_sub => "i16x8.sub",
+			i16x8_sub_sat_s => "i16x8.sub_sat_s",
+			i16x8_sub_sat_u => "i16x8.sub_sat_u",
+			f64x2_nearest => "f64x2.nearest",
+			i16x8_mul => "i16x8.mul",
+			i16x8_min_s => "i16x8.min_s",
+			i16x8_min_u => "i16x8.min_u",
+			i16x8_max_s => "i16x8.max_s",
+			i16x8_max_u => "i16x8.max_u",
+			i16x8_avgr_u => "i16x8.avgr_u",
+			i16x8_extmul_low_i8x16_s => "i16x8.extmul_low_i8x16_s",
+			i16x8_extmul_high_i8x16_s => "i16x8.extmul_high_i8x16_s",
+			i16x8_extmul_low_i8x16_u => "i16x8.extmul_low_i8x16_u",
+			i16x8_extmul_high_i8x16_u => "i16x8.extmul_high_i8x16_u",
+			i32x4_abs => "i32x4.abs",
+			i32x4_neg => "i32x4.neg",
+			i32x4_all_true => "i32x4.all_true",
+			i32x4_bitmask => "i32x4.bitmask",
+			i32x4_extend_low_i16x8_s => "i32x4.extend_low_i16x8_s",
+			i32x4_extend_high_i16x8_s => "i32x4.extend_high_i16x8_s",
+			i32x4_extend_low_i16x8_u => "i32x4.extend_low_i16x8_u",
+			i32x4_extend_high_i16x8_u => "i32x4.extend_high_i16x8_u",
+			i32x4_shl => "i32x4.shl",
+			i32x4_shr_s => "i32x4.shr_s",
+			i32x4_shr_u => "i32x4.shr_u",
+			i32x4_add => "i32x4.add",
+			i32x4_sub => "i32x4.sub",
+			i32x4_mul => "i32x4.mul",
+			i32x4_min_s => "i32x4.min_s",
+			i32x4_min_u => "i32x4.min_u",
+			i32x4_max_s => "i32x4.max_s",
+			i32x4_max_u => "i32x4.max_u",
+			i32x4_dot_i16x8_s => "i32x4.dot_i16x8_s",
+			i32x4_extmul_low_i16x8_s => "i32x4.extmul_low_i16x8_s",
+			i32x4_extmul_high_i16x8_s => "i32x4.extmul_high_i16x8_s",
+			i32x4_extmul_low_i16x8_u => "i32x4.extmul_low_i16x8_u",
+			i32x4_extmul_high_i16x8_u => "i32x4.extmul_high_i16x8_u",
+			i64x2_abs => "i64x2.abs",
+			i64x2_neg => "i64x2.neg",
+			i64x2_all_true => "i64x2.all_true",
+			i64x2_bitmask => "i64x2.bitmask",
+			i64x2_extend_low_i32x4_s => "i64x2.extend_low_i32x4_s",
+			i64x2_extend_high_i32x4_s => "i64x2.extend_high_i32x4_s",
+			i64x2_extend_low_i32x4_u => "i64x2.extend_low_i32x4_u",
+			i64x2_extend_high_i32x4_u => "i64x2.extend_high_i32x4_u",
+			i64x2_shl => "i64x2.shl",
+			i64x2_shr_s => "i64x2.shr_s",
+			i64x2_shr_u => "i64x2.shr_u",
+			i64x2_add => "i64x2.add",
+			i64x2_sub => "i64x2.sub",
+			i64x2_mul => "i64x2.mul",
+			i64x2_eq => "i64x2.eq",
+			i64x2_ne => "i64x2.ne",
+			i64x2_lt_s => "i64x2.lt_s",
+			i64x2_gt_s => "i64x2.gt_s",
+			i64x2_le_s => "i64x2.le_s",
+			i64x2_ge_s => "i64x2.ge_s",
+			i64x2_extmul_low_i32x4_s => "i64x2.extmul_low_i32x4_s",
+			i64x2_extmul_high_i32x4_s => "i64x2.extmul_high_i32x4_s",
+			i64x2_extmul_low_i32x4_u => "i64x2.extmul_low_i32x4_u",
+			i64x2_extmul_high_i32x4_u => "i64x2.extmul_high_i32x4_u",
+			f32x4_abs => "f32x4.abs",
+			f32x4_neg => "f32x4.neg",
+			f32x4_sqrt => "f32x4.sqrt",
+			f32x4_add => "f32x4.add",
+			f32x4_sub => "f32x4.sub",
+			f32x4_mul => "f32x4.mul",
+			f32x4_div => "f32x4.div",
+			f32x4_min => "f32x4.min",
+			f32x4_max => "f32x4.max",
+			f32x4_pmin => "f32x4.pmin",
+			f32x4_pmax => "f32x4.pmax",
+			f64x2_abs => "f64x2.abs",
+			f64x2_neg => "f64x2.neg",
+			f64x2_sqrt => "f64x2.sqrt",
+			f64x2_add => "f64x2.add",
+			f64x2_sub => "f64x2.sub",
+			f64x2_mul => "f64x2.mul",
+			f64x2_div => "f64x2.div",
+			f64x2_min => "f64x2.min",
+			f64x2_max => "f64x2.max",
+			f64x2_pmin => "f64x2.pmin",
+			f64x2_pmax => "f64x2.pmax",
+			i32x4_trunc_sat_f32x4_s => "i32x4.trunc_sat_f32x4_s",
+			i32x4_trunc_sat_f32x4_u => "i32x4.trunc_sat_f32x4_u",
+			f32x4_convert_i32x4_s => "f32x4.convert_i32x4_s",
+			f32x4_convert_i32x4_u => "f32x4.convert_i32x4_u",
+			i32x4_trunc_sat_f64x2_s_zero => "i32x4.trunc_sat_f64x2_s_zero",
+			i32x4_trunc_sat_f64x2_u_zero => "i32x4.trunc_sat_f64x2_u_zero",
+			f64x2_convert_low_i32x4_s => "f64x2.convert_low_i32x4_s",
+			f64x2_convert_low_i32x4_u => "f64x2.convert_low_i32x4_u",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Self> {
+		use Instr::*;
+		Some(match name {
+			"unreachable" => unreachable,
+			"nop" => nop,
+			"block" => block(BlockType::Empty, vec![]),
+			"loop" => looped(BlockType::Empty, vec![]),
+			"if" => if_else(BlockType::Empty, vec![], vec![]),
+			"else" => then_end,
+			"end" => end,
+			"br" => br(0),
+			"br_if" => br_if(0),
+			"br_table" => br_table(vec![]),
+			"return" => return_result,
+			"call" => call(0),
+			"call_indirect" => call_indirect(0, 0),
+			"drop" => drop,
+			"select" => select,
+			"local.get" => local_get(0),
+			"local.set" => local_set(0),
+			"local.tee" => local_tee(0),
+			"global.get" => global_get(0),
+			"global.set" => global_set(0),
+			"table.get" => table_get(0),
+			"table.set" => table_set(0),
+			"i32.load" => i32_load(0, 0),
+			"i64.load" => i64_load(0, 0),
+			"f32.load" => f32_load(0, 0),
+			"f64.load" => f64_load(0, 0),
+			"i32.load8_s" => i32_load8_s(0, 0),
+			"i32.load8_u" => i32_load8_u(0, 0),
+			"i32.load16_s" => i32_load16_s(0, 0),
+			"i32.load16_u" => i32_load16_u(0, 0),
+			"i64.load8_s" => i64_load8_s(0, 0),
+			"i64.load8_u" => i64_load8_u(0, 0),
+			"i64.load16_s" => i64_load16_s(0, 0),
+			"i64.load16_u" => i64_load16_u(0, 0),
+			"i64.load32_s" => i64_load32_s(0, 0),
+			"i64.load32_u" => i64_load32_u(0, 0),
+			"i32.store" => i32_store(0, 0),
+			"i64.store" => i64_store(0, 0),
+			"f32.store" => f32_store(0, 0),
+			"f64.store" => f64_store(0, 0),
+			"i32.store8" => i32_store8(0, 0),
+			"i32.store16" => i32_store16(0, 0),
+			"i64.store8" => i64_store8(0, 0),
+			"i64.store16" => i64_store16(0, 0),
+			"i64.store32" => i64_store32(0, 0),
+			"memory.size" => memory_size,
+			"memory.grow" => memory_grow,
+			"i32.const" => i32_const(0),
+			"i64.const" => i64_const(0),
+			"f32.const" => f32_const(0.0),
+			"f64.const" => f64_const(0.0),
+			"i32.eqz" => i32_eqz,
+			"i32.eq" => i32_eq,
+			"i32.ne" => i32_ne,
+			"i32.lt_s" => i32_lt_s,
+			"i32.lt_u" => i32_lt_u,
+			"i32.gt_s" => i32_gt_s,
+			"i32.gt_u" => i32_gt_u,
+			"i32.le_s" => i32_le_s,
+			"i32.le_u" => i32_le_u,
+			"i32.ge_s" => i32_ge_s,
+			"i32.ge_u" => i32_ge_u,
+			"i64.eqz" => i64_eqz,
+			"i64.eq" => i64_eq,
+			"i64.ne" => i64_ne,
+			"i64.lt_s" => i64_lt_s,
+			"i64.lt_u" => i64_lt_u,
+			"i64.gt_s" => i64_gt_s,
+			"i64.gt_u" => i64_gt_u,
+			"i64.le_s" => i64_le_s,
+			"i64.le_u" => i64_le_u,
+			"i64.ge_s" => i64_ge_s,
+			"i64.ge_u" => i64_ge_u,
+			"f32.eq" => f32_eq,
+			"f32.ne" => f32_ne,
+			"f32.lt" => f32_lt,
+			"f32.gt" => f32_gt,
+			"f32.le" => f32_le,
+			"f32.ge" => f32_ge,
+			"f64.eq" => f64_eq,
+			"f64.ne" => f64_ne,
+			"f64.lt" => f64_lt,
+			"f64.gt" => f64_gt,
+			"f64.le" => f64_le,
+			"f64.ge" => f64_ge,
+			"i32.clz" => i32_clz,
+			"i32.ctz" => i32_ctz,
+			"i32.popcnt" => i32_popcnt,
+			"i32.add" => i32_add,
+			"i32.sub" => i32_sub,
+			"i32.mul" => i32_mul,
+			"i32.div_s" => i32_div_s,
+			"i32.div_u" => i32_div_u,
+			"i32.rem_s" => i32_rem_s,
+			"i32.rem_u" => i32_rem_u,
+			"i32.and" => i32_and,
+			"i32.or" => i32_or,
+			"i32.xor" => i32_xor,
+			"i32.shl" => i32_shl,
+			"i32.shr_s" => i32_shr_s,
+			"i32.shr_u" => i32_shr_u,
+			"i32.rotl" => i32_rotl,
+			"i32.rotr" => i32_rotr,
+			"i64.clz" => i64_clz,
+			"i64.ctz" => i64_ctz,
+			"i64.popcnt" => i64_popcnt,
+			"i64.add" => i64_add,
+			"i64.sub" => i64_sub,
+			"i64.mul" => i64_mul,
+			"i64.div_s" => i64_div_s,
+			"i64.div_u" => i64_div_u,
+			"i64.rem_s" => i64_rem_s,
+			"i64.rem_u" => i64_rem_u,
+			"i64.and" => i64_and,
+			"i64.or" => i64_or,
+			"i64.xor" => i64_xor,
+			"i64.shl" => i64_shl,
+			"i64.shr_s" => i64_shr_s,
+			"i64.shr_u" => i64_shr_u,
+			"i64.rotl" => i64_rotl,
+			"i64.rotr" => i64_rotr,
+			"f32.abs" => f32_abs,
+			"f32.neg" => f32_neg,
+			"f32.ceil" => f32_ceil,
+			"f32.floor" => f32_floor,
+			"f32.trunc" => f32_trunc,
+			"f32.nearest" => f32_nearest,
+			"f32.sqrt" => f32_sqrt,
+			"f32.add" => f32_add,
+			"f32.sub" => f32_sub,
+			"f32.mul" => f32_mul,
+			"f32.div" => f32_div,
+			"f32.min" => f32_min,
+			"f32.max" => f32_max,
+			"f32.copysign" => f32_copysign,
+			"f64.abs" => f64_abs,
+			"f64.neg" => f64_neg,
+			"f64.ceil" => f64_ceil,
+			"f64.floor" => f64_floor,
+			"f64.trunc" => f64_trunc,
+			"f64.nearest" => f64_nearest,
+			"f64.sqrt" => f64_sqrt,
+			"f64.add" => f64_add,
+			"f64.sub" => f64_sub,
+			"f64.mul" => f64_mul,
+			"f64.div" => f64_div,
+			"f64.min" => f64_min,
+			"f64.max" => f64_max,
+			"f64.copysign" => f64_copysign,
+			"i32.wrap_i64" => i32_wrap_i64,
+			"i32.trunc_f32_s" => i32_trunc_f32_s,
+			"i32.trunc_f32_u" => i32_trunc_f32_u,
+			"i32.trunc_f64_s" => i32_trunc_f64_s,
+			"i32.trunc_f64_u" => i32_trunc_f64_u,
+			"i64.extend_i32_s" => i64_extend_i32_s,
+			"i64.extend_i32_u" => i64_extend_i32_u,
+			"i64.trunc_f32_s" => i64_trunc_f32_s,
+			"i64.trunc_f32_u" => i64_trunc_f32_u,
+			"i64.trunc_f64_s" => i64_trunc_f64_s,
+			"i64.trunc_f64_u" => i64_trunc_f64_u,
+			"f32.convert_i32_s" => f32_convert_i32_s,
+			"f32.convert_i32_u" => f32_convert_i32_u,
+			"f32.convert_i64_s" => f32_convert_i64_s,
+			"f32.convert_i64_u" => f32_convert_i64_u,
+			"f32.demote_f64" => f32_demote_f64,
+			"f64.convert_i32_s" => f64_convert_i32_s,
+			"f64.convert_i32_u" => f64_convert_i32_u,
+			"f64.convert_i64_s" => f64_convert_i64_s,
+			"f64.convert_i64_u" => f64_convert_i64_u,
+			"f64.promote_f32" => f64_promote_f32,
+			"i32.reinterpret_f32" => i32_reinterpret_f32,
+			"i64.reinterpret_f64" => i64_reinterpret_f64,
+			"f32.reinterpret_i32" => f32_reinterpret_i32,
+			"f64.reinterpret_i64" => f64_reinterpret_i64,
+			"i32.extend8_s" => i32_extend8_s,
+			"i32.extend16_s" => i32_extend16_s,
+			"i64.extend8_s" => i64_extend8_s,
+			"i64.extend16_s" => i64_extend16_s,
+			"i64.extend32_s" => i64_extend32_s,
+			"ref.null" => ref_null(DataType::FuncRef),
+			"ref.is_null" => ref_is_null,
+			"ref.func" => ref_func(0),
+			"i32.trunc_sat_f32_s" => i32_trunc_sat_f32_s,
+			"i32.trunc_sat_f32_u" => i32_trunc_sat_f32_u,
+			"i32.trunc_sat_f64_s" => i32_trunc_sat_f64_s,
+			"i32.trunc_sat_f64_u" => i32_trunc_sat_f64_u,
+			"i64.trunc_sat_f32_s" => i64_trunc_sat_f32_s,
+			"i64.trunc_sat_f32_u" => i64_trunc_sat_f32_u,
+			"i64.trunc_sat_f64_s" => i64_trunc_sat_f64_s,
+			"i64.trunc_sat_f64_u" => i64_trunc_sat_f64_u,
+			"memory.init" => memory_init(0, 0),
+			"data.drop" => data_drop(0),
+			"memory.copy" => memory_copy(0, 0),
+			"memory.fill" => memory_fill(0),
+			"table.init" => table_init(0, 0),
+			"elem.drop" => elem_drop(0),
+			"table.copy" => table_copy(0, 0),
+			"table.grow" => table_grow(0),
+			"table.size" => table_size(0),
+			"table.fill" => table_fill(0),
+			"v128.load" => v128_load(0, 0),
+			"v128.load8x8_s" => v128_load8x8_s(0, 0),
+			"v128.load8x8_u" => v128_load8x8_u(0, 0),
+			"v128.load16x4_s" => v128_load16x4_s(0, 0),
+			"v128.load16x4_u" => v128_load16x4_u(0, 0),
+			"v128.load32x2_s" => v128_load32x2_s(0, 0),
+			"v128.load32x2_u" => v128_load32x2_u(0, 0),
+			"v128.load8_splat" => v128_load8_splat(0, 0),
+			"v128.load16_splat" => v128_load16_splat(0, 0),
+			"v128.load32_splat" => v128_load32_splat(0, 0),
+			"v128.load64_splat" => v128_load64_splat(0, 0),
+			"v128.store" => v128_store(0, 0),
+			"v128.const" => v128_const(0),
+			"i8x16.shuffle" => i8x16_shuffle([0; 16]),
+			"i8x16.swizzle" => i8x16_swizzle,
+			"i8x16.splat" => i8x16_splat,
+			"i16x8.splat" => i16x8_splat,
+			"i32x4.splat" => i32x4_splat,
+			"i64x2.splat" => i64x2_splat,
+			"f32x4.splat" => f32x4_splat,
+			"f64x2.splat" => f64x2_splat,
+			"i8x16.extract_lane_s" => i8x16_extract_lane_s(0),
+			"i8x16.extract_lane_u" => i8x16_extract_lane_u(0),
+			"i8x16.replace_lane" => i8x16_replace_lane(0),
+			"i16x8.extract_lane_s" => i16x8_extract_lane_s(0),
+			"i16x8.extract_lane_u" => i16x8_extract_lane_u(0),
+			"i16x8.replace_lane" => i16x8_replace_lane(0),
+			"i32x4.extract_lane" => i32x4_extract_lane(0),
+			"i32x4.replace_lane" => i32x4_replace_lane(0),
+			"i64x2.extract_lane" => i64x2_extract_lane(0),
+			"i64x2.replace_lane" => i64x2_replace_lane(0),
+			"f32x4.extract_lane" => f32x4_extract_lane(0),
+			"f32x4.replace_lane" => f32x4_replace_lane(0),
+			"f64x2.extract_lane" => f64x2_extract_lane(0),
+			"f64x2.replace_lane" => f64x2_replace_lane(0),
+			"i8x16.eq" => i8x16_eq,
+			"i8x16.ne" => i8x16_ne,
+			"i8x16.lt_s" => i8x16_lt_s,
+			"i8x16.lt_u" => i8x16_lt_u,
+			"i8x16.gt_s" => i8x16_gt_s,
+			"i8x16.gt_u" => i8x16_gt_u,
+			"i8x16.le_s" => i8x16_le_s,
+			"i8x16.le_u" => i8x16_le_u,
+			"i8x16.ge_s" => i8x16_ge_s,
+			"i8x16.ge_u" => i8x16_ge_u,
+			"i16x8.eq" => i16x8_eq,
+			"i16x8.ne" => i16x8_ne,
+			"i16x8.lt_s" => i16x8_lt_s,
+			"i16x8.lt_u" => i16x8_lt_u,
+			"i16x8.gt_s" => i16x8_gt_s,
+			"i16x8.gt_u" => i16x8_gt_u,
+			"i16x8.le_s" => i16x8_le_s,
+			"i16x8.le_u" => i16x8_le_u,
+			"i16x8.ge_s" => i16x8_ge_s,
+			"i16x8.ge_u" => i16x8_ge_u,
+			"i32x4.eq" => i32x4_eq,
+			"i32x4.ne" => i32x4_ne,
+			"i32x4.lt_s" => i32x4_lt_s,
+			"i32x4.lt_u" => i32x4_lt_u,
+			"i32x4.gt_s" => i32x4_gt_s,
+			"i32x4.gt_u" => i32x4_gt_u,
+			"i32x4.le_s" => i32x4_le_s,
+			"i32x4.le_u" => i32x4_le_u,
+			"i32x4.ge_s" => i32x4_ge_s,
+			"i32x4.ge_u" => i32x4_ge_u,
+			"f32x4.eq" => f32x4_eq,
+			"f32x4.ne" => f32x4_ne,
+			"f32x4.lt" => f32x4_lt,
+			"f32x4.gt" => f32x4_gt,
+			"f32x4.le" => f32x4_le,
+			"f32x4.ge" => f32x4_ge,
+			"f64x2.eq" => f64x2_eq,
+			"f64x2.ne" => f64x2_ne,
+			"f64x2.lt" => f64x2_lt,
+			"f64x2.gt" => f64x2_gt,
+			"f64x2.le" => f64x2_le,
+			"f64x2.ge" => f64x2_ge,
+			"v128.not" => v128_not,
+			"v128.and" => v128_and,
+			"v128.andnot" => v128_andnot,
+			"v128.or" => v128_or,
+			"v128.xor" => v128_xor,
+			"v128.bitselect" => v128_bitselect,
+			"v128.any_true" => v128_any_true,
+			"v128.load8_lane" => v128_load8_lane(0, 0, 0),
+			"v128.load16_lane" => v128_load16_lane(0, 0, 0),
+			"v128.load32_lane" => v128_load32_lane(0, 0, 0),
+			"v128.load64_lane" => v128_load64_lane(0, 0, 0),
+			"v128.store8_lane" => v128_store8_lane(0, 0, 0),
+			"v128.store16_lane" => v128_store16_lane(0, 0, 0),
+			"v128.store32_lane" => v128_store32_lane(0, 0, 0),
+			"v128.store64_lane" => v128_store64_lane(0, 0, 0),
+			"v128.load32_zero" => v128_load32_zero(0, 0),
+			"v128.load64_zero" => v128_load64_zero(0, 0),
+			"f32x4.demote_f64x2_zero" => f32x4_demote_f64x2_zero,
+			"f64x2.promote_low_f32x4" => f64x2_promote_low_f32x4,
+			"i8x16.abs" => i8x16_abs,
+			"i8x16.neg" => i8x16_neg,
+			"i8x16.popcnt" => i8x16_popcnt,
+			"i8x16.all_true" => i8x16_all_true,
+			"i8x16.bitmask" => i8x16_bitmask,
+			"i8x16.narrow_i16x8_s" => i8x16_narrow_i16x8_s,
+			"i8x16.narrow_i16x8_u" => i8x16_narrow_i16x8_u,
+			"f32x4.ceil" => f32x4_ceil,
+			"f32x4.floor" => f32x4_floor,
+			"f32x4.trunc" => f32x4_trunc,
+			"f32x4.nearest" => f32x4_nearest,
+			"i8x16.shl" => i8x16_shl,
+			"i8x16.shr_s" => i8x16_shr_s,
+			"i8x16.shr_u" => i8x16_shr_u,
+			"i8x16.add" => i8x16_add,
+			"i8x16.add_sat_s" => i8x16_add_sat_s,
+			"i8x16.add_sat_u" => i8x16_add_sat_u,
+			"i8x16.sub" => i8x16_sub,
+			"i8x16.sub_sat_s" => i8x16_sub_sat_s,
+			"i8x16.sub_sat_u" => i8x16_sub_sat_u,
+			"f64x2.ceil" => f64x2_ceil,
+			"f64x2.floor" => f64x2_floor,
+			"i8x16.min_s" => i8x16_min_s,
+			"i8x16.min_u" => i8x16_min_u,
+			"i8x16.max_s" => i8x16_max_s,
+			"i8x16.max_u" => i8x16_max_u,
+			"f64x2.trunc" => f64x2_trunc,
+			"i8x16.avgr_u" => i8x16_avgr_u,
+			"i16x8.extadd_pairwise_i8x16_s" => i16x8_extadd_pairwise_i8x16_s,
+			"i16x8.extadd_pairwise_i8x16_u" => i16x8_extadd_pairwise_i8x16_u,
+			"i32x4.extadd_pairwise_i16x8_s" => i32x4_extadd_pairwise_i16x8_s,
+			"i32x4.extadd_pairwise_i16x8_u" => i32x4_extadd_pairwise_i16x8_u,
+			"i16x8.abs" => i16x8_abs,
+			"i16x8.neg" => i16x8_neg,
+			"i16x8.q15mulr_sat_s" => i16x8_q15mulr_sat_s,
+			"i16x8.all_true" => i16x8_all_true,
+			"i16x8.bitmask" => i16x8_bitmask,
+			"i16x8.narrow_i32x4_s" => i16x8_narrow_i32x4_s,
+			"i16x8.narrow_i32x4_u" => i16x8_narrow_i32x4_u,
+			"i16x8.extend_low_i8x16_s" => i16x8_extend_low_i8x16_s,
+			"i16x8.extend_high_i8x16_s" => i16x8_extend_high_i8x16_s,
+			"i16x8.extend_low_i8x16_u" => i16x8_extend_low_i8x16_u,
+			"i16x8.extend_high_i8x16_u" => i16x8_extend_high_i8x16_u,
+			"i16x8.shl" => i16x8_shl,
+			"i16x8.shr_s" => i16x8_shr_s,
+			"i16x8.shr_u" => i16x8_shr_u,
+			"i16x8.add" => i16x8_add,
+			"i16x8.add_sat_s" => i16x8_add_sat_s,
+			"i16x8.add_sat_u" => i16x8_add_sat_u,
+			"i16x8.sub" => i16x8_sub,
+			"i16x8.sub_sat_s" => i16x8_sub_sat_s,
+			"i16x8.sub_sat_u" => i16x8_sub_sat_u,
+			"f64x2.nearest" => f64x2_nearest,
+			"i16x8.mul" => i16x8_mul,
+			"i16x8.min_s" => i16x8_min_s,
+			"i16x8.min_u" => i16x8_min_u,
+			"i16x8.max_s" => i16x8_max_s,
+			"i16x8.max_u" => i16x8_max_u,
+			"i16x8.avgr_u" => i16x8_avgr_u,
+			"i16x8.extmul_low_i8x16_s" => i16x8_extmul_low_i8x16_s,
+			"i16x8.extmul_high_i8x16_s" => i16x8_extmul_high_i8x16_s,
+			"i16x8.extmul_low_i8x16_u" => i16x8_extmul_low_i8x16_u,
+			"i16x8.extmul_high_i8x16_u" => i16x8_extmul_high_i8x16_u,
+			"i32x4.abs" => i32x4_abs,
+			"i32x4.neg" => i32x4_neg,
+			"i32x4.all_true" => i32x4_all_true,
+			"i32x4.bitmask" => i32x4_bitmask,
+			"i32x4.extend_low_i16x8_s" => i32x4_extend_low_i16x8_s,
+			"i32x4.extend_high_i16x8_s" => i32x4_extend_high_i16x8_s,
+			"i32x4.extend_low_i16x8_u" => i32x4_extend_low_i16x8_u,
+			"i32x4.extend_high_i16x8_u" => i32x4_extend_high_i16x8_u,
+			"i32x4.shl" => i32x4_shl,
+			"i32x4.shr_s" => i32x4_shr_s,
+			"i32x4.shr_u" => i32x4_shr_u,
+			"i32x4.add" => i32x4_add,
+			"i32x4.sub" => i32x4_sub,
+			"i32x4.mul" => i32x4_mul,
+			"i32x4.min_s" => i32x4_min_s,
+			"i32x4.min_u" => i32x4_min_u,
+			"i32x4.max_s" => i32x4_max_s,
+			"i32x4.max_u" => i32x4_max_u,
+			"i32x4.dot_i16x8_s" => i32x4_dot_i16x8_s,
+			"i32x4.extmul_low_i16x8_s" => i32x4_extmul_low_i16x8_s,
+			"i32x4.extmul_high_i16x8_s" => i32x4_extmul_high_i16x8_s,
+			"i32x4.extmul_low_i16x8_u" => i32x4_extmul_low_i16x8_u,
+			"i32x4.extmul_high_i16x8_u" => i32x4_extmul_high_i16x8_u,
+			"i64x2.abs" => i64x2_abs,
+			"i64x2.neg" => i64x2_neg,
+			"i64x2.all_true" => i64x2_all_true,
+			"i64x2.bitmask" => i64x2_bitmask,
+			"i64x2.extend_low_i32x4_s" => i64x2_extend_low_i32x4_s,
+			"i64x2.extend_high_i32x4_s" => i64x2_extend_high_i32x4_s,
+			"i64x2.extend_low_i32x4_u" => i64x2_extend_low_i32x4_u,
+			"i64x2.extend_high_i32x4_u" => i64x2_extend_high_i32x4_u,
+			"i64x2.shl" => i64x2_shl,
+			"i64x2.shr_s" => i64x2_shr_s,
+			"i64x2.shr_u" => i64x2_shr_u,
+			"i64x2.add" => i64x2_add,
+			"i64x2.sub" => i64x2_sub,
+			"i64x2.mul" => i64x2_mul,
+			"i64x2.eq" => i64x2_eq,
+			"i64x2.ne" => i64x2_ne,
+			"i64x2.lt_s" => i64x2_lt_s,
+			"i64x2.gt_s" => i64x2_gt_s,
+			"i64x2.le_s" => i64x2_le_s,
+			"i64x2.ge_s" => i64x2_ge_s,
+			"i64x2.extmul_low_i32x4_s" => i64x2_extmul_low_i32x4_s,
+			"i64x2.extmul_high_i32x4_s" => i64x2_extmul_high_i32x4_s,
+			"i64x2.extmul_low_i32x4_u" => i64x2_extmul_low_i32x4_u,
+			"i64x2.extmul_high_i32x4_u" => i64x2_extmul_high_i32x4_u,
+			"f32x4.abs" => f32x4_abs,
+			"f32x4.neg" => f32x4_neg,
+			"f32x4.sqrt" => f32x4_sqrt,
+			"f32x4.add" => f32x4_add,
+			"f32x4.sub" => f32x4_sub,
+			"f32x4.mul" => f32x4_mul,
+			"f32x4.div" => f32x4_div,
+			"f32x4.min" => f32x4_min,
+			"f32x4.max" => f32x4_max,
+			"f32x4.pmin" => f32x4_pmin,
+			"f32x4.pmax" => f32x4_pmax,
+			"f64x2.abs" => f64x2_abs,
+			"f64x2.neg" => f64x2_neg,
+			"f64x2.sqrt" => f64x2_sqrt,
+			"f64x2.add" => f64x2_add,
+			"f64x2.sub" => f64x2_sub,
+			"f64x2.mul" => f64x2_mul,
+			"f64x2.div" => f64x2_div,
+			"f64x2.min" => f64x2_min,
+			"f64x2.max" => f64x2_max,
+			"f64x2.pmin" => f64x2_pmin,
+			"f64x2.pmax" => f64x2_pmax,
+			"i32x4.trunc_sat_f32x4_s" => i32x4_trunc_sat_f32x4_s,
+			"i32x4.trunc_sat_f32x4_u" => i32x4_trunc_sat_f32x4_u,
+			"f32x4.convert_i32x4_s" => f32x4_convert_i32x4_s,
+			"f32x4.convert_i32x4_u" => f32x4_convert_i32x4_u,
+			"i32x4.trunc_sat_f64x2_s_zero" => i32x4_trunc_sat_f64x2_s_zero,
+			"i32x4.trunc_sat_f64x2_u_zero" => i32x4_trunc_sat_f64x2_u_zero,
+			"f64x2.convert_low_i32x4_s" => f64x2_convert_low_i32x4_s,
+			"f64x2.convert_low_i32x4_u" => f64x2_convert_low_i32x4_u,
+			_ => return None,
+		})
+	}
+
+	pub fn memarg_mut(&mut self) -> Option<(&mut u32, &mut u32)> {
+		use Instr::*;
+		match self {
+			i32_load(align, offset) => Some((align, offset)),
+			i64_load(align, offset) => Some((align, offset)),
+			f32_load(align, offset) => Some((align, offset)),
+			f64_load(align, offset) => Some((align, offset)),
+			i32_load8_s(align, offset) => Some((align, offset)),
+			i32_load8_u(align, offset) => Some((align, offset)),
+			i32_load16_s(align, offset) => Some((align, offset)),
+			i32_load16_u(align, offset) => Some((align, offset)),
+			i64_load8_s(align, offset) => Some((align, offset)),
+			i64_load8_u(align, offset) => Some((align, offset)),
+			i64_load16_s(align, offset) => Some((align, offset)),
+			i64_load16_u(align, offset) => Some((align, offset)),
+			i64_load32_s(align, offset) => Some((align, offset)),
+			i64_load32_u(align, offset) => Some((align, offset)),
+			i32_store(align, offset) => Some((align, offset)),
+			i64_store(align, offset) => Some((align, offset)),
+			f32_store(align, offset) => Some((align, offset)),
+			f64_store(align, offset) => Some((align, offset)),
+			i32_store8(align, offset) => Some((align, offset)),
+			i32_store16(align, offset) => Some((align, offset)),
+			i64_store8(align, offset) => Some((align, offset)),
+			i64_store16(align, offset) => Some((align, offset)),
+			i64_store32(align, offset) => Some((align, offset)),
+			v128_load(align, offset) => Some((align, offset)),
+			v128_load8x8_s(align, offset) => Some((align, offset)),
+			v128_load8x8_u(align, offset) => Some((align, offset)),
+			v128_load16x4_s(align, offset) => Some((align, offset)),
+			v128_load16x4_u(align, offset) => Some((align, offset)),
+			v128_load32x2_s(align, offset) => Some((align, offset)),
+			v128_load32x2_u(align, offset) => Some((align, offset)),
+			v128_load8_splat(align, offset) => Some((align, offset)),
+			v128_load16_splat(align, offset) => Some((align, offset)),
+			v128_load32_splat(align, offset) => Some((align, offset)),
+			v128_load64_splat(align, offset) => Some((align, offset)),
+			v128_store(align, offset) => Some((align, offset)),
+			v128_load8_lane(align, offset, ..) => Some((align, offset)),
+			v128_load16_lane(align, offset, ..) => Some((align, offset)),
+			v128_load32_lane(align, offset, ..) => Some((align, offset)),
+			v128_load64_lane(align, offset, ..) => Some((align, offset)),
+			v128_store8_lane(align, offset, ..) => Some((align, offset)),
+			v128_store16_lane(align, offset, ..) => Some((align, offset)),
+			v128_store32_lane(align, offset, ..) => Some((align, offset)),
+			v128_store64_lane(align, offset, ..) => Some((align, offset)),
+			v128_load32_zero(align, offset) => Some((align, offset)),
+			v128_load64_zero(align, offset) => Some((align, offset)),
+			_ => None,
+		}
+	}
+
+	pub fn lane_mut(&mut self) -> Option<&mut u8> {
+		use Instr::*;
+		match self {
+			i8x16_extract_lane_s(lane) => Some(lane),
+			i8x16_extract_lane_u(lane) => Some(lane),
+			i8x16_replace_lane(lane) => Some(lane),
+			i16x8_extract_lane_s(lane) => Some(lane),
+			i16x8_extract_lane_u(lane) => Some(lane),
+			i16x8_replace_lane(lane) => Some(lane),
+			i32x4_extract_lane(lane) => Some(lane),
+			i32x4_replace_lane(lane) => Some(lane),
+			i64x2_extract_lane(lane) => Some(lane),
+			i64x2_replace_lane(lane) => Some(lane),
+			f32x4_extract_lane(lane) => Some(lane),
+			f32x4_replace_lane(lane) => Some(lane),
+			f64x2_extract_lane(lane) => Some(lane),
+			f64x2_replace_lane(lane) => Some(lane),
+			v128_load8_lane(_, _, lane) => Some(lane),
+			v128_load16_lane(_, _, lane) => Some(lane),
+			v128_load32_lane(_, _, lane) => Some(lane),
+			v128_load64_lane(_, _, lane) => Some(lane),
+			v128_store8_lane(_, _, lane) => Some(lane),
+			v128_store16_lane(_, _, lane) => Some(lane),
+			v128_store32_lane(_, _, lane) => Some(lane),
+			v128_store64_lane(_, _, lane) => Some(lane),
+			_ => None,
+		}
+	}
 }
@@ -0,0 +1,1457 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::instruction::Instr;
+use super::memory::Memory;
+use super::{BlockType, DataMode, DataType, ElementMode, FunctionType, GlobalType, Wasm};
+
+// Calls and nested blocks both recurse on the host stack, so they share one budget.
+const MAX_DEPTH: usize = 256;
+const MAX_TABLE_SIZE: u32 = 10_000_000;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Value {
+	I32(i32),
+	I64(i64),
+	F32(f32),
+	F64(f64),
+	V128(u128),
+	FuncRef(Option<usize>),
+	ExternRef(Option<usize>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Trap {
+	Unreachable,
+	DivisionByZero,
+	IntegerOverflow,
+	InvalidConversion,
+	MemoryOutOfBounds,
+	TableOutOfBounds,
+	UndefinedElement,
+	UninitializedElement,
+	IndirectCallTypeMismatch,
+	ImmutableGlobal,
+	StackOverflow,
+	TypeMismatch,
+	UnknownExport(String),
+	UnlinkedImport(String, String),
+	IncompatibleImport(String),
+	Exit(i32),
+	Unsupported(&'static str),
+}
+
+impl fmt::Display for Trap {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Trap::Unreachable => write!(f, "unreachable executed"),
+			Trap::DivisionByZero => write!(f, "integer divide by zero"),
+			Trap::IntegerOverflow => write!(f, "integer overflow"),
+			Trap::InvalidConversion => write!(f, "invalid conversion to integer"),
+			Trap::MemoryOutOfBounds => write!(f, "out of bounds memory access"),
+			Trap::TableOutOfBounds => write!(f, "out of bounds table access"),
+			Trap::UndefinedElement => write!(f, "undefined element"),
+			Trap::UninitializedElement => write!(f, "uninitialized element"),
+			Trap::IndirectCallTypeMismatch => write!(f, "indirect call type mismatch"),
+			Trap::ImmutableGlobal => write!(f, "global is immutable"),
+			Trap::StackOverflow => write!(f, "call stack exhausted"),
+			Trap::TypeMismatch => write!(f, "type mismatch"),
+			Trap::UnknownExport(name) => write!(f, "unknown export: {}", name),
+			Trap::UnlinkedImport(module, name) => write!(f, "unlinked import: {}.{}", module, name),
+			Trap::IncompatibleImport(name) => write!(f, "incompatible import type: {}", name),
+			Trap::Exit(code) => write!(f, "exited with code {}", code),
+			Trap::Unsupported(name) => write!(f, "unsupported instruction: {}", name),
+		}
+	}
+}
+
+impl Value {
+	pub fn zero(typ: &DataType) -> Value {
+		match typ {
+			DataType::I32 => Value::I32(0),
+			DataType::I64 => Value::I64(0),
+			DataType::F32 => Value::F32(0.0),
+			DataType::F64 => Value::F64(0.0),
+			DataType::V128 => Value::V128(0),
+			DataType::FuncRef => Value::FuncRef(None),
+			DataType::ExternRef => Value::ExternRef(None),
+		}
+	}
+
+	pub fn typ(&self) -> DataType {
+		match self {
+			Value::I32(_) => DataType::I32,
+			Value::I64(_) => DataType::I64,
+			Value::F32(_) => DataType::F32,
+			Value::F64(_) => DataType::F64,
+			Value::V128(_) => DataType::V128,
+			Value::FuncRef(_) => DataType::FuncRef,
+			Value::ExternRef(_) => DataType::ExternRef,
+		}
+	}
+
+	fn reference(typ: &DataType, target: Option<usize>) -> Value {
+		match typ {
+			DataType::ExternRef => Value::ExternRef(target),
+			_ => Value::FuncRef(target),
+		}
+	}
+
+	fn target(self) -> Result<Option<usize>, Trap> {
+		match self {
+			Value::FuncRef(target) | Value::ExternRef(target) => Ok(target),
+			_ => Err(Trap::TypeMismatch),
+		}
+	}
+}
+
+impl fmt::Display for Value {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Value::I32(x) => write!(f, "{}:i32", x),
+			Value::I64(x) => write!(f, "{}:i64", x),
+			Value::F32(x) => write!(f, "{}:f32", x),
+			Value::F64(x) => write!(f, "{}:f64", x),
+			Value::V128(x) => write!(f, "{:#034x}:v128", x),
+			Value::FuncRef(None) | Value::ExternRef(None) => write!(f, "null:{}", self.typ()),
+			Value::FuncRef(Some(x)) | Value::ExternRef(Some(x)) => {
+				write!(f, "{}:{}", x, self.typ())
+			}
+		}
+	}
+}
+
+trait FromValue: Sized {
+	fn from_value(value: Value) -> Option<Self>;
+}
+
+macro_rules! value_conversions {
+	($($type:ty => $variant:ident),*) => {$(
+		impl FromValue for $type {
+			fn from_value(value: Value) -> Option<Self> {
+				match value {
+					Value::$variant(x) => Some(x as $type),
+					_ => None,
+				}
+			}
+		}
+		impl From<$type> for Value {
+			fn from(x: $type) -> Value {
+				Value::$variant(x as _)
+			}
+		}
+	)*};
+}
+
+value_conversions!(
+	i32 => I32, u32 => I32, i64 => I64, u64 => I64, f32 => F32, f64 => F64, u128 => V128
+);
+
+impl From<bool> for Value {
+	fn from(x: bool) -> Value {
+		Value::I32(x as i32)
+	}
+}
+
+#[derive(Default)]
+struct Stack {
+	values: Vec<Value>,
+}
+
+impl Stack {
+	fn push(&mut self, value: impl Into<Value>) {
+		self.values.push(value.into());
+	}
+
+	fn pop_value(&mut self) -> Result<Value, Trap> {
+		self.values.pop().ok_or(Trap::TypeMismatch)
+	}
+
+	fn pop<T: FromValue>(&mut self) -> Result<T, Trap> {
+		T::from_value(self.pop_value()?).ok_or(Trap::TypeMismatch)
+	}
+
+	fn unary<A: FromValue, R: Into<Value>>(&mut self, f: impl FnOnce(A) -> R) -> Result<(), Trap> {
+		let a = self.pop()?;
+		self.push(f(a));
+		Ok(())
+	}
+
+	fn binary<A: FromValue, R: Into<Value>>(
+		&mut self,
+		f: impl FnOnce(A, A) -> R,
+	) -> Result<(), Trap> {
+		let b = self.pop()?;
+		let a = self.pop()?;
+		self.push(f(a, b));
+		Ok(())
+	}
+
+	fn try_unary<A: FromValue, R: Into<Value>>(
+		&mut self,
+		f: impl FnOnce(A) -> Result<R, Trap>,
+	) -> Result<(), Trap> {
+		let a = self.pop()?;
+		self.push(f(a)?);
+		Ok(())
+	}
+
+	fn try_binary<A: FromValue, R: Into<Value>>(
+		&mut self,
+		f: impl FnOnce(A, A) -> Result<R, Trap>,
+	) -> Result<(), Trap> {
+		let b = self.pop()?;
+		let a = self.pop()?;
+		self.push(f(a, b)?);
+		Ok(())
+	}
+
+	fn keep(&mut self, height: usize, arity: usize) -> Result<(), Trap> {
+		let top = self.values.len().checked_sub(arity).filter(|&top| top >= height);
+		self.values.drain(height..top.ok_or(Trap::TypeMismatch)?);
+		Ok(())
+	}
+}
+
+enum Flow {
+	Next,
+	Branch(usize),
+	Return,
+}
+
+struct Frame {
+	locals: Vec<Value>,
+	stack: Stack,
+}
+
+// Kept out of `execute`, whose stack frame decides how much host stack `MAX_DEPTH` needs.
+fn variable(locals: &mut [Value], stack: &mut Stack, instr: &Instr) -> Result<(), Trap> {
+	match instr {
+		Instr::local_get(index) => {
+			stack.push(*locals.get(*index as usize).ok_or(Trap::TypeMismatch)?);
+		}
+		Instr::local_set(index) | Instr::local_tee(index) => {
+			let value = stack.pop_value()?;
+			if matches!(instr, Instr::local_tee(_)) {
+				stack.push(value);
+			}
+			*locals.get_mut(*index as usize).ok_or(Trap::TypeMismatch)? = value;
+		}
+		_ => unreachable!(),
+	}
+	Ok(())
+}
+
+pub struct Table {
+	pub typ: DataType,
+	pub elements: Vec<Option<usize>>,
+	pub max: Option<u32>,
+}
+
+impl Table {
+	fn execute(&mut self, stack: &mut Stack, instr: &Instr) -> Result<(), Trap> {
+		use Instr::*;
+		match instr {
+			table_get(_) => {
+				let index = stack.pop::<u32>()? as usize;
+				let target = self.elements.get(index).ok_or(Trap::TableOutOfBounds)?;
+				stack.push(Value::reference(&self.typ, *target));
+			}
+			table_set(_) => {
+				let target = stack.pop_value()?.target()?;
+				let index = stack.pop::<u32>()? as usize;
+				*self.elements.get_mut(index).ok_or(Trap::TableOutOfBounds)? = target;
+			}
+			table_grow(_) => {
+				let delta = stack.pop::<u32>()?;
+				let target = stack.pop_value()?.target()?;
+				stack.push(self.grow(delta, target).map_or(-1, |size| size as i32));
+			}
+			table_size(_) => stack.push(self.elements.len() as u32),
+			table_fill(_) => {
+				let length = stack.pop::<u32>()? as usize;
+				let target = stack.pop_value()?.target()?;
+				let offset = stack.pop::<u32>()? as usize;
+				let limit = offset.checked_add(length);
+				let slots = limit.and_then(|limit| self.elements.get_mut(offset..limit));
+				slots.ok_or(Trap::TableOutOfBounds)?.fill(target);
+			}
+			_ => unreachable!(),
+		}
+		Ok(())
+	}
+
+	pub fn grow(&mut self, delta: u32, target: Option<usize>) -> Option<u32> {
+		let size = self.elements.len() as u32;
+		let new_size = size.checked_add(delta)?;
+		if new_size > self.max.unwrap_or(MAX_TABLE_SIZE).min(MAX_TABLE_SIZE) {
+			return None;
+		}
+		self.elements.resize(new_size as usize, target);
+		Some(size)
+	}
+
+	fn write(&mut self, offset: u32, elements: Vec<Option<usize>>) -> Result<(), Trap> {
+		let end = (offset as usize).checked_add(elements.len());
+		let slots = end.and_then(|end| self.elements.get_mut(offset as usize..end));
+		slots.ok_or(Trap::TableOutOfBounds)?.clone_from_slice(&elements);
+		Ok(())
+	}
+}
+
+fn matches_types(values: &[Value], types: &[DataType]) -> bool {
+	values.len() == types.len() && values.iter().zip(types).all(|(value, typ)| value.typ() == *typ)
+}
+
+fn segment<T: Clone>(items: &[T], start: u32, length: usize) -> Option<Vec<T>> {
+	let end = (start as usize).checked_add(length)?;
+	items.get(start as usize..end).map(<[T]>::to_vec)
+}
+
+type HostFunction = Box<dyn FnMut(&mut [Memory], &[Value]) -> Result<Vec<Value>, Trap>>;
+
+#[derive(Default)]
+pub struct Imports {
+	functions: HashMap<(String, String), (FunctionType, HostFunction)>,
+	globals: HashMap<(String, String), (GlobalType, Value)>,
+}
+
+impl Imports {
+	pub fn new() -> Imports {
+		Imports::default()
+	}
+
+	pub fn function(
+		&mut self,
+		module: &str,
+		name: &str,
+		params: &[DataType],
+		results: &[DataType],
+		function: impl FnMut(&mut [Memory], &[Value]) -> Result<Vec<Value>, Trap> + 'static,
+	) -> &mut Self {
+		let typ = FunctionType { params: params.to_vec(), resuls: results.to_vec() };
+		self.functions.insert((module.to_string(), name.to_string()), (typ, Box::new(function)));
+		self
+	}
+
+	pub fn global(&mut self, module: &str, name: &str, typ: GlobalType, value: Value) -> &mut Self {
+		self.globals.insert((module.to_string(), name.to_string()), (typ, value));
+		self
+	}
+}
+
+pub struct Instance<'a> {
+	pub wasm: &'a Wasm,
+	pub memories: Vec<Memory>,
+	pub globals: Vec<Value>,
+	pub tables: Vec<Table>,
+	host: Vec<HostFunction>,
+	dropped_data: Vec<bool>,
+	dropped_elements: Vec<bool>,
+	depth: usize,
+}
+
+impl<'a> Instance<'a> {
+	pub fn new(wasm: &'a Wasm) -> Result<Instance<'a>, Trap> {
+		Instance::with_imports(wasm, Imports::new())
+	}
+
+	pub fn with_imports(wasm: &'a Wasm, mut imports: Imports) -> Result<Instance<'a>, Trap> {
+		let mut host = vec![];
+		for (typ, module, name) in wasm.imports.iter() {
+			let key = (module.clone(), name.clone());
+			match imports.functions.remove(&key) {
+				Some((signature, function)) if signature == *typ => host.push(function),
+				Some(_) => return Err(Trap::IncompatibleImport(format!("{}.{}", key.0, key.1))),
+				None => return Err(Trap::UnlinkedImport(key.0, key.1)),
+			}
+		}
+		if let Some((_, module, name)) = wasm.memory_imports.first() {
+			return Err(Trap::UnlinkedImport(module.clone(), name.clone()));
+		}
+		let mut globals = vec![];
+		for (typ, module, name) in wasm.global_imports.iter() {
+			let key = (module.clone(), name.clone());
+			match imports.globals.remove(&key) {
+				Some((global, value)) if global == *typ && value.typ() == typ.typ => {
+					globals.push(value)
+				}
+				Some(_) => return Err(Trap::IncompatibleImport(format!("{}.{}", key.0, key.1))),
+				None => return Err(Trap::UnlinkedImport(key.0, key.1)),
+			}
+		}
+		if let Some((_, module, name)) = wasm.table_imports.first() {
+			return Err(Trap::UnlinkedImport(module.clone(), name.clone()));
+		}
+		let memories = wasm.memories.iter().map(Memory::new).collect();
+		let tables = wasm.tables.iter().map(|typ| Table {
+			typ: typ.typ.clone(),
+			elements: vec![None; typ.limits.min as usize],
+			max: typ.limits.max,
+		});
+		let tables = tables.collect();
+		let mut instance = Instance {
+			wasm,
+			memories,
+			globals,
+			tables,
+			host,
+			dropped_data: wasm.data.iter().map(|data| data.mode != DataMode::Passive).collect(),
+			dropped_elements: wasm
+				.elements
+				.iter()
+				.map(|element| element.mode != ElementMode::Passive)
+				.collect(),
+			depth: 0,
+		};
+		for global in wasm.globals.iter() {
+			let value = instance.evaluate(&global.init)?;
+			if value.typ() != global.typ.typ {
+				return Err(Trap::TypeMismatch);
+			}
+			instance.globals.push(value);
+		}
+		for element in wasm.elements.iter() {
+			if let ElementMode::Active(table, offset) = &element.mode {
+				let offset = instance.evaluate(offset)?;
+				let offset = u32::from_value(offset).ok_or(Trap::TypeMismatch)?;
+				let table = instance.tables.get_mut(*table).ok_or(Trap::TableOutOfBounds)?;
+				table.write(offset, element.functions.iter().copied().map(Some).collect())?;
+			}
+		}
+		for data in wasm.data.iter() {
+			if let DataMode::Active(memory, offset) = &data.mode {
+				let offset = instance.evaluate(offset)?;
+				let offset = u32::from_value(offset).ok_or(Trap::TypeMismatch)?;
+				let memory = instance.memories.get_mut(*memory).ok_or(Trap::MemoryOutOfBounds)?;
+				memory.write(offset, 0, &data.bytes)?;
+			}
+		}
+		if let Some(start) = wasm.start_function {
+			instance.call(start, &[])?;
+		}
+		Ok(instance)
+	}
+
+	pub fn invoke(&mut self, name: &str, args: &[Value]) -> Result<Vec<Value>, Trap> {
+		let index = self.wasm.exports.get(name).ok_or_else(|| Trap::UnknownExport(name.into()))?;
+		self.call(*index, args)
+	}
+
+	pub fn global(&self, name: &str) -> Option<Value> {
+		self.globals.get(*self.wasm.global_exports.get(name)?).copied()
+	}
+
+	pub fn set_global(&mut self, name: &str, value: Value) -> Result<(), Trap> {
+		let index =
+			self.wasm.global_exports.get(name).ok_or_else(|| Trap::UnknownExport(name.into()))?;
+		self.set_global_at(*index, value)
+	}
+
+	fn set_global_at(&mut self, index: usize, value: Value) -> Result<(), Trap> {
+		let typ = self.wasm.global_type(index).ok_or(Trap::TypeMismatch)?;
+		if !typ.mutable {
+			return Err(Trap::ImmutableGlobal);
+		}
+		if value.typ() != typ.typ {
+			return Err(Trap::TypeMismatch);
+		}
+		self.globals[index] = value;
+		Ok(())
+	}
+
+	pub fn call(&mut self, index: usize, args: &[Value]) -> Result<Vec<Value>, Trap> {
+		let typ = self.wasm.function_type(index).ok_or(Trap::TypeMismatch)?;
+		if !matches_types(args, &typ.params) {
+			return Err(Trap::TypeMismatch);
+		}
+		if self.depth == MAX_DEPTH {
+			return Err(Trap::StackOverflow);
+		}
+		if index < self.host.len() {
+			return self.call_host(index, args);
+		}
+		let function = &self.wasm.functions[index - self.wasm.imports.len()];
+		let mut locals = args.to_vec();
+		locals.extend(function.locals.iter().map(Value::zero));
+		let arity = function.signature.resuls.len();
+		let mut frame = Frame { locals, stack: Stack::default() };
+		self.depth += 1;
+		let flow = self.execute(&function.body, &mut frame);
+		self.depth -= 1;
+		flow?;
+		let values = &frame.stack.values;
+		let start = values.len().checked_sub(arity).ok_or(Trap::TypeMismatch)?;
+		Ok(values[start..].to_vec())
+	}
+
+	fn call_host(&mut self, index: usize, args: &[Value]) -> Result<Vec<Value>, Trap> {
+		let results = self.host[index](&mut self.memories, args)?;
+		let typ = &self.wasm.imports[index].0;
+		match matches_types(&results, &typ.resuls) {
+			true => Ok(results),
+			false => Err(Trap::TypeMismatch),
+		}
+	}
+
+	fn evaluate(&mut self, expr: &[Instr]) -> Result<Value, Trap> {
+		let mut frame = Frame { locals: vec![], stack: Stack::default() };
+		self.execute(expr, &mut frame)?;
+		frame.stack.pop_value()
+	}
+
+	fn block(
+		&mut self,
+		typ: &BlockType,
+		body: &[Instr],
+		frame: &mut Frame,
+		looped: bool,
+	) -> Result<Flow, Trap> {
+		if self.depth == MAX_DEPTH {
+			return Err(Trap::StackOverflow);
+		}
+		self.depth += 1;
+		let flow = self.nested(typ, body, frame, looped);
+		self.depth -= 1;
+		flow
+	}
+
+	fn nested(
+		&mut self,
+		typ: &BlockType,
+		body: &[Instr],
+		frame: &mut Frame,
+		looped: bool,
+	) -> Result<Flow, Trap> {
+		let (params, results) = match typ {
+			BlockType::Empty => (0, 0),
+			BlockType::Value(_) => (0, 1),
+			BlockType::Type(index) => {
+				let typ = self.wasm.types.get(*index).ok_or(Trap::TypeMismatch)?;
+				(typ.params.len(), typ.resuls.len())
+			}
+		};
+		let height = frame.stack.values.len().checked_sub(params).ok_or(Trap::TypeMismatch)?;
+		loop {
+			match self.execute(body, frame)? {
+				Flow::Branch(0) if looped => frame.stack.keep(height, params)?,
+				Flow::Branch(0) => {
+					frame.stack.keep(height, results)?;
+					return Ok(Flow::Next);
+				}
+				Flow::Branch(depth) => return Ok(Flow::Branch(depth - 1)),
+				flow => return Ok(flow),
+			}
+		}
+	}
+
+	fn call_from(&mut self, index: usize, stack: &mut Stack) -> Result<(), Trap> {
+		let typ = self.wasm.function_type(index).ok_or(Trap::TypeMismatch)?;
+		let split = stack.values.len().checked_sub(typ.params.len());
+		let args = stack.values.split_off(split.ok_or(Trap::TypeMismatch)?);
+		let results = self.call(index, &args)?;
+		stack.values.extend(results);
+		Ok(())
+	}
+
+	fn resolve(&self, typ: usize, table: usize, index: u32) -> Result<usize, Trap> {
+		let table = self.tables.get(table).ok_or(Trap::TypeMismatch)?;
+		let element = table.elements.get(index as usize).ok_or(Trap::UndefinedElement)?;
+		let function = element.ok_or(Trap::UninitializedElement)?;
+		let typ = self.wasm.types.get(typ).ok_or(Trap::TypeMismatch)?;
+		if self.wasm.function_type(function) != Some(typ) {
+			return Err(Trap::IndirectCallTypeMismatch);
+		}
+		Ok(function)
+	}
+
+	fn bulk(&mut self, stack: &mut Stack, instr: &Instr) -> Result<(), Trap> {
+		use Instr::*;
+		match instr {
+			data_drop(data) => {
+				*self.dropped_data.get_mut(*data).ok_or(Trap::TypeMismatch)? = true;
+			}
+			elem_drop(element) => {
+				*self.dropped_elements.get_mut(*element).ok_or(Trap::TypeMismatch)? = true;
+			}
+			table_get(table) | table_set(table) | table_grow(table) | table_size(table)
+			| table_fill(table) => {
+				self.tables.get_mut(*table).ok_or(Trap::TypeMismatch)?.execute(stack, instr)?
+			}
+			_ => {
+				let length = stack.pop::<u32>()? as usize;
+				let source = stack.pop::<u32>()?;
+				let destination = stack.pop::<u32>()?;
+				self.transfer(instr, destination, source, length)?;
+			}
+		}
+		Ok(())
+	}
+
+	// For memory.fill the source operand is the fill byte.
+	fn transfer(
+		&mut self,
+		instr: &Instr,
+		destination: u32,
+		source: u32,
+		length: usize,
+	) -> Result<(), Trap> {
+		use Instr::*;
+		match instr {
+			memory_init(data, memory) => {
+				let bytes: &[u8] = match self.dropped_data.get(*data).ok_or(Trap::TypeMismatch)? {
+					true => &[],
+					false => &self.wasm.data[*data].bytes,
+				};
+				let bytes = segment(bytes, source, length).ok_or(Trap::MemoryOutOfBounds)?;
+				let memory = self.memories.get_mut(*memory).ok_or(Trap::TypeMismatch)?;
+				memory.write(destination, 0, &bytes)
+			}
+			memory_copy(to, from) => {
+				let memory = self.memories.get(*from).ok_or(Trap::TypeMismatch)?;
+				let bytes =
+					segment(&memory.bytes, source, length).ok_or(Trap::MemoryOutOfBounds)?;
+				let memory = self.memories.get_mut(*to).ok_or(Trap::TypeMismatch)?;
+				memory.write(destination, 0, &bytes)
+			}
+			memory_fill(memory) => {
+				let memory = self.memories.get_mut(*memory).ok_or(Trap::TypeMismatch)?;
+				memory.fill(destination, source as u8, length)
+			}
+			table_init(element, table) => {
+				let dropped = self.dropped_elements.get(*element).ok_or(Trap::TypeMismatch)?;
+				let functions: &[usize] = match dropped {
+					true => &[],
+					false => &self.wasm.elements[*element].functions,
+				};
+				let functions = segment(functions, source, length).ok_or(Trap::TableOutOfBounds)?;
+				let table = self.tables.get_mut(*table).ok_or(Trap::TypeMismatch)?;
+				table.write(destination, functions.into_iter().map(Some).collect())
+			}
+			table_copy(to, from) => {
+				let table = self.tables.get(*from).ok_or(Trap::TypeMismatch)?;
+				let elements = segment(&table.elements, source, length);
+				let elements = elements.ok_or(Trap::TableOutOfBounds)?;
+				self.tables.get_mut(*to).ok_or(Trap::TypeMismatch)?.write(destination, elements)
+			}
+			_ => unreachable!(),
+		}
+	}
+
+	fn execute(&mut self, body: &[Instr], frame: &mut Frame) -> Result<Flow, Trap> {
+		use Instr::*;
+		for instr in body {
+			let stack = &mut frame.stack;
+			match instr {
+				unreachable => return Err(Trap::Unreachable),
+				nop => {}
+				block(typ, body) | looped(typ, body) => {
+					match self.block(typ, body, frame, matches!(instr, looped(..)))? {
+						Flow::Next => {}
+						flow => return Ok(flow),
+					}
+				}
+				if_else(typ, then, otherwise) => {
+					let body = if stack.pop::<i32>()? != 0 { then } else { otherwise };
+					match self.block(typ, body, frame, false)? {
+						Flow::Next => {}
+						flow => return Ok(flow),
+					}
+				}
+				then_end | end => {}
+				br(depth) => return Ok(Flow::Branch(*depth)),
+				br_if(depth) => {
+					if stack.pop::<i32>()? != 0 {
+						return Ok(Flow::Branch(*depth));
+					}
+				}
+				br_table(labels) => {
+					let index = stack.pop::<u32>()? as usize;
+					let label = labels.get(index).or(labels.last()).ok_or(Trap::TypeMismatch)?;
+					return Ok(Flow::Branch(*label));
+				}
+				return_result => return Ok(Flow::Return),
+				call(index) => self.call_from(*index, stack)?,
+				call_indirect(typ, table) => {
+					let function = self.resolve(*typ, *table, stack.pop::<u32>()?)?;
+					self.call_from(function, stack)?;
+				}
+				drop => {
+					stack.pop_value()?;
+				}
+				select | select_t(_) => {
+					let condition = stack.pop::<i32>()?;
+					let second = stack.pop_value()?;
+					let first = stack.pop_value()?;
+					stack.push(if condition != 0 { first } else { second });
+				}
+				local_get(_) | local_set(_) | local_tee(_) => {
+					variable(&mut frame.locals, stack, instr)?
+				}
+				global_get(index) => {
+					let value = self.globals.get(*index as usize).ok_or(Trap::TypeMismatch)?;
+					stack.push(*value);
+				}
+				global_set(index) => {
+					let value = stack.pop_value()?;
+					self.set_global_at(*index as usize, value)?;
+				}
+				i32_load(..) | i64_load(..) | f32_load(..) | f64_load(..) | i32_load8_s(..)
+				| i32_load8_u(..) | i32_load16_s(..) | i32_load16_u(..) | i64_load8_s(..)
+				| i64_load8_u(..) | i64_load16_s(..) | i64_load16_u(..) | i64_load32_s(..)
+				| i64_load32_u(..) | i32_store(..) | i64_store(..) | f32_store(..)
+				| f64_store(..) | i32_store8(..) | i32_store16(..) | i64_store8(..)
+				| i64_store16(..) | i64_store32(..) | v128_load(..) | v128_store(..)
+				| memory_size | memory_grow => {
+					let memory = self.memories.first_mut().ok_or(Trap::TypeMismatch)?;
+					access(memory, stack, instr)?
+				}
+				memory_init(..) | data_drop(_) | memory_copy(..) | memory_fill(_)
+				| table_init(..) | elem_drop(_) | table_copy(..) | table_get(_) | table_set(_)
+				| table_grow(_) | table_size(_) | table_fill(_) => self.bulk(stack, instr)?,
+				instr => numeric(stack, instr)?,
+			}
+		}
+		Ok(Flow::Next)
+	}
+}
+
+fn access(memory: &mut Memory, stack: &mut Stack, instr: &Instr) -> Result<(), Trap> {
+	use Instr::*;
+	macro_rules! load {
+		($offset:expr, $convert:expr) => {{
+			let address = stack.pop()?;
+			stack.push($convert(memory.load(address, *$offset)?));
+		}};
+	}
+	macro_rules! store {
+		($offset:expr, $type:ty, $convert:expr) => {{
+			let value: $type = stack.pop()?;
+			let address = stack.pop()?;
+			memory.write(address, *$offset, &$convert(value).to_le_bytes())?;
+		}};
+	}
+	match instr {
+		i32_load(_, offset) => load!(offset, i32::from_le_bytes),
+		i64_load(_, offset) => load!(offset, i64::from_le_bytes),
+		f32_load(_, offset) => load!(offset, f32::from_le_bytes),
+		f64_load(_, offset) => load!(offset, f64::from_le_bytes),
+		i32_load8_s(_, offset) => load!(offset, |b| i8::from_le_bytes(b) as i32),
+		i32_load8_u(_, offset) => load!(offset, |b| u8::from_le_bytes(b) as i32),
+		i32_load16_s(_, offset) => load!(offset, |b| i16::from_le_bytes(b) as i32),
+		i32_load16_u(_, offset) => load!(offset, |b| u16::from_le_bytes(b) as i32),
+		i64_load8_s(_, offset) => load!(offset, |b| i8::from_le_bytes(b) as i64),
+		i64_load8_u(_, offset) => load!(offset, |b| u8::from_le_bytes(b) as i64),
+		i64_load16_s(_, offset) => load!(offset, |b| i16::from_le_bytes(b) as i64),
+		i64_load16_u(_, offset) => load!(offset, |b| u16::from_le_bytes(b) as i64),
+		i64_load32_s(_, offset) => load!(offset, |b| i32::from_le_bytes(b) as i64),
+		i64_load32_u(_, offset) => load!(offset, |b| u32::from_le_bytes(b) as i64),
+		i32_store(_, offset) => store!(offset, i32, |x| x),
+		i64_store(_, offset) => store!(offset, i64, |x| x),
+		f32_store(_, offset) => store!(offset, f32, |x| x),
+		f64_store(_, offset) => store!(offset, f64, |x| x),
+		i32_store8(_, offset) => store!(offset, i32, |x| x as u8),
+		i32_store16(_, offset) => store!(offset, i32, |x| x as u16),
+		i64_store8(_, offset) => store!(offset, i64, |x| x as u8),
+		i64_store16(_, offset) => store!(offset, i64, |x| x as u16),
+		i64_store32(_, offset) => store!(offset, i64, |x| x as u32),
+		v128_load(_, offset) => load!(offset, u128::from_le_bytes),
+		v128_store(_, offset) => store!(offset, u128, |x| x),
+		memory_size => stack.push(memory.pages()),
+		memory_grow => {
+			let delta = stack.pop()?;
+			stack.push(memory.grow(delta).map_or(-1, |pages| pages as i32));
+		}
+		_ => unreachable!(),
+	}
+	Ok(())
+}
+
+fn numeric(stack: &mut Stack, instr: &Instr) -> Result<(), Trap> {
+	use Instr::*;
+	match instr {
+		i32_const(x) => stack.push(*x),
+		i64_const(x) => stack.push(*x),
+		f32_const(x) => stack.push(*x),
+		f64_const(x) => stack.push(*x),
+		i32_eqz => stack.unary(|a: i32| a == 0)?,
+		i32_eq => stack.binary(|a: i32, b| a == b)?,
+		i32_ne => stack.binary(|a: i32, b| a != b)?,
+		i32_lt_s => stack.binary(|a: i32, b| a < b)?,
+		i32_lt_u => stack.binary(|a: u32, b| a < b)?,
+		i32_gt_s => stack.binary(|a: i32, b| a > b)?,
+		i32_gt_u => stack.binary(|a: u32, b| a > b)?,
+		i32_le_s => stack.binary(|a: i32, b| a <= b)?,
+		i32_le_u => stack.binary(|a: u32, b| a <= b)?,
+		i32_ge_s => stack.binary(|a: i32, b| a >= b)?,
+		i32_ge_u => stack.binary(|a: u32, b| a >= b)?,
+		i64_eqz => stack.unary(|a: i64| a == 0)?,
+		i64_eq => stack.binary(|a: i64, b| a == b)?,
+		i64_ne => stack.binary(|a: i64, b| a != b)?,
+		i64_lt_s => stack.binary(|a: i64, b| a < b)?,
+		i64_lt_u => stack.binary(|a: u64, b| a < b)?,
+		i64_gt_s => stack.binary(|a: i64, b| a > b)?,
+		i64_gt_u => stack.binary(|a: u64, b| a > b)?,
+		i64_le_s => stack.binary(|a: i64, b| a <= b)?,
+		i64_le_u => stack.binary(|a: u64, b| a <= b)?,
+		i64_ge_s => stack.binary(|a: i64, b| a >= b)?,
+		i64_ge_u => stack.binary(|a: u64, b| a >= b)?,
+		f32_eq => stack.binary(|a: f32, b| a == b)?,
+		f32_ne => stack.binary(|a: f32, b| a != b)?,
+		f32_lt => stack.binary(|a: f32, b| a < b)?,
+		f32_gt => stack.binary(|a: f32, b| a > b)?,
+		f32_le => stack.binary(|a: f32, b| a <= b)?,
+		f32_ge => stack.binary(|a: f32, b| a >= b)?,
+		f64_eq => stack.binary(|a: f64, b| a == b)?,
+		f64_ne => stack.binary(|a: f64, b| a != b)?,
+		f64_lt => stack.binary(|a: f64, b| a < b)?,
+		f64_gt => stack.binary(|a: f64, b| a > b)?,
+		f64_le => stack.binary(|a: f64, b| a <= b)?,
+		f64_ge => stack.binary(|a: f64, b| a >= b)?,
+		i32_clz => stack.unary(|a: i32| a.leading_zeros())?,
+		i32_ctz => stack.unary(|a: i32| a.trailing_zeros())?,
+		i32_popcnt => stack.unary(|a: i32| a.count_ones())?,
+		i32_add => stack.binary(|a: i32, b| a.wrapping_add(b))?,
+		i32_sub => stack.binary(|a: i32, b| a.wrapping_sub(b))?,
+		i32_mul => stack.binary(|a: i32, b| a.wrapping_mul(b))?,
+		i32_div_s => stack.try_binary(|a: i32, b| divide(a, b, i32::checked_div))?,
+		i32_div_u => stack.try_binary(|a: u32, b| divide(a, b, u32::checked_div))?,
+		i32_rem_s => stack.try_binary(|a: i32, b| divide(a, b, |a, b| Some(a.wrapping_rem(b))))?,
+		i32_rem_u => stack.try_binary(|a: u32, b| divide(a, b, u32::checked_rem))?,
+		i32_and => stack.binary(|a: i32, b| a & b)?,
+		i32_or => stack.binary(|a: i32, b| a | b)?,
+		i32_xor => stack.binary(|a: i32, b| a ^ b)?,
+		i32_shl => stack.binary(|a: i32, b| a.wrapping_shl(b as u32))?,
+		i32_shr_s => stack.binary(|a: i32, b| a.wrapping_shr(b as u32))?,
+		i32_shr_u => stack.binary(|a: u32, b| a.wrapping_shr(b))?,
+		i32_rotl => stack.binary(|a: i32, b| a.rotate_left(b as u32 % 32))?,
+		i32_rotr => stack.binary(|a: i32, b| a.rotate_right(b as u32 % 32))?,
+		i64_clz => stack.unary(|a: i64| a.leading_zeros() as i64)?,
+		i64_ctz => stack.unary(|a: i64| a.trailing_zeros() as i64)?,
+		i64_popcnt => stack.unary(|a: i64| a.count_ones() as i64)?,
+		i64_add => stack.binary(|a: i64, b| a.wrapping_add(b))?,
+		i64_sub => stack.binary(|a: i64, b| a.wrapping_sub(b))?,
+		i64_mul => stack.binary(|a: i64, b| a.wrapping_mul(b))?,
+		i64_div_s => stack.try_binary(|a: i64, b| divide(a, b, i64::checked_div))?,
+		i64_div_u => stack.try_binary(|a: u64, b| divide(a, b, u64::checked_div))?,
+		i64_rem_s => stack.try_binary(|a: i64, b| divide(a, b, |a, b| Some(a.wrapping_rem(b))))?,
+		i64_rem_u => stack.try_binary(|a: u64, b| divide(a, b, u64::checked_rem))?,
+		i64_and => stack.binary(|a: i64, b| a & b)?,
+		i64_or => stack.binary(|a: i64, b| a | b)?,
+		i64_xor => stack.binary(|a: i64, b| a ^ b)?,
+		i64_shl => stack.binary(|a: i64, b| a.wrapping_shl(b as u32))?,
+		i64_shr_s => stack.binary(|a: i64, b| a.wrapping_shr(b as u32))?,
+		i64_shr_u => stack.binary(|a: u64, b| a.wrapping_shr(b as u32))?,
+		i64_rotl => stack.binary(|a: i64, b| a.rotate_left((b % 64) as u32))?,
+		i64_rotr => stack.binary(|a: i64, b| a.rotate_right((b % 64) as u32))?,
+		f32_abs => stack.unary(f32::abs)?,
+		f32_neg => stack.unary(|a: f32| -a)?,
+		f32_ceil => stack.unary(f32::ceil)?,
+		f32_floor => stack.unary(f32::floor)?,
+		f32_trunc => stack.unary(f32::trunc)?,
+		f32_nearest => stack.unary(f32::round_ties_even)?,
+		f32_sqrt => stack.unary(f32::sqrt)?,
+		f32_add => stack.binary(|a: f32, b| a + b)?,
+		f32_sub => stack.binary(|a: f32, b| a - b)?,
+		f32_mul => stack.binary(|a: f32, b| a * b)?,
+		f32_div => stack.binary(|a: f32, b| a / b)?,
+		f32_min => stack.binary(|a: f32, b| minimum(a as f64, b as f64) as f32)?,
+		f32_max => stack.binary(|a: f32, b| -minimum(-a as f64, -b as f64) as f32)?,
+		f32_copysign => stack.binary(f32::copysign)?,
+		f64_abs => stack.unary(f64::abs)?,
+		f64_neg => stack.unary(|a: f64| -a)?,
+		f64_ceil => stack.unary(f64::ceil)?,
+		f64_floor => stack.unary(f64::floor)?,
+		f64_trunc => stack.unary(f64::trunc)?,
+		f64_nearest => stack.unary(f64::round_ties_even)?,
+		f64_sqrt => stack.unary(f64::sqrt)?,
+		f64_add => stack.binary(|a: f64, b| a + b)?,
+		f64_sub => stack.binary(|a: f64, b| a - b)?,
+		f64_mul => stack.binary(|a: f64, b| a * b)?,
+		f64_div => stack.binary(|a: f64, b| a / b)?,
+		f64_min => stack.binary(minimum)?,
+		f64_max => stack.binary(|a: f64, b| -minimum(-a, -b))?,
+		f64_copysign => stack.binary(f64::copysign)?,
+		i32_wrap_i64 => stack.unary(|a: i64| a as i32)?,
+		i32_trunc_f32_s => {
+			stack.try_unary(|a: f32| truncate(a as f64, 31, true).map(|x| x as i32))?
+		}
+		i32_trunc_f32_u => {
+			stack.try_unary(|a: f32| truncate(a as f64, 32, false).map(|x| x as u32))?
+		}
+		i32_trunc_f64_s => stack.try_unary(|a: f64| truncate(a, 31, true).map(|x| x as i32))?,
+		i32_trunc_f64_u => stack.try_unary(|a: f64| truncate(a, 32, false).map(|x| x as u32))?,
+		i64_extend_i32_s => stack.unary(|a: i32| a as i64)?,
+		i64_extend_i32_u => stack.unary(|a: u32| a as i64)?,
+		i64_trunc_f32_s => {
+			stack.try_unary(|a: f32| truncate(a as f64, 63, true).map(|x| x as i64))?
+		}
+		i64_trunc_f32_u => {
+			stack.try_unary(|a: f32| truncate(a as f64, 64, false).map(|x| x as u64))?
+		}
+		i64_trunc_f64_s => stack.try_unary(|a: f64| truncate(a, 63, true).map(|x| x as i64))?,
+		i64_trunc_f64_u => stack.try_unary(|a: f64| truncate(a, 64, false).map(|x| x as u64))?,
+		f32_convert_i32_s => stack.unary(|a: i32| a as f32)?,
+		f32_convert_i32_u => stack.unary(|a: u32| a as f32)?,
+		f32_convert_i64_s => stack.unary(|a: i64| a as f32)?,
+		f32_convert_i64_u => stack.unary(|a: u64| a as f32)?,
+		f32_demote_f64 => stack.unary(|a: f64| a as f32)?,
+		f64_convert_i32_s => stack.unary(|a: i32| a as f64)?,
+		f64_convert_i32_u => stack.unary(|a: u32| a as f64)?,
+		f64_convert_i64_s => stack.unary(|a: i64| a as f64)?,
+		f64_convert_i64_u => stack.unary(|a: u64| a as f64)?,
+		f64_promote_f32 => stack.unary(|a: f32| a as f64)?,
+		i32_reinterpret_f32 => stack.unary(f32::to_bits)?,
+		i64_reinterpret_f64 => stack.unary(f64::to_bits)?,
+		f32_reinterpret_i32 => stack.unary(f32::from_bits)?,
+		f64_reinterpret_i64 => stack.unary(f64::from_bits)?,
+		i32_extend8_s => stack.unary(|a: i32| a as i8 as i32)?,
+		i32_extend16_s => stack.unary(|a: i32| a as i16 as i32)?,
+		i64_extend8_s => stack.unary(|a: i64| a as i8 as i64)?,
+		i64_extend16_s => stack.unary(|a: i64| a as i16 as i64)?,
+		i64_extend32_s => stack.unary(|a: i64| a as i32 as i64)?,
+		i32_trunc_sat_f32_s => stack.unary(|a: f32| a as i32)?,
+		i32_trunc_sat_f32_u => stack.unary(|a: f32| a as u32)?,
+		i32_trunc_sat_f64_s => stack.unary(|a: f64| a as i32)?,
+		i32_trunc_sat_f64_u => stack.unary(|a: f64| a as u32)?,
+		i64_trunc_sat_f32_s => stack.unary(|a: f32| a as i64)?,
+		i64_trunc_sat_f32_u => stack.unary(|a: f32| a as u64)?,
+		i64_trunc_sat_f64_s => stack.unary(|a: f64| a as i64)?,
+		i64_trunc_sat_f64_u => stack.unary(|a: f64| a as u64)?,
+		v128_const(x) => stack.push(*x),
+		ref_null(typ) => stack.push(Value::zero(typ)),
+		ref_is_null => {
+			let target = stack.pop_value()?.target()?;
+			stack.push(target.is_none());
+		}
+		ref_func(index) => stack.push(Value::FuncRef(Some(*index))),
+		instr => return Err(Trap::Unsupported(instr.name())),
+	}
+	Ok(())
+}
+
+pub(super) fn fold(instr: &Instr, args: &[Value]) -> Option<Value> {
+	let mut stack = Stack { values: args.to_vec() };
+	numeric(&mut stack, instr).ok()?;
+	match stack.values[..] {
+		[value] => Some(value),
+		_ => None,
+	}
+}
+
+fn divide<T: Default + PartialEq>(
+	a: T,
+	b: T,
+	f: impl FnOnce(T, T) -> Option<T>,
+) -> Result<T, Trap> {
+	if b == T::default() {
+		return Err(Trap::DivisionByZero);
+	}
+	f(a, b).ok_or(Trap::IntegerOverflow)
+}
+
+fn truncate(x: f64, bits: i32, signed: bool) -> Result<f64, Trap> {
+	if x.is_nan() {
+		return Err(Trap::InvalidConversion);
+	}
+	let x = x.trunc();
+	let min = if signed { -(2f64.powi(bits)) } else { 0.0 };
+	if x < min || x >= 2f64.powi(bits) {
+		return Err(Trap::IntegerOverflow);
+	}
+	Ok(x)
+}
+
+fn minimum(a: f64, b: f64) -> f64 {
+	if a.is_nan() || b.is_nan() {
+		f64::NAN
+	} else if a == b {
+		if a.is_sign_negative() {
+			a
+		} else {
+			b
+		}
+	} else {
+		a.min(b)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::{
+		Data, Element, Function, FunctionType, Global, GlobalType, Limits, TableType,
+	};
+	use std::cell::RefCell;
+	use std::rc::Rc;
+	use DataType::*;
+	use Instr::*;
+
+	type Definition = (Vec<DataType>, Vec<DataType>, Vec<DataType>, Vec<Instr>);
+
+	fn module(functions: Vec<Definition>) -> Wasm {
+		let functions = functions.into_iter().map(|(params, resuls, locals, body)| Function {
+			signature: FunctionType { params, resuls },
+			locals,
+			body,
+		});
+		Wasm {
+			exports: HashMap::from([("main".to_string(), 0)]),
+			functions: functions.collect(),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn recursion() {
+		let fib = vec![
+			local_get(0),
+			i32_const(2),
+			i32_lt_u,
+			if_else(
+				BlockType::Value(I32),
+				vec![local_get(0)],
+				vec![
+					local_get(0),
+					i32_const(1),
+					i32_sub,
+					call(0),
+					local_get(0),
+					i32_const(2),
+					i32_sub,
+					call(0),
+					i32_add,
+				],
+			),
+		];
+		let wasm = module(vec![(vec![I32], vec![I32], vec![], fib)]);
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("main", &[Value::I32(20)]), Ok(vec![Value::I32(6765)]));
+	}
+
+	#[test]
+	fn loops_and_branches() {
+		let factorial = vec![
+			i64_const(1),
+			local_set(1),
+			block(
+				BlockType::Empty,
+				vec![looped(
+					BlockType::Empty,
+					vec![
+						local_get(0),
+						i64_eqz,
+						br_if(1),
+						local_get(0),
+						local_get(1),
+						i64_mul,
+						local_set(1),
+						local_get(0),
+						i64_const(1),
+						i64_sub,
+						local_set(0),
+						br(0),
+					],
+				)],
+			),
+			local_get(1),
+			return_result,
+		];
+		let classify = vec![
+			block(
+				BlockType::Empty,
+				vec![
+					block(BlockType::Empty, vec![local_get(0), br_table(vec![0, 1])]),
+					f64_const(1.5),
+					return_result,
+				],
+			),
+			f64_const(-0.5),
+		];
+		let wasm = module(vec![
+			(vec![I64], vec![I64], vec![I64], factorial),
+			(vec![I32], vec![F64], vec![], classify),
+		]);
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.call(0, &[Value::I64(10)]), Ok(vec![Value::I64(3628800)]));
+		assert_eq!(instance.call(1, &[Value::I32(0)]), Ok(vec![Value::F64(1.5)]));
+		assert_eq!(instance.call(1, &[Value::I32(7)]), Ok(vec![Value::F64(-0.5)]));
+		assert_eq!(instance.call(1, &[Value::I64(7)]), Err(Trap::TypeMismatch));
+	}
+
+	#[test]
+	fn multiple_values() {
+		let step = vec![
+			local_set(2),
+			local_set(1),
+			local_get(2),
+			local_get(1),
+			local_get(2),
+			i32_add,
+			local_get(0),
+			i32_const(1),
+			i32_sub,
+			local_tee(0),
+			br_if(0),
+		];
+		let body = vec![
+			i32_const(0),
+			i32_const(1),
+			looped(BlockType::Type(0), step),
+			i32_const(1),
+			if_else(BlockType::Type(0), vec![], vec![drop, drop, i32_const(0), i32_const(0)]),
+			block(BlockType::Type(0), vec![i32_const(7), local_set(1), br(0)]),
+		];
+		let mut wasm = module(vec![(vec![I32], vec![I32, I32], vec![I32, I32], body)]);
+		wasm.types.push(FunctionType { params: vec![I32, I32], resuls: vec![I32, I32] });
+		wasm.validate().unwrap();
+		let mut instance = Instance::new(&wasm).unwrap();
+		let mut fibonacci = |n| instance.invoke("main", &[Value::I32(n)]);
+		assert_eq!(fibonacci(1), Ok(vec![Value::I32(1), Value::I32(1)]));
+		assert_eq!(fibonacci(5), Ok(vec![Value::I32(5), Value::I32(8)]));
+	}
+
+	#[test]
+	fn traps() {
+		let trap = |body: Vec<Instr>| {
+			let wasm = module(vec![(vec![], vec![], vec![], body)]);
+			Instance::new(&wasm).unwrap().invoke("main", &[]).unwrap_err()
+		};
+		assert_eq!(trap(vec![unreachable]), Trap::Unreachable);
+		assert_eq!(trap(vec![i32_const(1), i32_const(0), i32_div_u, drop]), Trap::DivisionByZero);
+		assert_eq!(
+			trap(vec![i32_const(i32::MIN), i32_const(-1), i32_div_s, drop]),
+			Trap::IntegerOverflow
+		);
+		assert_eq!(trap(vec![f32_const(f32::NAN), i32_trunc_f32_s, drop]), Trap::InvalidConversion);
+		assert_eq!(trap(vec![f64_const(-1.0), i64_trunc_f64_u, drop]), Trap::IntegerOverflow);
+		assert_eq!(trap(vec![call(0)]), Trap::StackOverflow);
+		let nested = (0..30).fold(vec![call(0)], |body, _| vec![block(BlockType::Empty, body)]);
+		assert_eq!(trap(nested), Trap::StackOverflow);
+		let deep = (0..255).fold(vec![unreachable], |body, _| vec![block(BlockType::Empty, body)]);
+		assert_eq!(trap(deep.clone()), Trap::Unreachable);
+		assert_eq!(trap(vec![block(BlockType::Empty, deep)]), Trap::StackOverflow);
+	}
+
+	#[test]
+	fn memory() {
+		let body = vec![
+			i32_const(4),
+			i32_const(4),
+			i32_load16_u(1, 6),
+			i32_store(2, 0),
+			i32_const(1),
+			memory_grow,
+			memory_size,
+			i32_const(4),
+			i64_load32_s(2, 0),
+		];
+		let mut wasm = module(vec![(vec![], vec![I32, I32, I64], vec![], body)]);
+		wasm.memories = vec![Limits { min: 1, max: Some(2) }];
+		wasm.data = vec![
+			Data { mode: DataMode::Active(0, vec![i32_const(10)]), bytes: vec![0xFE, 0xFF] },
+			Data { mode: DataMode::Passive, bytes: vec![1, 2, 3] },
+		];
+		let mut instance = Instance::new(&wasm).unwrap();
+		let results = vec![Value::I32(1), Value::I32(2), Value::I64(0xFFFE)];
+		assert_eq!(instance.invoke("main", &[]), Ok(results));
+		assert_eq!(instance.memories[0].pages(), 2);
+		assert_eq!(instance.invoke("main", &[]).unwrap()[0], Value::I32(-1));
+
+		wasm.data[0].mode = DataMode::Active(0, vec![i32_const(0x10000 - 1)]);
+		assert_eq!(Instance::new(&wasm).err(), Some(Trap::MemoryOutOfBounds));
+		wasm.data.clear();
+		wasm.functions[0].body = vec![i32_const(-1), i32_load8_u(0, 1), drop];
+		wasm.functions[0].signature.resuls.clear();
+		assert_eq!(Instance::new(&wasm).unwrap().invoke("main", &[]), Err(Trap::MemoryOutOfBounds));
+	}
+
+	#[test]
+	fn globals() {
+		let body = vec![global_get(0), i64_const(5), i64_add, global_set(0), global_get(1)];
+		let mut wasm = module(vec![(vec![], vec![F32], vec![], body)]);
+		wasm.global_exports.insert("counter".to_string(), 0);
+		wasm.global_exports.insert("scale".to_string(), 1);
+		wasm.globals = vec![
+			Global { typ: GlobalType { typ: I64, mutable: true }, init: vec![i64_const(1)] },
+			Global { typ: GlobalType { typ: F32, mutable: false }, init: vec![f32_const(0.5)] },
+		];
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("main", &[]), Ok(vec![Value::F32(0.5)]));
+		assert_eq!(instance.global("counter"), Some(Value::I64(6)));
+		assert_eq!(instance.set_global("counter", Value::I32(6)), Err(Trap::TypeMismatch));
+		assert_eq!(instance.set_global("scale", Value::F32(2.0)), Err(Trap::ImmutableGlobal));
+		instance.set_global("counter", Value::I64(-5)).unwrap();
+		instance.invoke("main", &[]).unwrap();
+		assert_eq!(instance.global("counter"), Some(Value::I64(0)));
+
+		wasm.functions[0].body = vec![f32_const(1.0), global_set(1), f32_const(1.0)];
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("main", &[]), Err(Trap::ImmutableGlobal));
+	}
+
+	#[test]
+	fn imported_globals() {
+		let wasm = Wasm::from_wat(
+			r#"
+			(module
+				(import "env" "base" (global $base i32))
+				(import "env" "sp" (global $sp (mut i32)))
+				(global $offset i32 (global.get $base))
+				(func (export "main") (result i32)
+					global.get $sp
+					i32.const 16
+					i32.sub
+					global.set $sp
+					global.get $sp
+					global.get $offset
+					i32.add)
+				(export "sp" (global $sp)))
+		"#,
+		)
+		.unwrap();
+		wasm.validate().unwrap();
+		let imports = |sp: GlobalType, value| {
+			let mut imports = Imports::new();
+			imports.global("env", "base", GlobalType { typ: I32, mutable: false }, Value::I32(100));
+			imports.global("env", "sp", sp, value);
+			imports
+		};
+		let sp = GlobalType { typ: I32, mutable: true };
+		let mut instance =
+			Instance::with_imports(&wasm, imports(sp.clone(), Value::I32(1000))).unwrap();
+		assert_eq!(instance.invoke("main", &[]), Ok(vec![Value::I32(1084)]));
+		assert_eq!(instance.global("sp"), Some(Value::I32(984)));
+
+		let incompatible = Some(Trap::IncompatibleImport("env.sp".to_string()));
+		let immutable = GlobalType { typ: I32, mutable: false };
+		assert_eq!(
+			Instance::with_imports(&wasm, imports(immutable, Value::I32(0))).err(),
+			incompatible
+		);
+		assert_eq!(Instance::with_imports(&wasm, imports(sp, Value::I64(0))).err(), incompatible);
+		let unlinked = Some(Trap::UnlinkedImport("env".to_string(), "base".to_string()));
+		assert_eq!(Instance::new(&wasm).err(), unlinked);
+	}
+
+	#[test]
+	fn indirect_calls() {
+		let dispatch = vec![local_get(1), local_get(0), call_indirect(0, 0)];
+		let mut wasm = module(vec![
+			(vec![I32, I32], vec![I32], vec![], dispatch),
+			(vec![I32], vec![I32], vec![], vec![local_get(0), i32_const(1), i32_add]),
+			(vec![I32], vec![I32], vec![], vec![local_get(0), i32_const(2), i32_mul]),
+			(vec![], vec![], vec![], vec![]),
+		]);
+		wasm.types.push(FunctionType { params: vec![I32], resuls: vec![I32] });
+		wasm.tables.push(TableType { typ: FuncRef, limits: Limits { min: 5, max: None } });
+		let offset = vec![i32_const(1)];
+		let functions = vec![1, 2, 3];
+		wasm.elements.push(Element { mode: ElementMode::Active(0, offset), functions });
+		let mut instance = Instance::new(&wasm).unwrap();
+		let mut dispatch = |slot, x| instance.invoke("main", &[Value::I32(slot), Value::I32(x)]);
+		assert_eq!(dispatch(1, 10), Ok(vec![Value::I32(11)]));
+		assert_eq!(dispatch(2, 10), Ok(vec![Value::I32(20)]));
+		assert_eq!(dispatch(3, 10), Err(Trap::IndirectCallTypeMismatch));
+		assert_eq!(dispatch(0, 10), Err(Trap::UninitializedElement));
+		assert_eq!(dispatch(5, 10), Err(Trap::UndefinedElement));
+
+		wasm.elements[0].mode = ElementMode::Active(0, vec![i32_const(3)]);
+		assert_eq!(Instance::new(&wasm).err(), Some(Trap::TableOutOfBounds));
+	}
+
+	#[test]
+	fn host_functions() {
+		let body =
+			vec![local_get(0), i64_extend_i32_s, call(0), local_get(0), i32_const(2), call(1)];
+		let mut wasm = module(vec![(vec![I32], vec![I32], vec![], body)]);
+		let log = FunctionType { params: vec![I64], resuls: vec![] };
+		let scale = FunctionType { params: vec![I32, I32], resuls: vec![I32] };
+		wasm.imports.push((log, "env".to_string(), "log".to_string()));
+		wasm.imports.push((scale, "env".to_string(), "scale".to_string()));
+		wasm.memories.push(Limits { min: 1, max: None });
+		wasm.exports.insert("main".to_string(), 2);
+		let logged = Rc::new(RefCell::new(vec![]));
+		let sink = logged.clone();
+		let mut imports = Imports::new();
+		imports
+			.function("env", "log", &[I64], &[], move |_, args| {
+				sink.borrow_mut().push(args[0]);
+				Ok(vec![])
+			})
+			.function("env", "scale", &[I32, I32], &[I32], |memories, args| {
+				let (Value::I32(x), Value::I32(factor)) = (args[0], args[1]) else {
+					return Err(Trap::TypeMismatch);
+				};
+				memories[0].write(0, 0, &[factor as u8])?;
+				Ok(vec![Value::I32(x * factor)])
+			});
+		let mut instance = Instance::with_imports(&wasm, imports).unwrap();
+		assert_eq!(instance.invoke("main", &[Value::I32(-21)]), Ok(vec![Value::I32(-42)]));
+		assert_eq!(instance.call(0, &[Value::I64(5)]), Ok(vec![]));
+		assert_eq!(instance.call(1, &[Value::I64(5)]), Err(Trap::TypeMismatch));
+		assert_eq!(instance.memories[0].bytes[0], 2);
+		assert_eq!(*logged.borrow(), vec![Value::I64(-21), Value::I64(5)]);
+
+		let unlinked = Instance::new(&wasm).err();
+		assert_eq!(unlinked, Some(Trap::UnlinkedImport("env".to_string(), "log".to_string())));
+		let mut imports = Imports::new();
+		imports.function("env", "log", &[I32], &[], |_, _| Ok(vec![]));
+		let error = Instance::with_imports(&wasm, imports).err().unwrap();
+		assert_eq!(error.to_string(), "incompatible import type: env.log");
+		let mut imports = Imports::new();
+		imports.function("env", "log", &[I64], &[], |_, _| Ok(vec![])).function(
+			"env",
+			"scale",
+			&[I32, I32],
+			&[I32],
+			|_, _| Ok(vec![Value::I64(0)]),
+		);
+		let mut instance = Instance::with_imports(&wasm, imports).unwrap();
+		assert_eq!(instance.invoke("main", &[Value::I32(1)]), Err(Trap::TypeMismatch));
+	}
+
+	#[test]
+	fn bulk_memory() {
+		let run = |body: Vec<Instr>| {
+			let mut wasm = module(vec![(vec![], vec![I32], vec![], body)]);
+			wasm.memories.push(Limits { min: 1, max: None });
+			wasm.data.push(Data { mode: DataMode::Passive, bytes: vec![1, 2, 3, 4] });
+			Instance::new(&wasm).unwrap().invoke("main", &[])
+		};
+		let init = |address, length| vec![i32_const(address), i32_const(1), i32_const(length)];
+		let load = vec![i32_const(16), i32_load(2, 0)];
+		let body = [init(16, 3), vec![memory_init(0, 0)], load.clone()].concat();
+		assert_eq!(run(body), Ok(vec![Value::I32(0x040302)]));
+		let body = [init(16, 4), vec![memory_init(0, 0)], load.clone()].concat();
+		assert_eq!(run(body), Err(Trap::MemoryOutOfBounds));
+		let body = [vec![data_drop(0)], init(16, 1), vec![memory_init(0, 0)], load.clone()];
+		assert_eq!(run(body.concat()), Err(Trap::MemoryOutOfBounds));
+		let empty =
+			vec![data_drop(0), i32_const(16), i32_const(0), i32_const(0), memory_init(0, 0)];
+		assert_eq!(run([empty, load.clone()].concat()), Ok(vec![Value::I32(0)]));
+		let fill = vec![i32_const(17), i32_const(0xAB), i32_const(2), memory_fill(0)];
+		let copy = vec![i32_const(16), i32_const(17), i32_const(3), memory_copy(0, 0)];
+		assert_eq!(run([fill, copy, load].concat()), Ok(vec![Value::I32(0xABAB)]));
+		let body = vec![i32_const(65535), i32_const(0), i32_const(2), memory_copy(0, 0)];
+		assert_eq!(run([body, vec![i32_const(0)]].concat()), Err(Trap::MemoryOutOfBounds));
+	}
+
+	#[test]
+	fn bulk_tables() {
+		let dispatch = vec![local_get(0), call_indirect(0, 0)];
+		let mut wasm = module(vec![
+			(vec![I32], vec![I32], vec![], dispatch),
+			(vec![], vec![I32], vec![], vec![i32_const(1)]),
+			(vec![], vec![I32], vec![], vec![i32_const(2)]),
+		]);
+		wasm.exports.insert("setup".to_string(), 3);
+		let setup = vec![
+			i32_const(0),
+			i32_const(0),
+			i32_const(2),
+			table_init(0, 0),
+			elem_drop(0),
+			i32_const(2),
+			i32_const(0),
+			i32_const(2),
+			table_copy(0, 0),
+			table_size(0),
+		];
+		let signature = FunctionType { params: vec![], resuls: vec![I32] };
+		wasm.functions.push(Function { signature, locals: vec![], body: setup });
+		wasm.types.push(FunctionType { params: vec![], resuls: vec![I32] });
+		wasm.tables.push(TableType { typ: FuncRef, limits: Limits { min: 4, max: None } });
+		wasm.elements.push(Element { mode: ElementMode::Passive, functions: vec![1, 2] });
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("setup", &[]), Ok(vec![Value::I32(4)]));
+		assert_eq!(instance.tables[0].elements, vec![Some(1), Some(2), Some(1), Some(2)]);
+		assert_eq!(instance.invoke("main", &[Value::I32(3)]), Ok(vec![Value::I32(2)]));
+		assert_eq!(instance.invoke("setup", &[]), Err(Trap::TableOutOfBounds));
+	}
+
+	#[test]
+	fn references() {
+		let body = vec![
+			ref_func(1),
+			i32_const(2),
+			table_grow(0),
+			drop,
+			i32_const(1),
+			local_get(0),
+			i32_const(1),
+			table_fill(0),
+			i32_const(0),
+			table_get(0),
+			ref_is_null,
+			i32_const(2),
+			table_get(0),
+			ref_null(FuncRef),
+			local_get(1),
+			select_t(vec![FuncRef]),
+			ref_is_null,
+			i32_add,
+		];
+		let mut wasm = module(vec![
+			(vec![FuncRef, I32], vec![I32], vec![], body),
+			(vec![], vec![], vec![], vec![]),
+		]);
+		wasm.tables.push(TableType { typ: FuncRef, limits: Limits { min: 1, max: Some(3) } });
+		let mut instance = Instance::new(&wasm).unwrap();
+		let mut run = |target, condition| {
+			instance.invoke("main", &[Value::FuncRef(target), Value::I32(condition)])
+		};
+		assert_eq!(run(None, 1), Ok(vec![Value::I32(1)]));
+		assert_eq!(run(Some(1), 1), Ok(vec![Value::I32(1)]));
+		assert_eq!(run(Some(1), 0), Ok(vec![Value::I32(2)]));
+		assert_eq!(instance.tables[0].elements, vec![None, Some(1), Some(1)]);
+		let mut table = Table { typ: FuncRef, elements: vec![], max: Some(1) };
+		assert_eq!(table.grow(2, None), None);
+		assert_eq!(table.grow(1, Some(0)), Some(0));
+	}
+
+	#[test]
+	fn vectors() {
+		let body = vec![
+			i32_const(16),
+			v128_const(u128::MAX / 3),
+			v128_store(4, 0),
+			i32_const(20),
+			v128_load(0, 0),
+		];
+		let mut wasm = module(vec![(vec![], vec![V128], vec![], body)]);
+		wasm.memories.push(Limits { min: 1, max: None });
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("main", &[]), Ok(vec![Value::V128((u128::MAX / 3) >> 32)]));
+		wasm.functions[0].body.push(i64x2_add);
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("main", &[]), Err(Trap::Unsupported("i64x2.add")));
+	}
+
+	#[test]
+	fn traps_on_malformed_bodies() {
+		let run = |body: Vec<Instr>| {
+			let wasm = module(vec![(vec![], vec![I32], vec![], body)]);
+			Instance::new(&wasm).unwrap().invoke("main", &[])
+		};
+		assert_eq!(run(vec![]), Err(Trap::TypeMismatch));
+		assert_eq!(run(vec![local_get(3)]), Err(Trap::TypeMismatch));
+		assert_eq!(run(vec![i32_const(1), local_tee(0)]), Err(Trap::TypeMismatch));
+		assert_eq!(run(vec![block(BlockType::Value(I32), vec![br(0)])]), Err(Trap::TypeMismatch));
+		assert_eq!(run(vec![i32_const(0), br_table(vec![])]), Err(Trap::TypeMismatch));
+	}
+
+	#[test]
+	fn numeric_edge_cases() {
+		let run = |body: Vec<Instr>, result: DataType| {
+			let wasm = module(vec![(vec![], vec![result], vec![], body)]);
+			Instance::new(&wasm).unwrap().invoke("main", &[]).unwrap()[0]
+		};
+		assert_eq!(run(vec![i32_const(i32::MIN), i32_const(-1), i32_rem_s], I32), Value::I32(0));
+		assert_eq!(run(vec![i32_const(1), i32_const(33), i32_shl], I32), Value::I32(2));
+		assert_eq!(run(vec![f32_const(2.5), f32_nearest], F32), Value::F32(2.0));
+		assert_eq!(run(vec![f64_const(-0.0), f64_const(0.0), f64_max], F64), Value::F64(0.0));
+		assert_eq!(run(vec![f64_const(-0.9), i32_trunc_f64_u], I32), Value::I32(0));
+		assert_eq!(run(vec![f64_const(1e100), i64_trunc_sat_f64_s], I64), Value::I64(i64::MAX));
+	}
+}
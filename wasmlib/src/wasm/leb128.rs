@@ -1,14 +1,19 @@
 use std::io::Result;
 
+use super::error::ErrorKind;
+
 macro_rules! unsigned_leb128 {
 	( $reader:expr, ($type:ty) ) => {{
 		let mut value = 0;
 		let mut shift = 0;
 		while {
+			if shift >= <$type>::BITS {
+				return Err(ErrorKind::MalformedLeb128.into());
+			}
 			let byte = $reader.u8()?;
 			value |= (byte as $type & 0x7f) << shift;
 			shift += 7;
-			byte > 0x80
+			byte & 0x80 != 0
 		} {}
 		Ok(value)
 	}};
@@ -28,12 +33,15 @@ macro_rules! signed_leb128 {
 		let mut shift = 0;
 		let mut byte;
 		while {
+			if shift >= <$type>::BITS {
+				return Err(ErrorKind::MalformedLeb128.into());
+			}
 			byte = $reader.u8()?;
 			value |= (byte as $type & 0x7f) << shift;
 			shift += 7;
-			byte > 0x80
+			byte & 0x80 != 0
 		} {}
-		Ok(value | if byte & 0x40 == 0x40 { -1 << shift } else { 0 })
+		Ok(value | if shift < <$type>::BITS && byte & 0x40 == 0x40 { -1 << shift } else { 0 })
 	}};
 	( $writer:expr, $value:expr ) => {{
 		let mut more = true;
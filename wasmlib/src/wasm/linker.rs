@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::instruction::Instr;
+use super::{BlockType, DataMode, ElementMode, Function, FunctionType, Limits, Wasm};
+
+pub struct LinkError {
+	pub module: String,
+	pub message: String,
+}
+
+impl fmt::Display for LinkError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}: {}", self.module, self.message)
+	}
+}
+
+impl fmt::Debug for LinkError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+type Link<T = ()> = Result<T, LinkError>;
+
+fn error<T>(module: &str, message: impl Into<String>) -> Link<T> {
+	Err(LinkError { module: module.to_string(), message: message.into() })
+}
+
+// How to look up one index space (functions, globals, memories or tables) of a module.
+struct Kind<T: 'static> {
+	name: &'static str,
+	imports: fn(&Wasm) -> &Vec<(T, String, String)>,
+	exports: fn(&Wasm) -> &HashMap<String, usize>,
+	typ: fn(&Wasm, usize) -> Option<&T>,
+	defined: fn(&Wasm) -> usize,
+	compatible: fn(&T, &T) -> bool,
+}
+
+// An index space of the merged module: the imports left unresolved, followed by the
+// definitions of every module in order. `indices[m][i]` is the new index of index `i` of
+// module `m`.
+struct Space<T> {
+	imports: Vec<(T, String, String)>,
+	indices: Vec<Vec<usize>>,
+}
+
+enum Target {
+	Import(usize),
+	Defined(usize, usize),
+}
+
+fn resolve<T: Clone + PartialEq>(modules: &[(&str, &Wasm)], kind: Kind<T>) -> Link<Space<T>> {
+	let mut imports = vec![];
+	let mut targets = vec![];
+	for m in 0..modules.len() {
+		let count = (kind.imports)(modules[m].1).len();
+		let module: Link<Vec<_>> =
+			(0..count).map(|i| target(modules, &kind, m, i, &mut imports)).collect();
+		targets.push(module?);
+	}
+	let mut bases = vec![];
+	let mut base = imports.len();
+	for (_, wasm) in modules {
+		bases.push(base);
+		base += (kind.defined)(wasm);
+	}
+	let indices = targets.into_iter().enumerate().map(|(m, targets)| {
+		let mut indices: Vec<_> = targets
+			.into_iter()
+			.map(|target| match target {
+				Target::Import(index) => index,
+				Target::Defined(j, k) => bases[j] + k,
+			})
+			.collect();
+		indices.extend((0..(kind.defined)(modules[m].1)).map(|k| bases[m] + k));
+		indices
+	});
+	Ok(Space { imports, indices: indices.collect() })
+}
+
+// Follows an import through the exports of other modules, possibly several of them.
+fn target<T: Clone + PartialEq>(
+	modules: &[(&str, &Wasm)],
+	kind: &Kind<T>,
+	mut m: usize,
+	mut index: usize,
+	unresolved: &mut Vec<(T, String, String)>,
+) -> Link<Target> {
+	let start = m;
+	let limit: usize = modules.iter().map(|(_, wasm)| (kind.imports)(wasm).len()).sum();
+	for _ in 0..=limit {
+		let imports = (kind.imports)(modules[m].1);
+		let Some((typ, module, name)) = imports.get(index) else {
+			return Ok(Target::Defined(m, index - imports.len()));
+		};
+		let export = modules.iter().position(|(id, _)| id == module).and_then(|j| {
+			let k = *(kind.exports)(modules[j].1).get(name)?;
+			Some((j, k, (kind.typ)(modules[j].1, k)?))
+		});
+		let Some((j, k, export)) = export else {
+			let import = (typ.clone(), module.clone(), name.clone());
+			let index = unresolved.iter().position(|other| *other == import);
+			return Ok(Target::Import(index.unwrap_or_else(|| {
+				unresolved.push(import);
+				unresolved.len() - 1
+			})));
+		};
+		if !(kind.compatible)(typ, export) {
+			let message = format!("Incompatible {} import: {}.{}", kind.name, module, name);
+			return error(modules[m].0, message);
+		}
+		(m, index) = (j, k);
+	}
+	error(modules[start].0, format!("Cyclic {} import", kind.name))
+}
+
+fn fits(export: &Limits, import: &Limits) -> bool {
+	export.min >= import.min
+		&& import.max.is_none_or(|max| export.max.is_some_and(|limit| limit <= max))
+}
+
+struct Remap<'a> {
+	types: Vec<usize>,
+	functions: &'a [usize],
+	globals: &'a [usize],
+	memories: &'a [usize],
+	tables: &'a [usize],
+	elements: usize,
+	data: usize,
+}
+
+impl Remap<'_> {
+	fn block_type(&self, typ: &mut BlockType) {
+		if let BlockType::Type(index) = typ {
+			*index = self.types[*index];
+		}
+	}
+
+	fn body(&self, body: &mut [Instr]) {
+		use Instr::*;
+		for instr in body {
+			match instr {
+				block(typ, body) | looped(typ, body) => {
+					self.block_type(typ);
+					self.body(body);
+				}
+				if_else(typ, then, otherwise) => {
+					self.block_type(typ);
+					self.body(then);
+					self.body(otherwise);
+				}
+				call(function) | ref_func(function) => *function = self.functions[*function],
+				call_indirect(typ, table) => {
+					*typ = self.types[*typ];
+					*table = self.tables[*table];
+				}
+				global_get(global) | global_set(global) => {
+					*global = self.globals[*global as usize] as u32;
+				}
+				table_get(table) | table_set(table) | table_grow(table) | table_size(table)
+				| table_fill(table) => *table = self.tables[*table],
+				table_copy(to, from) => {
+					*to = self.tables[*to];
+					*from = self.tables[*from];
+				}
+				table_init(element, table) => {
+					*element += self.elements;
+					*table = self.tables[*table];
+				}
+				elem_drop(element) => *element += self.elements,
+				memory_init(data, memory) => {
+					*data += self.data;
+					*memory = self.memories[*memory];
+				}
+				data_drop(data) => *data += self.data,
+				memory_copy(to, from) => {
+					*to = self.memories[*to];
+					*from = self.memories[*from];
+				}
+				memory_fill(memory) => *memory = self.memories[*memory],
+				_ => {}
+			}
+		}
+	}
+}
+
+impl Wasm {
+	/// Merges `modules` into one, resolving imports against the exports of the module with the
+	/// given name. Imports that no module provides remain imports of the result.
+	pub fn link(modules: &[(&str, &Wasm)]) -> Link<Wasm> {
+		let functions = resolve(
+			modules,
+			Kind {
+				name: "function",
+				imports: |wasm| &wasm.imports,
+				exports: |wasm| &wasm.exports,
+				typ: |wasm, index| wasm.function_type(index),
+				defined: |wasm| wasm.functions.len(),
+				compatible: |import, export| import == export,
+			},
+		)?;
+		let globals = resolve(
+			modules,
+			Kind {
+				name: "global",
+				imports: |wasm| &wasm.global_imports,
+				exports: |wasm| &wasm.global_exports,
+				typ: |wasm, index| wasm.global_type(index),
+				defined: |wasm| wasm.globals.len(),
+				compatible: |import, export| import == export,
+			},
+		)?;
+		let memories = resolve(
+			modules,
+			Kind {
+				name: "memory",
+				imports: |wasm| &wasm.memory_imports,
+				exports: |wasm| &wasm.memory_exports,
+				typ: |wasm, index| match wasm.memory_imports.get(index) {
+					Some((limits, _, _)) => Some(limits),
+					None => wasm.memories.get(index - wasm.memory_imports.len()),
+				},
+				defined: |wasm| wasm.memories.len(),
+				compatible: |import, export| fits(export, import),
+			},
+		)?;
+		let tables = resolve(
+			modules,
+			Kind {
+				name: "table",
+				imports: |wasm| &wasm.table_imports,
+				exports: |wasm| &wasm.table_exports,
+				typ: |wasm, index| wasm.table_type(index),
+				defined: |wasm| wasm.tables.len(),
+				compatible: |import, export| {
+					import.typ == export.typ && fits(&export.limits, &import.limits)
+				},
+			},
+		)?;
+		// Loads and stores always address memory 0, so every module has to share it.
+		if let Some(m) = memories.indices.iter().position(|indices| indices.iter().any(|&i| i > 0))
+		{
+			return error(modules[m].0, "Multiple memories are not supported");
+		}
+
+		let mut wasm = Wasm {
+			imports: functions.imports,
+			global_imports: globals.imports,
+			memory_imports: memories.imports,
+			table_imports: tables.imports,
+			..Default::default()
+		};
+		let mut starts = vec![];
+		for (m, &(id, module)) in modules.iter().enumerate() {
+			let remap = Remap {
+				types: module.types.iter().map(|typ| wasm.type_index(typ.clone())).collect(),
+				functions: &functions.indices[m],
+				globals: &globals.indices[m],
+				memories: &memories.indices[m],
+				tables: &tables.indices[m],
+				elements: wasm.elements.len(),
+				data: wasm.data.len(),
+			};
+			for function in &module.functions {
+				let mut function = function.clone();
+				remap.body(&mut function.body);
+				wasm.functions.push(function);
+			}
+			for global in &module.globals {
+				let mut global = global.clone();
+				remap.body(&mut global.init);
+				wasm.globals.push(global);
+			}
+			wasm.memories.extend(module.memories.iter().cloned());
+			wasm.tables.extend(module.tables.iter().cloned());
+			for element in &module.elements {
+				let mut element = element.clone();
+				for function in element.functions.iter_mut() {
+					*function = remap.functions[*function];
+				}
+				if let ElementMode::Active(table, offset) = &mut element.mode {
+					*table = remap.tables[*table];
+					remap.body(offset);
+				}
+				wasm.elements.push(element);
+			}
+			for data in &module.data {
+				let mut data = data.clone();
+				if let DataMode::Active(memory, offset) = &mut data.mode {
+					*memory = remap.memories[*memory];
+					remap.body(offset);
+				}
+				wasm.data.push(data);
+			}
+			let exports = [
+				(&module.exports, remap.functions),
+				(&module.table_exports, remap.tables),
+				(&module.memory_exports, remap.memories),
+				(&module.global_exports, remap.globals),
+			];
+			for (kind, (exports, indices)) in exports.into_iter().enumerate() {
+				for (name, &index) in exports {
+					let index = indices[index];
+					let mut merged = [
+						&mut wasm.exports,
+						&mut wasm.table_exports,
+						&mut wasm.memory_exports,
+						&mut wasm.global_exports,
+					];
+					let duplicate = merged.iter().enumerate().any(|(other, exports)| {
+						exports.get(name).is_some_and(|&i| other != kind || i != index)
+					});
+					if duplicate {
+						return error(id, format!("Duplicate export: {}", name));
+					}
+					merged[kind].insert(name.clone(), index);
+				}
+			}
+			if let Some(start) = module.start_function {
+				starts.push(remap.functions[start]);
+			}
+			for (&index, name) in &module.names.functions {
+				wasm.names.functions.entry(remap.functions[index]).or_insert_with(|| name.clone());
+			}
+			for (&index, locals) in &module.names.locals {
+				if index >= module.imports.len() {
+					wasm.names.locals.insert(remap.functions[index], locals.clone());
+				}
+			}
+		}
+		wasm.start_function = match starts[..] {
+			[] => None,
+			[start] => Some(start),
+			_ => {
+				let signature = FunctionType { params: vec![], resuls: vec![] };
+				let body = starts.iter().map(|&start| Instr::call(start)).collect();
+				wasm.functions.push(Function { signature, locals: vec![], body });
+				Some(wasm.imports.len() + wasm.functions.len() - 1)
+			}
+		};
+		Ok(wasm)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::interpreter::{Imports, Instance, Value};
+	use crate::wasm::DataType::*;
+
+	const RUNTIME: &str = r#"
+		(module
+			(global $base (export "base") (mut i32) (i32.const 100))
+			(func $add (export "add") (param i32 i32) (result i32)
+				local.get 0
+				local.get 1
+				i32.add)
+			(func $bump (export "bump")
+				global.get $base
+				i32.const 1
+				i32.add
+				global.set $base))
+	"#;
+
+	const MAIN: &str = r#"
+		(module
+			(import "rt" "add" (func $add (param i32 i32) (result i32)))
+			(import "env" "log" (func $log (param i32)))
+			(import "rt" "bump" (func $bump))
+			(import "rt" "base" (global $base (mut i32)))
+			(table 2 funcref)
+			(elem (i32.const 0) $add $main)
+			(func $main (export "main") (param i32) (result i32)
+				call $bump
+				local.get 0
+				global.get $base
+				call $add)
+			(func (export "indirect") (param i32) (result i32)
+				local.get 0
+				i32.const 1
+				i32.const 0
+				call_indirect (param i32 i32) (result i32)))
+	"#;
+
+	#[test]
+	fn links_modules() {
+		let runtime = Wasm::from_wat(RUNTIME).unwrap();
+		let main = Wasm::from_wat(MAIN).unwrap();
+		let wasm = Wasm::link(&[("main", &main), ("rt", &runtime)]).unwrap();
+		wasm.validate().unwrap();
+		assert_eq!(wasm.imports.len(), 1);
+		assert_eq!((&wasm.imports[0].1[..], &wasm.imports[0].2[..]), ("env", "log"));
+		assert!(wasm.global_imports.is_empty());
+		assert_eq!(wasm.functions.len(), 4);
+		assert_eq!(wasm.exports["add"], 3);
+		assert_eq!(wasm.elements[0].functions, vec![3, 1]);
+		assert_eq!(wasm.names.functions[&1], "main");
+		assert_eq!(wasm.names.functions[&3], "add");
+
+		let mut bytes = vec![];
+		wasm.write_to(&mut bytes).unwrap();
+		let wasm = Wasm::read_from(&mut &bytes[..]).unwrap();
+		let mut imports = Imports::new();
+		imports.function("env", "log", &[I32], &[], |_, _| Ok(vec![]));
+		let mut instance = Instance::with_imports(&wasm, imports).unwrap();
+		assert_eq!(instance.invoke("main", &[Value::I32(5)]), Ok(vec![Value::I32(106)]));
+		assert_eq!(instance.invoke("main", &[Value::I32(5)]), Ok(vec![Value::I32(107)]));
+		assert_eq!(instance.invoke("indirect", &[Value::I32(2)]), Ok(vec![Value::I32(3)]));
+	}
+
+	#[test]
+	fn reports_link_errors() {
+		let runtime = Wasm::from_wat(RUNTIME).unwrap();
+		let main =
+			Wasm::from_wat(r#"(module (import "rt" "add" (func (param i32) (result i32))))"#)
+				.unwrap();
+		let error = Wasm::link(&[("main", &main), ("rt", &runtime)]).err().unwrap();
+		assert_eq!(error.to_string(), "main: Incompatible function import: rt.add");
+		let main = Wasm::from_wat(r#"(module (import "rt" "base" (global i32)))"#).unwrap();
+		let error = Wasm::link(&[("main", &main), ("rt", &runtime)]).err().unwrap();
+		assert_eq!(error.to_string(), "main: Incompatible global import: rt.base");
+		let other = Wasm::from_wat(r#"(module (func (export "add")))"#).unwrap();
+		let error = Wasm::link(&[("rt", &runtime), ("other", &other)]).err().unwrap();
+		assert_eq!(error.to_string(), "other: Duplicate export: add");
+		let a = Wasm::from_wat(r#"(module (func (export "f") (import "b" "f")))"#).unwrap();
+		let b = Wasm::from_wat(r#"(module (func (export "f") (import "a" "f")))"#).unwrap();
+		let error = Wasm::link(&[("a", &a), ("b", &b)]).err().unwrap();
+		assert_eq!(error.to_string(), "a: Cyclic function import");
+	}
+}
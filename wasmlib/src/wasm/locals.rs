@@ -0,0 +1,373 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::instruction::Instr;
+use super::{DataType, Function};
+
+type Live = BTreeSet<u32>;
+
+pub struct Liveness {
+	pub entry: Live,
+	stores: HashMap<Vec<usize>, Live>,
+}
+
+impl Liveness {
+	/// Locals live right after the `local_set` or `local_tee` at `path`, where the path holds
+	/// instruction indices as reported by the validator.
+	pub fn after_store(&self, path: &[usize]) -> Option<&Live> {
+		self.stores.get(path)
+	}
+}
+
+#[derive(Default)]
+struct Analysis {
+	labels: Vec<Live>,
+	path: Vec<usize>,
+	stores: HashMap<Vec<usize>, Live>,
+}
+
+impl Analysis {
+	fn block(&mut self, body: &[Instr], mut live: Live, first: usize) -> Live {
+		for (i, instr) in body.iter().enumerate().rev() {
+			self.path.push(first + i);
+			live = self.instr(instr, live);
+			self.path.pop();
+		}
+		live
+	}
+
+	fn label(&self, depth: usize) -> Live {
+		match self.labels.len().checked_sub(depth + 1) {
+			Some(i) => self.labels[i].clone(),
+			None => Live::new(),
+		}
+	}
+
+	fn instr(&mut self, instr: &Instr, mut live: Live) -> Live {
+		use Instr::*;
+		match instr {
+			local_get(index) => {
+				live.insert(*index);
+			}
+			local_set(index) | local_tee(index) => {
+				self.stores.insert(self.path.clone(), live.clone());
+				live.remove(index);
+			}
+			br(depth) => live = self.label(*depth),
+			br_if(depth) => live.extend(self.label(*depth)),
+			br_table(labels) => live = labels.iter().flat_map(|&label| self.label(label)).collect(),
+			unreachable | return_result => live.clear(),
+			block(_, body) => {
+				self.labels.push(live.clone());
+				live = self.block(body, live, 0);
+				self.labels.pop();
+			}
+			looped(_, body) => {
+				let mut head = Live::new();
+				loop {
+					self.labels.push(head.clone());
+					let entry = self.block(body, live.clone(), 0);
+					self.labels.pop();
+					if entry == head {
+						break;
+					}
+					head = entry;
+				}
+				live = head;
+			}
+			if_else(_, then, otherwise) => {
+				self.labels.push(live.clone());
+				let mut entry = self.block(then, live.clone(), 0);
+				entry.extend(self.block(otherwise, live, then.len()));
+				self.labels.pop();
+				live = entry;
+			}
+			_ => {}
+		}
+		live
+	}
+}
+
+impl Function {
+	pub fn liveness(&self) -> Liveness {
+		let mut analysis = Analysis { labels: vec![Live::new()], ..Default::default() };
+		let entry = analysis.block(&self.body, Live::new(), 0);
+		Liveness { entry, stores: analysis.stores }
+	}
+
+	/// Turns `local_set x; local_get x` into `local_tee x`, removes stores to locals that are
+	/// never read again and merges locals whose lifetimes do not overlap. Returns the new index
+	/// of every old local, or `None` where a local is no longer used.
+	pub fn optimize_locals(&mut self) -> Vec<Option<u32>> {
+		loop {
+			tee(&mut self.body);
+			let liveness = self.liveness();
+			if !remove_dead_stores(&mut self.body, &liveness, &mut vec![], 0) {
+				break;
+			}
+		}
+		let remap = self.coalesce();
+		rename(&mut self.body, &remap);
+		remap
+	}
+
+	fn coalesce(&mut self) -> Vec<Option<u32>> {
+		let liveness = self.liveness();
+		let mut interference = HashSet::new();
+		let mut conflict = |a: u32, live: &Live| {
+			interference.extend(live.iter().filter(|&&b| b != a).flat_map(|&b| [(a, b), (b, a)]));
+		};
+		for &local in liveness.entry.iter() {
+			conflict(local, &liveness.entry);
+		}
+		let mut used = HashSet::new();
+		accesses(&self.body, &mut vec![], 0, &mut |path, instr| match *instr {
+			Instr::local_set(local) | Instr::local_tee(local) => {
+				conflict(local, &liveness.stores[path]);
+				used.insert(local);
+			}
+			Instr::local_get(local) => {
+				used.insert(local);
+			}
+			_ => {}
+		});
+		let params = self.signature.params.len();
+		let mut remap: Vec<_> = (0..params as u32).map(Some).collect();
+		let mut slots: Vec<(DataType, Vec<u32>)> = vec![];
+		for (i, typ) in self.locals.iter().enumerate() {
+			let local = (params + i) as u32;
+			if !used.contains(&local) {
+				remap.push(None);
+				continue;
+			}
+			let free = slots.iter().position(|(slot, members)| {
+				slot == typ && members.iter().all(|&other| !interference.contains(&(local, other)))
+			});
+			let slot = free.unwrap_or_else(|| {
+				slots.push((typ.clone(), vec![]));
+				slots.len() - 1
+			});
+			slots[slot].1.push(local);
+			remap.push(Some((params + slot) as u32));
+		}
+		self.locals = slots.into_iter().map(|(typ, _)| typ).collect();
+		remap
+	}
+}
+
+fn tee(body: &mut Vec<Instr>) {
+	for instr in body.iter_mut() {
+		for child in children(instr) {
+			tee(child);
+		}
+	}
+	let mut i = 0;
+	while i + 1 < body.len() {
+		if let [Instr::local_set(a), Instr::local_get(b)] = body[i..i + 2] {
+			if a == b {
+				body[i] = Instr::local_tee(a);
+				body.remove(i + 1);
+			}
+		}
+		i += 1;
+	}
+}
+
+fn remove_dead_stores(
+	body: &mut Vec<Instr>,
+	liveness: &Liveness,
+	path: &mut Vec<usize>,
+	first: usize,
+) -> bool {
+	let mut changed = false;
+	let mut i = body.len();
+	while i > 0 {
+		i -= 1;
+		path.push(first + i);
+		let mut offset = 0;
+		for child in children(&mut body[i]) {
+			let length = child.len();
+			changed |= remove_dead_stores(child, liveness, path, offset);
+			offset += length;
+		}
+		let dead = match body[i] {
+			Instr::local_set(local) | Instr::local_tee(local) => {
+				!liveness.stores.get(path.as_slice()).is_some_and(|live| live.contains(&local))
+			}
+			_ => false,
+		};
+		path.pop();
+		if !dead {
+			continue;
+		}
+		changed = true;
+		match body[i] {
+			Instr::local_tee(_) => {
+				body.remove(i);
+			}
+			_ if i > 0 && is_pure(&body[i - 1]) => {
+				body.drain(i - 1..=i);
+				i -= 1;
+			}
+			_ => body[i] = Instr::drop,
+		}
+	}
+	changed
+}
+
+fn is_pure(instr: &Instr) -> bool {
+	use Instr::*;
+	matches!(
+		instr,
+		local_get(_)
+			| global_get(_)
+			| i32_const(_)
+			| i64_const(_)
+			| f32_const(_)
+			| f64_const(_)
+			| v128_const(_)
+			| ref_null(_)
+			| ref_func(_)
+	)
+}
+
+fn accesses(
+	body: &[Instr],
+	path: &mut Vec<usize>,
+	first: usize,
+	f: &mut impl FnMut(&[usize], &Instr),
+) {
+	for (i, instr) in body.iter().enumerate() {
+		path.push(first + i);
+		match instr {
+			Instr::block(_, body) | Instr::looped(_, body) => accesses(body, path, 0, f),
+			Instr::if_else(_, then, otherwise) => {
+				accesses(then, path, 0, f);
+				accesses(otherwise, path, then.len(), f);
+			}
+			instr => f(path, instr),
+		}
+		path.pop();
+	}
+}
+
+fn rename(body: &mut [Instr], remap: &[Option<u32>]) {
+	for instr in body.iter_mut() {
+		match instr {
+			Instr::local_get(local) | Instr::local_set(local) | Instr::local_tee(local) => {
+				*local = remap[*local as usize].unwrap();
+			}
+			instr => {
+				for child in children(instr) {
+					rename(child, remap);
+				}
+			}
+		}
+	}
+}
+
+fn children(instr: &mut Instr) -> Vec<&mut Vec<Instr>> {
+	match instr {
+		Instr::block(_, body) | Instr::looped(_, body) => vec![body],
+		Instr::if_else(_, then, otherwise) => vec![then, otherwise],
+		_ => vec![],
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::interpreter::{Instance, Value};
+	use crate::wasm::{BlockType, FunctionType, Wasm};
+	use DataType::*;
+	use Instr::*;
+
+	fn function(locals: Vec<DataType>, body: Vec<Instr>) -> Function {
+		let signature = FunctionType { params: vec![I32], resuls: vec![I32] };
+		Function { signature, locals, body }
+	}
+
+	#[test]
+	fn computes_liveness() {
+		let body = vec![
+			local_get(0),
+			local_set(1),
+			looped(
+				BlockType::Empty,
+				vec![local_get(1), local_get(2), i32_add, local_set(2), br(0)],
+			),
+		];
+		let liveness = function(vec![I32, I32], body).liveness();
+		assert_eq!(liveness.entry, Live::from([0, 2]));
+		assert_eq!(liveness.after_store(&[1]), Some(&Live::from([1, 2])));
+		assert_eq!(liveness.after_store(&[2, 3]), Some(&Live::from([1, 2])));
+		assert_eq!(liveness.after_store(&[0]), None);
+	}
+
+	#[test]
+	fn rewrites_stores() {
+		let body = vec![
+			local_get(0),
+			call(0),
+			local_set(2),
+			local_get(0),
+			local_set(1),
+			local_get(1),
+			i32_const(5),
+			local_set(2),
+			call(0),
+			local_tee(2),
+			return_result,
+			local_get(2),
+		];
+		let mut function = function(vec![I32, I32], body);
+		assert_eq!(function.optimize_locals(), vec![Some(0), None, Some(1)]);
+		let body =
+			vec![local_get(0), call(0), drop, local_get(0), call(0), return_result, local_get(1)];
+		assert_eq!(function.body, body);
+		assert_eq!(function.locals, vec![I32]);
+	}
+
+	#[test]
+	fn coalesces_locals() {
+		let body = vec![
+			local_get(0),
+			local_set(1),
+			local_get(1),
+			local_get(1),
+			i32_mul,
+			local_set(3),
+			f64_const(1.5),
+			local_set(2),
+			local_get(2),
+			local_get(2),
+			f64_add,
+			i32_trunc_f64_s,
+			local_get(3),
+			i32_add,
+			local_set(4),
+			local_get(4),
+			local_get(5),
+			i32_add,
+		];
+		let locals = vec![I32, F64, I32, I32, I32];
+		let mut wasm = Wasm {
+			exports: [("main".to_string(), 0)].into(),
+			functions: vec![function(locals, body)],
+			..Default::default()
+		};
+		wasm.names.locals.insert(0, [(0, "x".to_string()), (3, "square".to_string())].into());
+		let mut instance = Instance::new(&wasm).unwrap();
+		let expected = instance.invoke("main", &[Value::I32(7)]);
+		wasm.optimize();
+		wasm.validate().unwrap();
+		let function = &wasm.functions[0];
+		assert_eq!(function.locals, vec![I32, F64, I32]);
+		assert_eq!(function.body[1], local_tee(1));
+		assert_eq!(function.body[4], local_set(1));
+		assert_eq!(function.body[10], local_get(1));
+		assert_eq!(function.body[12], local_get(3));
+		assert_eq!(wasm.names.locals[&0], [(0, "x".to_string()), (1, "square".to_string())].into());
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("main", &[Value::I32(7)]), expected);
+	}
+}
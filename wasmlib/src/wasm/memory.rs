@@ -0,0 +1,91 @@
+use super::interpreter::Trap;
+use super::Limits;
+
+pub const PAGE_SIZE: usize = 0x10000;
+pub const MAX_PAGES: u32 = 0x10000;
+
+pub struct Memory {
+	pub bytes: Vec<u8>,
+	pub max: Option<u32>,
+}
+
+impl Memory {
+	pub fn new(limits: &Limits) -> Memory {
+		Memory { bytes: vec![0; limits.min as usize * PAGE_SIZE], max: limits.max }
+	}
+
+	pub fn pages(&self) -> u32 {
+		(self.bytes.len() / PAGE_SIZE) as u32
+	}
+
+	pub fn grow(&mut self, delta: u32) -> Option<u32> {
+		let pages = self.pages();
+		let new_pages = pages.checked_add(delta)?;
+		if new_pages > self.max.unwrap_or(MAX_PAGES).min(MAX_PAGES) {
+			return None;
+		}
+		self.bytes.resize(new_pages as usize * PAGE_SIZE, 0);
+		Some(pages)
+	}
+
+	fn range(
+		&self,
+		address: u32,
+		offset: u32,
+		size: usize,
+	) -> Result<std::ops::Range<usize>, Trap> {
+		let start = address as u64 + offset as u64;
+		let end = start + size as u64;
+		if end > self.bytes.len() as u64 {
+			return Err(Trap::MemoryOutOfBounds);
+		}
+		Ok(start as usize..end as usize)
+	}
+
+	pub fn read(&self, address: u32, offset: u32, buffer: &mut [u8]) -> Result<(), Trap> {
+		buffer.copy_from_slice(&self.bytes[self.range(address, offset, buffer.len())?]);
+		Ok(())
+	}
+
+	pub fn write(&mut self, address: u32, offset: u32, bytes: &[u8]) -> Result<(), Trap> {
+		let range = self.range(address, offset, bytes.len())?;
+		self.bytes[range].copy_from_slice(bytes);
+		Ok(())
+	}
+
+	pub fn fill(&mut self, address: u32, value: u8, length: usize) -> Result<(), Trap> {
+		let range = self.range(address, 0, length)?;
+		self.bytes[range].fill(value);
+		Ok(())
+	}
+
+	pub fn load<const N: usize>(&self, address: u32, offset: u32) -> Result<[u8; N], Trap> {
+		let mut buffer = [0; N];
+		self.read(address, offset, &mut buffer)?;
+		Ok(buffer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn grows_within_limits() {
+		let mut memory = Memory::new(&Limits { min: 1, max: Some(3) });
+		assert_eq!(memory.pages(), 1);
+		assert_eq!(memory.grow(2), Some(1));
+		assert_eq!(memory.grow(1), None);
+		assert_eq!(memory.pages(), 3);
+		assert_eq!(memory.bytes.len(), 3 * PAGE_SIZE);
+	}
+
+	#[test]
+	fn checks_bounds() {
+		let mut memory = Memory::new(&Limits { min: 1, max: None });
+		memory.write(PAGE_SIZE as u32 - 4, 0, &0x12345678u32.to_le_bytes()).unwrap();
+		assert_eq!(memory.load::<2>(PAGE_SIZE as u32 - 8, 4), Ok([0x78, 0x56]));
+		assert_eq!(memory.load::<4>(PAGE_SIZE as u32 - 3, 0), Err(Trap::MemoryOutOfBounds));
+		assert_eq!(memory.load::<1>(u32::MAX, u32::MAX), Err(Trap::MemoryOutOfBounds));
+	}
+}
@@ -1,36 +1,137 @@
+mod builder;
+mod cfg;
+mod error;
+mod fuel;
 mod instruction;
+mod interpreter;
 mod leb128;
-use instruction::Instr;
-use std::collections::HashMap;
+mod linker;
+mod locals;
+mod memory;
+mod optimizer;
+mod parser;
+mod printer;
+mod ssa;
+mod validator;
+mod wasi;
+use error::{Counter, Location};
+pub use error::{DecodeError, ErrorKind};
+pub use instruction::Instr;
+pub use interpreter::{Imports, Instance, Trap, Value};
+pub use printer::Style;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{Error, Read, Result, Write};
+pub use wasi::Wasi;
 
 use leb128::{Reader, Writer};
 
+#[derive(Clone, Default, PartialEq)]
 pub struct Wasm {
+	pub types: Vec<FunctionType>,
 	pub imports: Vec<(FunctionType, String, String)>,
 	pub exports: HashMap<String, usize>,
 	pub start_function: Option<usize>,
 	pub functions: Vec<Function>,
+	pub memory_imports: Vec<(Limits, String, String)>,
+	pub memory_exports: HashMap<String, usize>,
+	pub memories: Vec<Limits>,
+	pub data: Vec<Data>,
+	pub global_imports: Vec<(GlobalType, String, String)>,
+	pub global_exports: HashMap<String, usize>,
+	pub globals: Vec<Global>,
+	pub table_imports: Vec<(TableType, String, String)>,
+	pub table_exports: HashMap<String, usize>,
+	pub tables: Vec<TableType>,
+	pub elements: Vec<Element>,
+	pub names: Names,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default, PartialEq)]
+pub struct Names {
+	pub module: Option<String>,
+	pub functions: BTreeMap<usize, String>,
+	pub locals: BTreeMap<usize, BTreeMap<usize, String>>,
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Function {
 	pub signature: FunctionType,
 	pub locals: Vec<DataType>,
 	pub body: Vec<Instr>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct FunctionType {
 	pub params: Vec<DataType>,
 	pub resuls: Vec<DataType>,
 }
 
 #[derive(Clone, PartialEq)]
+pub struct Limits {
+	pub min: u32,
+	pub max: Option<u32>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct TableType {
+	pub typ: DataType,
+	pub limits: Limits,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Element {
+	pub mode: ElementMode,
+	pub functions: Vec<usize>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum ElementMode {
+	Passive,
+	Active(usize, Vec<Instr>),
+	Declarative,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct GlobalType {
+	pub typ: DataType,
+	pub mutable: bool,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Global {
+	pub typ: GlobalType,
+	pub init: Vec<Instr>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Data {
+	pub mode: DataMode,
+	pub bytes: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum DataMode {
+	Passive,
+	Active(usize, Vec<Instr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockType {
+	Empty,
+	Value(DataType),
+	Type(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum DataType {
 	I32,
 	I64,
 	F32,
 	F64,
+	V128,
+	FuncRef,
+	ExternRef,
 }
 
 impl DataType {
@@ -40,7 +141,1079 @@ impl DataType {
 			0x7E => DataType::I64,
 			0x7D => DataType::F32,
 			0x7C => DataType::F64,
+			0x7B => DataType::V128,
+			0x70 => DataType::FuncRef,
+			0x6F => DataType::ExternRef,
 			_ => return None,
 		})
 	}
+
+	fn code(&self) -> u8 {
+		match self {
+			DataType::I32 => 0x7F,
+			DataType::I64 => 0x7E,
+			DataType::F32 => 0x7D,
+			DataType::F64 => 0x7C,
+			DataType::V128 => 0x7B,
+			DataType::FuncRef => 0x70,
+			DataType::ExternRef => 0x6F,
+		}
+	}
+
+	fn is_reference(&self) -> bool {
+		matches!(self, DataType::FuncRef | DataType::ExternRef)
+	}
+
+	fn read_from<R: Read>(reader: &mut R) -> Result<DataType> {
+		let code = reader.u8()?;
+		DataType::from(code).ok_or_else(|| ErrorKind::InvalidTypeCode(code).into())
+	}
+}
+
+impl BlockType {
+	fn read_from<R: Read>(reader: &mut R) -> Result<BlockType> {
+		let value = reader.i64()?;
+		let code = (value & 0x7F) as u8;
+		match value {
+			-64 => Ok(BlockType::Empty),
+			..=-1 => match DataType::from(code) {
+				Some(typ) if value >= -64 => Ok(BlockType::Value(typ)),
+				_ => Err(ErrorKind::InvalidTypeCode(code).into()),
+			},
+			0..=0xFFFF_FFFF => Ok(BlockType::Type(value as usize)),
+			_ => Err(Error::other(format!("Invalid block type index: {}", value))),
+		}
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		match self {
+			BlockType::Empty => writer.u8(0x40),
+			BlockType::Value(typ) => writer.u8(typ.code()),
+			BlockType::Type(index) => writer.i64(*index as i64),
+		}
+	}
+}
+
+impl fmt::Display for DataType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			DataType::I32 => "i32",
+			DataType::I64 => "i64",
+			DataType::F32 => "f32",
+			DataType::F64 => "f64",
+			DataType::V128 => "v128",
+			DataType::FuncRef => "funcref",
+			DataType::ExternRef => "externref",
+		})
+	}
+}
+
+impl FunctionType {
+	fn read_from<R: Read>(reader: &mut R) -> Result<FunctionType> {
+		match reader.u8()? {
+			0x60 => {}
+			form => return Err(ErrorKind::InvalidTypeCode(form).into()),
+		}
+		let params = read_vec(reader, DataType::read_from)?;
+		let resuls = read_vec(reader, DataType::read_from)?;
+		Ok(FunctionType { params, resuls })
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		writer.u8(0x60)?;
+		write_vec(writer, &self.params, |writer, typ| writer.u8(typ.code()))?;
+		write_vec(writer, &self.resuls, |writer, typ| writer.u8(typ.code()))
+	}
+}
+
+impl Limits {
+	fn read_from<R: Read>(reader: &mut R) -> Result<Limits> {
+		match reader.u8()? {
+			0x00 => Ok(Limits { min: reader.u32()?, max: None }),
+			0x01 => Ok(Limits { min: reader.u32()?, max: Some(reader.u32()?) }),
+			flags => Err(Error::other(format!("Unsupported limits: {:#04x}", flags))),
+		}
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		match self.max {
+			None => {
+				writer.u8(0x00)?;
+				writer.u32(self.min)
+			}
+			Some(max) => {
+				writer.u8(0x01)?;
+				writer.u32(self.min)?;
+				writer.u32(max)
+			}
+		}
+	}
+}
+
+impl TableType {
+	fn read_from<R: Read>(reader: &mut R) -> Result<TableType> {
+		let code = reader.u8()?;
+		match DataType::from(code).filter(DataType::is_reference) {
+			Some(typ) => Ok(TableType { typ, limits: Limits::read_from(reader)? }),
+			None => Err(ErrorKind::InvalidTypeCode(code).into()),
+		}
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		writer.u8(self.typ.code())?;
+		self.limits.write_to(writer)
+	}
+}
+
+impl Element {
+	fn read_from<R: Read>(reader: &mut R) -> Result<Element> {
+		let flags = reader.u32()?;
+		if flags > 7 {
+			return Err(Error::other(format!("Unsupported element segment: {}", flags)));
+		}
+		let mode = match flags & 3 {
+			0 => ElementMode::Active(0, read_expr(reader)?),
+			1 => ElementMode::Passive,
+			2 => ElementMode::Active(reader.u32()? as usize, read_expr(reader)?),
+			_ => ElementMode::Declarative,
+		};
+		match flags {
+			0 | 4 => {}
+			1..=3 => match reader.u8()? {
+				0x00 => {}
+				kind => return Err(Error::other(format!("Unsupported element kind: {}", kind))),
+			},
+			_ => match DataType::read_from(reader)? {
+				DataType::FuncRef => {}
+				typ => return Err(Error::other(format!("Unsupported element type: {}", typ))),
+			},
+		}
+		let functions = match flags < 4 {
+			true => read_vec(reader, |reader| Ok(reader.u32()? as usize))?,
+			false => read_vec(reader, |reader| match read_expr(reader)?[..] {
+				[Instr::ref_func(index)] => Ok(index),
+				_ => Err(Error::other("Unsupported element expression")),
+			})?,
+		};
+		Ok(Element { mode, functions })
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		match &self.mode {
+			ElementMode::Active(0, offset) => {
+				writer.u32(0)?;
+				Instr::write_block_to(offset, writer)?;
+			}
+			mode => {
+				match mode {
+					ElementMode::Passive => writer.u32(1)?,
+					ElementMode::Active(table, offset) => {
+						writer.u32(2)?;
+						writer.u32(*table as u32)?;
+						Instr::write_block_to(offset, writer)?;
+					}
+					ElementMode::Declarative => writer.u32(3)?,
+				}
+				writer.u8(0x00)?;
+			}
+		}
+		write_vec(writer, &self.functions, |writer, &index| writer.u32(index as u32))
+	}
+}
+
+impl GlobalType {
+	fn read_from<R: Read>(reader: &mut R) -> Result<GlobalType> {
+		let typ = DataType::read_from(reader)?;
+		match reader.u8()? {
+			0x00 => Ok(GlobalType { typ, mutable: false }),
+			0x01 => Ok(GlobalType { typ, mutable: true }),
+			flag => Err(Error::other(format!("Invalid mutability: {:#04x}", flag))),
+		}
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		writer.u8(self.typ.code())?;
+		writer.u8(self.mutable as u8)
+	}
+}
+
+impl Global {
+	fn read_from<R: Read>(reader: &mut R) -> Result<Global> {
+		Ok(Global { typ: GlobalType::read_from(reader)?, init: read_expr(reader)? })
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		self.typ.write_to(writer)?;
+		Instr::write_block_to(&self.init, writer)
+	}
+}
+
+impl Data {
+	fn read_from<R: Read>(reader: &mut R) -> Result<Data> {
+		let mode = match reader.u32()? {
+			0 => DataMode::Active(0, read_expr(reader)?),
+			1 => DataMode::Passive,
+			2 => DataMode::Active(reader.u32()? as usize, read_expr(reader)?),
+			flags => return Err(Error::other(format!("Unsupported data segment: {}", flags))),
+		};
+		let size = reader.u32()? as u64;
+		let mut bytes = vec![];
+		reader.take(size).read_to_end(&mut bytes)?;
+		if bytes.len() as u64 != size {
+			return Err(ErrorKind::UnexpectedEof.into());
+		}
+		Ok(Data { mode, bytes })
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		match &self.mode {
+			DataMode::Active(0, offset) => {
+				writer.u32(0)?;
+				Instr::write_block_to(offset, writer)?;
+			}
+			DataMode::Passive => writer.u32(1)?,
+			DataMode::Active(memory, offset) => {
+				writer.u32(2)?;
+				writer.u32(*memory as u32)?;
+				Instr::write_block_to(offset, writer)?;
+			}
+		}
+		writer.u32(self.bytes.len() as u32)?;
+		writer.array(&self.bytes)
+	}
+}
+
+impl Names {
+	fn is_empty(&self) -> bool {
+		self.module.is_none() && self.functions.is_empty() && self.locals.is_empty()
+	}
+
+	fn read_from<R: Read>(reader: &mut R) -> Result<Names> {
+		let mut names = Names::default();
+		while let Some(id) = read_section_id(reader)? {
+			let size = reader.u32()? as u64;
+			let subsection = &mut reader.take(size);
+			match id {
+				0 => names.module = Some(read_name(subsection)?),
+				1 => names.functions = read_name_map(subsection)?,
+				2 => {
+					for _ in 0..subsection.u32()? {
+						let function = subsection.u32()? as usize;
+						names.locals.insert(function, read_name_map(subsection)?);
+					}
+				}
+				_ => skip(subsection)?,
+			}
+			if subsection.limit() != 0 {
+				return Err(ErrorKind::SectionSizeMismatch.into());
+			}
+		}
+		Ok(names)
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		let mut subsections = vec![];
+		if let Some(module) = &self.module {
+			let mut content = vec![];
+			write_name(&mut content, module)?;
+			subsections.push((0, content));
+		}
+		if !self.functions.is_empty() {
+			let mut content = vec![];
+			write_name_map(&mut content, &self.functions)?;
+			subsections.push((1, content));
+		}
+		if !self.locals.is_empty() {
+			let mut content = vec![];
+			content.u32(self.locals.len() as u32)?;
+			for (&function, locals) in self.locals.iter() {
+				content.u32(function as u32)?;
+				write_name_map(&mut content, locals)?;
+			}
+			subsections.push((2, content));
+		}
+		for (id, content) in subsections {
+			writer.u8(id)?;
+			writer.u32(content.len() as u32)?;
+			writer.array(&content)?;
+		}
+		Ok(())
+	}
+}
+
+impl Function {
+	fn read_from<R: Read>(reader: &mut R, signature: FunctionType) -> Result<Function> {
+		let mut locals = vec![];
+		for _ in 0..reader.u32()? {
+			let count = reader.u32()?;
+			let typ = DataType::read_from(reader)?;
+			if locals.len() + count as usize > u32::MAX as usize {
+				return Err(Error::other("Too many locals"));
+			}
+			locals.extend((0..count).map(|_| typ.clone()));
+		}
+		let (body, ended_with_else) = Instr::read_block_from(reader)?;
+		if ended_with_else {
+			return Err(Error::other("Unexpected else in function body"));
+		}
+		Ok(Function { signature, locals, body })
+	}
+
+	fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		let mut groups: Vec<(u32, &DataType)> = vec![];
+		for typ in self.locals.iter() {
+			match groups.last_mut() {
+				Some((count, last)) if *last == typ => *count += 1,
+				_ => groups.push((1, typ)),
+			}
+		}
+		write_vec(writer, &groups, |writer, (count, typ)| {
+			writer.u32(*count)?;
+			writer.u8(typ.code())
+		})?;
+		Instr::write_block_to(&self.body, writer)
+	}
+}
+
+impl Wasm {
+	pub fn function_type(&self, index: usize) -> Option<&FunctionType> {
+		match self.imports.get(index) {
+			Some((typ, _, _)) => Some(typ),
+			None => Some(&self.functions.get(index - self.imports.len())?.signature),
+		}
+	}
+
+	pub fn global_type(&self, index: usize) -> Option<&GlobalType> {
+		match self.global_imports.get(index) {
+			Some((typ, _, _)) => Some(typ),
+			None => Some(&self.globals.get(index - self.global_imports.len())?.typ),
+		}
+	}
+
+	pub fn block_type(&self, typ: &BlockType) -> Option<FunctionType> {
+		Some(match typ {
+			BlockType::Empty => FunctionType { params: vec![], resuls: vec![] },
+			BlockType::Value(typ) => FunctionType { params: vec![], resuls: vec![typ.clone()] },
+			BlockType::Type(index) => self.types.get(*index)?.clone(),
+		})
+	}
+
+	pub fn type_index(&mut self, typ: FunctionType) -> usize {
+		match self.types.iter().position(|t| *t == typ) {
+			Some(index) => index,
+			None => {
+				self.types.push(typ);
+				self.types.len() - 1
+			}
+		}
+	}
+
+	pub fn table_type(&self, index: usize) -> Option<&TableType> {
+		match self.table_imports.get(index) {
+			Some((typ, _, _)) => Some(typ),
+			None => self.tables.get(index - self.table_imports.len()),
+		}
+	}
+
+	pub fn read_from<R: Read>(reader: &mut R) -> std::result::Result<Wasm, DecodeError> {
+		let mut counter = Counter::new(reader);
+		let mut at = Location::default();
+		Wasm::decode(&mut counter, &mut at).map_err(|error| counter.error(error, at))
+	}
+
+	/// Lists the id, offset and size of the contents of each section, without decoding them.
+	pub fn sections(bytes: &[u8]) -> std::result::Result<Vec<(u8, usize, usize)>, DecodeError> {
+		let mut reader = bytes;
+		let mut counter = Counter::new(&mut reader);
+		let mut at = Location::default();
+		let mut sections = vec![];
+		Wasm::scan(&mut counter, &mut at, &mut sections)
+			.map_err(|error| counter.error(error, at))?;
+		Ok(sections)
+	}
+
+	fn scan<R: Read>(
+		reader: &mut Counter<R>,
+		at: &mut Location,
+		sections: &mut Vec<(u8, usize, usize)>,
+	) -> Result<()> {
+		if &reader.array::<4>()? != b"\0asm" {
+			return Err(ErrorKind::BadMagic.into());
+		}
+		reader.array::<4>()?;
+		while let Some(id) = read_section_id(reader)? {
+			at.section = Some(id);
+			let size = reader.u32()? as u64;
+			let offset = reader.offset();
+			skip(&mut reader.take(size))?;
+			sections.push((id, offset, size as usize));
+		}
+		Ok(())
+	}
+
+	fn decode<R: Read>(reader: &mut R, at: &mut Location) -> Result<Wasm> {
+		if &reader.array::<4>()? != b"\0asm" {
+			return Err(ErrorKind::BadMagic.into());
+		}
+		let version = u32::from_le_bytes(reader.array()?);
+		if version != 1 {
+			return Err(ErrorKind::UnsupportedVersion(version).into());
+		}
+		let mut wasm = Wasm::default();
+		let mut signatures = vec![];
+		let mut data_count = None;
+		let mut last_section = 0;
+		while let Some(id) = read_section_id(reader)? {
+			at.section = Some(id);
+			if id != 0 && section_order(id) <= section_order(last_section) {
+				return Err(Error::other(format!("Unexpected {} section", section_name(id))));
+			}
+			last_section = if id == 0 { last_section } else { id };
+			let size = reader.u32()? as u64;
+			let section = &mut reader.take(size);
+			match id {
+				0 => {
+					if read_name(section)? == "name" {
+						wasm.names = Names::read_from(section)?;
+					}
+					skip(section)?;
+				}
+				1 => wasm.types = read_vec(section, FunctionType::read_from)?,
+				2 => {
+					for _ in 0..section.u32()? {
+						let module = read_name(section)?;
+						let name = read_name(section)?;
+						match section.u8()? {
+							0x00 => {
+								let typ = get_type(&wasm.types, section.u32()?)?;
+								wasm.imports.push((typ, module, name))
+							}
+							0x01 => {
+								let typ = TableType::read_from(section)?;
+								wasm.table_imports.push((typ, module, name))
+							}
+							0x02 => {
+								let limits = Limits::read_from(section)?;
+								wasm.memory_imports.push((limits, module, name))
+							}
+							0x03 => {
+								let typ = GlobalType::read_from(section)?;
+								wasm.global_imports.push((typ, module, name))
+							}
+							kind => {
+								return Err(Error::other(format!(
+									"Unsupported import kind: {}",
+									kind
+								)))
+							}
+						}
+					}
+				}
+				3 => signatures = read_vec(section, |reader| get_type(&wasm.types, reader.u32()?))?,
+				4 => wasm.tables = read_vec(section, TableType::read_from)?,
+				5 => wasm.memories = read_vec(section, Limits::read_from)?,
+				6 => wasm.globals = read_vec(section, Global::read_from)?,
+				7 => {
+					let mut names = std::collections::HashSet::new();
+					for _ in 0..section.u32()? {
+						let name = read_name(section)?;
+						let exports = match section.u8()? {
+							0x00 => &mut wasm.exports,
+							0x01 => &mut wasm.table_exports,
+							0x02 => &mut wasm.memory_exports,
+							0x03 => &mut wasm.global_exports,
+							kind => {
+								return Err(Error::other(format!(
+									"Unsupported export kind: {}",
+									kind
+								)))
+							}
+						};
+						exports.insert(name.clone(), section.u32()? as usize);
+						if !names.insert(name.clone()) {
+							return Err(Error::other(format!("Duplicate export: {}", name)));
+						}
+					}
+				}
+				8 => wasm.start_function = Some(section.u32()? as usize),
+				9 => wasm.elements = read_vec(section, Element::read_from)?,
+				10 => {
+					let count = section.u32()? as usize;
+					if count != signatures.len() {
+						return Err(Error::other("Function and code section sizes differ"));
+					}
+					for signature in signatures.iter() {
+						at.function = Some(wasm.imports.len() + wasm.functions.len());
+						let size = section.u32()? as u64;
+						let body = &mut section.take(size);
+						wasm.functions.push(Function::read_from(body, signature.clone())?);
+						if body.limit() != 0 {
+							return Err(ErrorKind::BodySizeMismatch.into());
+						}
+					}
+					at.function = None;
+				}
+				11 => {
+					wasm.data = read_vec(section, Data::read_from)?;
+					if data_count.is_some_and(|count| count != wasm.data.len()) {
+						return Err(Error::other("Data count and data section sizes differ"));
+					}
+				}
+				12 => data_count = Some(section.u32()? as usize),
+				_ => return Err(Error::other(format!("Unsupported {} section", section_name(id)))),
+			}
+			if section.limit() != 0 {
+				return Err(ErrorKind::SectionSizeMismatch.into());
+			}
+		}
+		at.section = None;
+		if wasm.functions.len() != signatures.len() {
+			return Err(Error::other("Function and code section sizes differ"));
+		}
+		if data_count.is_some_and(|count| count != wasm.data.len()) {
+			return Err(Error::other("Data count and data section sizes differ"));
+		}
+		let function_count = wasm.imports.len() + wasm.functions.len();
+		for &index in wasm.exports.values().chain(wasm.start_function.iter()) {
+			if index >= function_count {
+				return Err(Error::other(format!("Unknown function: {}", index)));
+			}
+		}
+		let table_count = wasm.table_imports.len() + wasm.tables.len();
+		for &index in wasm.table_exports.values() {
+			if index >= table_count {
+				return Err(Error::other(format!("Unknown table: {}", index)));
+			}
+		}
+		let memory_count = wasm.memory_imports.len() + wasm.memories.len();
+		for &index in wasm.memory_exports.values() {
+			if index >= memory_count {
+				return Err(Error::other(format!("Unknown memory: {}", index)));
+			}
+		}
+		let global_count = wasm.global_imports.len() + wasm.globals.len();
+		for &index in wasm.global_exports.values() {
+			if index >= global_count {
+				return Err(Error::other(format!("Unknown global: {}", index)));
+			}
+		}
+		Ok(wasm)
+	}
+}
+
+impl Wasm {
+	pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<()> {
+		writer.array(b"\0asm")?;
+		writer.array(&1u32.to_le_bytes())?;
+		let mut types = self.types.clone();
+		let mut type_index = |typ: &FunctionType| match types.iter().position(|t| t == typ) {
+			Some(index) => index as u32,
+			None => {
+				types.push(typ.clone());
+				types.len() as u32 - 1
+			}
+		};
+		let mut imports = vec![];
+		for (typ, module, name) in self.imports.iter() {
+			let mut desc = vec![0x00];
+			desc.u32(type_index(typ))?;
+			imports.push((module, name, desc));
+		}
+		for (typ, module, name) in self.table_imports.iter() {
+			let mut desc = vec![0x01];
+			typ.write_to(&mut desc)?;
+			imports.push((module, name, desc));
+		}
+		for (limits, module, name) in self.memory_imports.iter() {
+			let mut desc = vec![0x02];
+			limits.write_to(&mut desc)?;
+			imports.push((module, name, desc));
+		}
+		for (typ, module, name) in self.global_imports.iter() {
+			let mut desc = vec![0x03];
+			typ.write_to(&mut desc)?;
+			imports.push((module, name, desc));
+		}
+		let signatures: Vec<_> = self.functions.iter().map(|f| type_index(&f.signature)).collect();
+		let mut exports = vec![];
+		exports.extend(self.exports.iter().map(|(name, &index)| (name, 0x00, index)));
+		exports.extend(self.table_exports.iter().map(|(name, &index)| (name, 0x01, index)));
+		exports.extend(self.memory_exports.iter().map(|(name, &index)| (name, 0x02, index)));
+		exports.extend(self.global_exports.iter().map(|(name, &index)| (name, 0x03, index)));
+		exports.sort();
+
+		write_section(writer, 1, &types, |writer, typ| typ.write_to(writer))?;
+		write_section(writer, 2, &imports, |writer, (module, name, desc)| {
+			write_name(writer, module)?;
+			write_name(writer, name)?;
+			writer.array(desc)
+		})?;
+		write_section(writer, 3, &signatures, |writer, typ| writer.u32(*typ))?;
+		write_section(writer, 4, &self.tables, |writer, typ| typ.write_to(writer))?;
+		write_section(writer, 5, &self.memories, |writer, limits| limits.write_to(writer))?;
+		write_section(writer, 6, &self.globals, |writer, global| global.write_to(writer))?;
+		write_section(writer, 7, &exports, |writer, (name, kind, index)| {
+			write_name(writer, name)?;
+			writer.u8(*kind)?;
+			writer.u32(*index as u32)
+		})?;
+		if let Some(start) = self.start_function {
+			writer.u8(8)?;
+			let mut content = vec![];
+			content.u32(start as u32)?;
+			writer.u32(content.len() as u32)?;
+			writer.array(&content)?;
+		}
+		write_section(writer, 9, &self.elements, |writer, element| element.write_to(writer))?;
+		let passive = self.data.iter().any(|data| data.mode == DataMode::Passive);
+		if passive || self.functions.iter().any(|function| uses_data_count(&function.body)) {
+			writer.u8(12)?;
+			let mut content = vec![];
+			content.u32(self.data.len() as u32)?;
+			writer.u32(content.len() as u32)?;
+			writer.array(&content)?;
+		}
+		write_section(writer, 10, &self.functions, |writer, function| {
+			let mut content = vec![];
+			function.write_to(&mut content)?;
+			writer.u32(content.len() as u32)?;
+			writer.array(&content)
+		})?;
+		write_section(writer, 11, &self.data, |writer, data| data.write_to(writer))?;
+		if !self.names.is_empty() {
+			let mut content = vec![];
+			write_name(&mut content, "name")?;
+			self.names.write_to(&mut content)?;
+			writer.u8(0)?;
+			writer.u32(content.len() as u32)?;
+			writer.array(&content)?;
+		}
+		Ok(())
+	}
+}
+
+fn uses_data_count(body: &[Instr]) -> bool {
+	body.iter().any(|instr| match instr {
+		Instr::memory_init(..) | Instr::data_drop(..) => true,
+		Instr::block(_, body) | Instr::looped(_, body) => uses_data_count(body),
+		Instr::if_else(_, then, otherwise) => uses_data_count(then) || uses_data_count(otherwise),
+		_ => false,
+	})
+}
+
+pub fn section_name(id: u8) -> &'static str {
+	match id {
+		0 => "custom",
+		1 => "type",
+		2 => "import",
+		3 => "function",
+		4 => "table",
+		5 => "memory",
+		6 => "global",
+		7 => "export",
+		8 => "start",
+		9 => "element",
+		10 => "code",
+		11 => "data",
+		12 => "data count",
+		13 => "tag",
+		_ => "unknown",
+	}
+}
+
+fn section_order(id: u8) -> u8 {
+	match id {
+		12 => 10,
+		10 | 11 => id + 1,
+		_ => id,
+	}
+}
+
+fn read_section_id<R: Read>(reader: &mut R) -> Result<Option<u8>> {
+	let mut buffer = [0u8; 1];
+	Ok(match reader.read(&mut buffer)? {
+		0 => None,
+		_ => Some(buffer[0]),
+	})
+}
+
+fn skip<R: Read>(reader: &mut std::io::Take<R>) -> Result<()> {
+	std::io::copy(reader, &mut std::io::sink())?;
+	match reader.limit() {
+		0 => Ok(()),
+		_ => Err(ErrorKind::UnexpectedEof.into()),
+	}
+}
+
+fn read_vec<R: Read, T>(reader: &mut R, read: impl Fn(&mut R) -> Result<T>) -> Result<Vec<T>> {
+	let size = reader.u32()?;
+	let mut items = vec![];
+	for _ in 0..size {
+		items.push(read(reader)?);
+	}
+	Ok(items)
+}
+
+fn write_vec<W: Write, T>(
+	writer: &mut W,
+	items: &[T],
+	write: impl Fn(&mut W, &T) -> Result<()>,
+) -> Result<()> {
+	writer.u32(items.len() as u32)?;
+	for item in items {
+		write(writer, item)?;
+	}
+	Ok(())
+}
+
+fn write_section<W: Write, T>(
+	writer: &mut W,
+	id: u8,
+	items: &[T],
+	write: impl Fn(&mut Vec<u8>, &T) -> Result<()>,
+) -> Result<()> {
+	if items.is_empty() {
+		return Ok(());
+	}
+	let mut content = vec![];
+	write_vec(&mut content, items, write)?;
+	writer.u8(id)?;
+	writer.u32(content.len() as u32)?;
+	writer.array(&content)
+}
+
+fn read_name<R: Read>(reader: &mut R) -> Result<String> {
+	let size = reader.u32()? as u64;
+	let mut bytes = vec![];
+	reader.take(size).read_to_end(&mut bytes)?;
+	if bytes.len() as u64 != size {
+		return Err(ErrorKind::UnexpectedEof.into());
+	}
+	String::from_utf8(bytes).map_err(|_| Error::other("Name is not valid UTF-8"))
+}
+
+fn write_name<W: Write>(writer: &mut W, name: &str) -> Result<()> {
+	writer.u32(name.len() as u32)?;
+	writer.array(name.as_bytes())
+}
+
+fn read_name_map<R: Read>(reader: &mut R) -> Result<BTreeMap<usize, String>> {
+	let mut names = BTreeMap::new();
+	for _ in 0..reader.u32()? {
+		let index = reader.u32()? as usize;
+		names.insert(index, read_name(reader)?);
+	}
+	Ok(names)
+}
+
+fn write_name_map<W: Write>(writer: &mut W, names: &BTreeMap<usize, String>) -> Result<()> {
+	writer.u32(names.len() as u32)?;
+	for (&index, name) in names.iter() {
+		writer.u32(index as u32)?;
+		write_name(writer, name)?;
+	}
+	Ok(())
+}
+
+fn read_expr<R: Read>(reader: &mut R) -> Result<Vec<Instr>> {
+	match Instr::read_block_from(reader)? {
+		(expr, false) => Ok(expr),
+		(_, true) => Err(Error::other("Unexpected else in constant expression")),
+	}
+}
+
+fn get_type(types: &[FunctionType], index: u32) -> Result<FunctionType> {
+	let typ = types.get(index as usize);
+	typ.cloned().ok_or_else(|| Error::other(format!("Unknown type: {}", index)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[rustfmt::skip]
+	const ADD: &[u8] = &[
+		0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+		0x01, 0x0B, 0x02, 0x60, 0x01, 0x7F, 0x00, 0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+		0x02, 0x0B, 0x01, 0x03, b'e', b'n', b'v', 0x03, b'l', b'o', b'g', 0x00, 0x00,
+		0x03, 0x02, 0x01, 0x01,
+		0x07, 0x07, 0x01, 0x03, b'a', b'd', b'd', 0x00, 0x01,
+		0x0A, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6A, 0x0B,
+		0x00, 0x04, 0x03, b'f', b'o', b'o',
+	];
+
+	#[test]
+	fn reads_module() {
+		let wasm = Wasm::read_from(&mut &ADD[..]).unwrap();
+		assert_eq!(wasm.imports.len(), 1);
+		let (typ, module, name) = &wasm.imports[0];
+		assert!(typ.params == [DataType::I32] && typ.resuls.is_empty());
+		assert_eq!((module.as_str(), name.as_str()), ("env", "log"));
+		assert_eq!(wasm.exports["add"], 1);
+		assert_eq!(wasm.functions.len(), 1);
+		let add = &wasm.functions[0];
+		assert!(add.signature.params == [DataType::I32, DataType::I32]);
+		assert!(add.body == [Instr::local_get(0), Instr::local_get(1), Instr::i32_add]);
+	}
+
+	#[test]
+	fn lists_sections() {
+		let sections = Wasm::sections(ADD).unwrap();
+		let expected = [(1, 10, 11), (2, 23, 11), (3, 36, 2), (7, 40, 7), (10, 49, 9), (0, 60, 4)];
+		assert_eq!(sections, expected);
+		let error = Wasm::sections(&ADD[..ADD.len() - 1]).err().unwrap();
+		assert_eq!(error.to_string(), "offset 0x3f, custom section: Unexpected end of input");
+	}
+
+	#[test]
+	fn writes_module() {
+		let wasm = Wasm::read_from(&mut &ADD[..]).unwrap();
+		let mut bytes = vec![];
+		wasm.write_to(&mut bytes).unwrap();
+		assert_eq!(bytes, &ADD[..ADD.len() - 6]);
+		assert!(Wasm::read_from(&mut &bytes[..]).unwrap() == wasm);
+	}
+
+	#[test]
+	fn memory_and_data_round_trip() {
+		let wasm = Wasm {
+			memory_exports: HashMap::from([("memory".to_string(), 0)]),
+			memories: vec![Limits { min: 1, max: Some(2) }],
+			data: vec![
+				Data {
+					mode: DataMode::Active(0, vec![Instr::i32_const(8)]),
+					bytes: b"hi".to_vec(),
+				},
+				Data { mode: DataMode::Passive, bytes: vec![1, 2, 3] },
+			],
+			..Default::default()
+		};
+		let mut bytes = vec![];
+		wasm.write_to(&mut bytes).unwrap();
+		assert!(Wasm::read_from(&mut &bytes[..]).unwrap() == wasm);
+	}
+
+	#[test]
+	fn globals_round_trip() {
+		let stack_pointer = GlobalType { typ: DataType::I32, mutable: true };
+		let wasm = Wasm {
+			global_imports: vec![(
+				GlobalType { typ: DataType::I32, mutable: false },
+				"env".to_string(),
+				"__memory_base".to_string(),
+			)],
+			global_exports: HashMap::from([("__stack_pointer".to_string(), 1)]),
+			globals: vec![Global { typ: stack_pointer, init: vec![Instr::i32_const(65536)] }],
+			..Default::default()
+		};
+		let mut bytes = vec![];
+		wasm.write_to(&mut bytes).unwrap();
+		assert!(Wasm::read_from(&mut &bytes[..]).unwrap() == wasm);
+	}
+
+	#[test]
+	fn tables_round_trip() {
+		let typ = FunctionType { params: vec![DataType::I32], resuls: vec![] };
+		let limits = Limits { min: 2, max: Some(8) };
+		let offset = vec![Instr::i32_const(1)];
+		let wasm = Wasm {
+			types: vec![typ.clone(), FunctionType { params: vec![], resuls: vec![DataType::F64] }],
+			functions: vec![Function { signature: typ, locals: vec![], body: vec![] }],
+			table_imports: vec![(
+				TableType { typ: DataType::FuncRef, limits },
+				"env".to_string(),
+				"table".to_string(),
+			)],
+			table_exports: HashMap::from([("table".to_string(), 1)]),
+			tables: vec![TableType {
+				typ: DataType::FuncRef,
+				limits: Limits { min: 1, max: None },
+			}],
+			elements: vec![
+				Element { mode: ElementMode::Active(0, offset), functions: vec![0, 0] },
+				Element {
+					mode: ElementMode::Active(1, vec![Instr::i32_const(0)]),
+					functions: vec![0],
+				},
+				Element { mode: ElementMode::Passive, functions: vec![0] },
+				Element { mode: ElementMode::Declarative, functions: vec![0] },
+			],
+			..Default::default()
+		};
+		let mut bytes = vec![];
+		wasm.write_to(&mut bytes).unwrap();
+		assert!(Wasm::read_from(&mut &bytes[..]).unwrap() == wasm);
+	}
+
+	#[test]
+	fn reads_element_expressions() {
+		let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+		bytes.extend([0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00]);
+		bytes.extend([0x09, 0x0A, 0x01, 0x05, 0x70, 0x02, 0xD2, 0x00, 0x0B, 0xD2, 0x00, 0x0B]);
+		bytes.extend([0x0A, 0x04, 0x01, 0x02, 0x00, 0x0B]);
+		let wasm = Wasm::read_from(&mut &bytes[..]).unwrap();
+		assert!(
+			wasm.elements == vec![Element { mode: ElementMode::Passive, functions: vec![0, 0] }]
+		);
+		bytes[24] = 0xD0;
+		bytes[25] = 0x70;
+		let error = Wasm::read_from(&mut &bytes[..]).err().unwrap();
+		assert_eq!(
+			error.to_string(),
+			"offset 0x1a, element section: Unsupported element expression"
+		);
+	}
+
+	#[test]
+	fn names_round_trip() {
+		let mut wasm = Wasm::read_from(&mut &ADD[..]).unwrap();
+		wasm.names.module = Some("math".to_string());
+		wasm.names.functions.insert(1, "add".to_string());
+		wasm.names.locals.insert(1, BTreeMap::from([(0, "a".to_string()), (1, "b".to_string())]));
+		let mut bytes = vec![];
+		wasm.write_to(&mut bytes).unwrap();
+		assert!(Wasm::read_from(&mut &bytes[..]).unwrap() == wasm);
+	}
+
+	#[test]
+	fn every_instruction_round_trips() {
+		for row in include_str!("../../instructions.tsv").lines() {
+			let columns: Vec<_> = row.split('\t').collect();
+			let fields: Vec<_> = columns[1].split_whitespace().collect();
+			if matches!(fields[0], "end" | "then_end") {
+				continue;
+			}
+			let mut bytes = vec![];
+			let opcode: Vec<_> =
+				columns[0].split(' ').map(|byte| u8::from_str_radix(&byte[2..], 16)).collect();
+			bytes.u8(opcode[0].clone().unwrap()).unwrap();
+			for byte in &opcode[1..] {
+				bytes.u32(byte.clone().unwrap() as u32).unwrap();
+			}
+			for (i, field) in fields.iter().enumerate().skip(1) {
+				match *field {
+					"blocktype" => bytes.u8(0x7F),
+					"[instr]" if i + 1 < fields.len() => bytes.array(&[0x41, 0x2A, 0x05]),
+					"[instr]" => bytes.array(&[0x02, 0x40, 0x01, 0x0B, 0x41, 0x07, 0x0B]),
+					"[labelidx]" => bytes.array(&[0x02, 0x01, 0x00, 0xAC, 0x02]),
+					"u8" => bytes.u8(7),
+					"reftype" => bytes.u8(0x6F),
+					"[valtype]" => bytes.array(&[0x01, 0x70]),
+					"u32" => bytes.u32(624485),
+					"v128" | "u8x16" => bytes.array(&(0..16).map(|i| i * 17).collect::<Vec<_>>()),
+					"i32" => bytes.i32(-123456),
+					"i64" => bytes.i64(-1 << 40),
+					"f32" => bytes.f32(1.5),
+					"f64" => bytes.f64(-0.25),
+					idx => {
+						assert!(idx.ends_with("idx"));
+						bytes.u32(300)
+					}
+				}
+				.unwrap();
+			}
+			let instr = Instr::read_from(&mut &bytes[..]).unwrap();
+			let mut encoded = vec![];
+			instr.write_to(&mut encoded).unwrap();
+			assert_eq!(encoded, bytes, "{}", fields[0]);
+			assert!(Instr::read_from(&mut &encoded[..]).unwrap() == instr, "{}", fields[0]);
+		}
+	}
+
+	#[test]
+	fn if_without_else_round_trips() {
+		let bytes = [0x04, 0x40, 0x01, 0x0B];
+		let instr = Instr::read_from(&mut &bytes[..]).unwrap();
+		assert!(instr == Instr::if_else(BlockType::Empty, vec![Instr::nop], vec![]));
+		let mut encoded = vec![];
+		instr.write_to(&mut encoded).unwrap();
+		assert_eq!(encoded, bytes);
+	}
+
+	#[test]
+	fn block_types_round_trip() {
+		for (bytes, typ) in [
+			(vec![0x40], BlockType::Empty),
+			(vec![0x7B], BlockType::Value(DataType::V128)),
+			(vec![0x6F], BlockType::Value(DataType::ExternRef)),
+			(vec![0x00], BlockType::Type(0)),
+			(vec![0xC0, 0x00], BlockType::Type(64)),
+		] {
+			assert_eq!(BlockType::read_from(&mut &bytes[..]).unwrap(), typ);
+			let mut encoded = vec![];
+			typ.write_to(&mut encoded).unwrap();
+			assert_eq!(encoded, bytes);
+		}
+		let error = |bytes: &[u8]| BlockType::read_from(&mut &bytes[..]).err().unwrap().to_string();
+		assert_eq!(error(&[0x7A]), "Invalid type code: 0x7a");
+		assert_eq!(error(&[0x80, 0x80, 0x80, 0x80, 0x10]), "Invalid block type index: 4294967296");
+	}
+
+	#[test]
+	fn multi_value_round_trips() {
+		let pair = FunctionType { params: vec![DataType::I32], resuls: vec![DataType::I32; 2] };
+		let signature = FunctionType { params: vec![], resuls: vec![DataType::I32; 2] };
+		let body =
+			vec![Instr::i32_const(1), Instr::block(BlockType::Type(0), vec![Instr::i32_const(2)])];
+		let wasm = Wasm {
+			types: vec![pair, signature.clone()],
+			functions: vec![Function { signature, locals: vec![], body }],
+			..Default::default()
+		};
+		wasm.validate().unwrap();
+		let mut bytes = vec![];
+		wasm.write_to(&mut bytes).unwrap();
+		assert!(bytes.windows(6).any(|window| window == [0x41, 0x01, 0x02, 0x00, 0x41, 0x02]));
+		assert!(Wasm::read_from(&mut &bytes[..]).unwrap() == wasm);
+	}
+
+	#[test]
+	fn rejects_bad_modules() {
+		let error = |bytes: &[u8]| Wasm::read_from(&mut &bytes[..]).err().unwrap();
+		let at = |kind, offset, section, function| DecodeError { kind, offset, section, function };
+		assert_eq!(error(b"\0asn\x01\0\0\0"), at(ErrorKind::BadMagic, 0, None, None));
+		assert_eq!(error(b"\0asm\x02\0\0\0").kind, ErrorKind::UnsupportedVersion(2));
+		let tag = error(b"\0asm\x01\0\0\0\x0D\x01\x00");
+		assert_eq!(tag.to_string(), "offset 0x9, tag section: Unsupported tag section");
+		let truncated = ErrorKind::UnexpectedEof;
+		assert_eq!(error(&ADD[..ADD.len() - 3]), at(truncated, 61, Some(0), None));
+		let sizes = ErrorKind::Malformed("Function and code section sizes differ".to_string());
+		assert_eq!(error(&ADD[..38]), at(sizes, 38, None, None));
+
+		let mut bytes = ADD.to_vec();
+		bytes[56] = 0xFF;
+		let opcode = error(&bytes);
+		assert_eq!(opcode, at(ErrorKind::UnknownOpcode("0xFF".into()), 56, Some(10), Some(1)));
+		assert_eq!(
+			opcode.to_string(),
+			"offset 0x38, code section, function 1: Unknown opcode: 0xFF"
+		);
+		let source = std::error::Error::source(&opcode).unwrap();
+		assert_eq!(source.downcast_ref(), Some(&ErrorKind::UnknownOpcode("0xFF".into())));
+		bytes[56] = 0xFC;
+		bytes[57] = 0x80;
+		assert_eq!(error(&bytes).kind, ErrorKind::UnexpectedEof);
+		let mut bytes = ADD.to_vec();
+		bytes[53] = 0x80;
+		bytes.splice(54..54, [0x80, 0x80, 0x80, 0x80]);
+		bytes[50] += 4;
+		bytes[48] += 4;
+		assert_eq!(error(&bytes), at(ErrorKind::MalformedLeb128, 57, Some(10), Some(1)));
+		let mut bytes = ADD.to_vec();
+		bytes[50] = 0x06;
+		assert_eq!(error(&bytes), at(ErrorKind::UnexpectedEof, 57, Some(10), Some(1)));
+		let mut bytes = ADD.to_vec();
+		bytes.insert(58, 0x01);
+		bytes[50] += 1;
+		bytes[48] += 1;
+		assert_eq!(error(&bytes), at(ErrorKind::BodySizeMismatch, 58, Some(10), Some(1)));
+		let mut bytes = ADD.to_vec();
+		bytes[37] = 0x01;
+		bytes.insert(38, 0x00);
+		bytes[35] += 1;
+		assert_eq!(error(&bytes), at(ErrorKind::SectionSizeMismatch, 38, Some(3), None));
+		let mut bytes = ADD.to_vec();
+		bytes[13] = 0x7A;
+		assert_eq!(error(&bytes), at(ErrorKind::InvalidTypeCode(0x7A), 13, Some(1), None));
+	}
 }
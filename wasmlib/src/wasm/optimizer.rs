@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::instruction::Instr;
+use super::interpreter::{fold, Value};
+use super::{BlockType, ElementMode, Function, Wasm};
+
+impl Wasm {
+	/// Folds constant expressions, removes unreachable code and optimizes locals, returning the
+	/// number of bytes saved in each function body.
+	pub fn optimize(&mut self) -> Vec<usize> {
+		let mut saved = vec![];
+		for (i, function) in self.functions.iter_mut().enumerate() {
+			let before = size(function);
+			function.body = optimize(std::mem::take(&mut function.body));
+			let remap = function.optimize_locals();
+			if let Some(names) = self.names.locals.get_mut(&(self.imports.len() + i)) {
+				let mut renamed = BTreeMap::new();
+				for (local, name) in std::mem::take(names) {
+					if let Some(Some(local)) = remap.get(local) {
+						renamed.entry(*local as usize).or_insert(name);
+					}
+				}
+				*names = renamed;
+			}
+			saved.push(before.saturating_sub(size(function)));
+		}
+		saved
+	}
+
+	/// Removes the functions that cannot be reached from exports, the start function or element
+	/// segments and renumbers the rest. Returns the original indices of the removed functions.
+	pub fn remove_dead_functions(&mut self) -> Vec<usize> {
+		let imports = self.imports.len();
+		let mut roots: Vec<usize> = self.exports.values().copied().collect();
+		roots.extend(self.start_function);
+		for element in &self.elements {
+			roots.extend(&element.functions);
+			if let ElementMode::Active(_, offset) = &element.mode {
+				calls(offset, &mut roots);
+			}
+		}
+		for global in &self.globals {
+			calls(&global.init, &mut roots);
+		}
+		let mut reachable = BTreeSet::new();
+		while let Some(index) = roots.pop() {
+			if reachable.insert(index) && index >= imports {
+				calls(&self.functions[index - imports].body, &mut roots);
+			}
+		}
+
+		let mut remap: Vec<_> = (0..imports).map(Some).collect();
+		remap.resize(imports + self.functions.len(), None);
+		let mut removed = vec![];
+		let mut functions = vec![];
+		for (index, function) in std::mem::take(&mut self.functions).into_iter().enumerate() {
+			let index = imports + index;
+			if reachable.contains(&index) {
+				remap[index] = Some(imports + functions.len());
+				functions.push(function);
+			} else {
+				removed.push(index);
+			}
+		}
+		self.functions = functions;
+		let names = &mut self.names;
+		names.functions.retain(|index, _| removed.binary_search(index).is_err());
+		names.locals.retain(|index, _| removed.binary_search(index).is_err());
+		self.renumber_functions(|index| remap[index].unwrap());
+		removed
+	}
+
+	/// Rewrites every reference to a function index, for passes that add or remove functions.
+	pub(super) fn renumber_functions(&mut self, remap: impl Fn(usize) -> usize) {
+		for function in self.functions.iter_mut() {
+			renumber(&mut function.body, &remap);
+		}
+		for global in self.globals.iter_mut() {
+			renumber(&mut global.init, &remap);
+		}
+		for element in self.elements.iter_mut() {
+			element.functions.iter_mut().for_each(|index| *index = remap(*index));
+			if let ElementMode::Active(_, offset) = &mut element.mode {
+				renumber(offset, &remap);
+			}
+		}
+		self.exports.values_mut().for_each(|index| *index = remap(*index));
+		self.start_function = self.start_function.map(&remap);
+		let names = &mut self.names;
+		names.functions = std::mem::take(&mut names.functions)
+			.into_iter()
+			.map(|(index, name)| (remap(index), name))
+			.collect();
+		names.locals = std::mem::take(&mut names.locals)
+			.into_iter()
+			.map(|(index, locals)| (remap(index), locals))
+			.collect();
+	}
+}
+
+fn calls(body: &[Instr], functions: &mut Vec<usize>) {
+	for instr in body {
+		match instr {
+			Instr::call(index) | Instr::ref_func(index) => functions.push(*index),
+			Instr::block(_, body) | Instr::looped(_, body) => calls(body, functions),
+			Instr::if_else(_, then, otherwise) => {
+				calls(then, functions);
+				calls(otherwise, functions);
+			}
+			_ => {}
+		}
+	}
+}
+
+fn renumber(body: &mut [Instr], remap: &impl Fn(usize) -> usize) {
+	for instr in body {
+		match instr {
+			Instr::call(index) | Instr::ref_func(index) => *index = remap(*index),
+			Instr::block(_, body) | Instr::looped(_, body) => renumber(body, remap),
+			Instr::if_else(_, then, otherwise) => {
+				renumber(then, remap);
+				renumber(otherwise, remap);
+			}
+			_ => {}
+		}
+	}
+}
+
+fn size(function: &Function) -> usize {
+	let mut bytes = vec![];
+	function.write_to(&mut bytes).unwrap();
+	bytes.len()
+}
+
+fn optimize(body: Vec<Instr>) -> Vec<Instr> {
+	use Instr::*;
+	let mut result = vec![];
+	for instr in body {
+		match instr {
+			block(typ, body) => push_block(&mut result, typ, optimize(body)),
+			looped(typ, body) => result.push(looped(typ, optimize(body))),
+			if_else(typ, then, otherwise) => {
+				let (then, otherwise) = (optimize(then), optimize(otherwise));
+				match condition(&mut result) {
+					Some(true) => push_block(&mut result, typ, then),
+					Some(false) => push_block(&mut result, typ, otherwise),
+					None => result.push(if_else(typ, then, otherwise)),
+				}
+			}
+			br_if(depth) => match condition(&mut result) {
+				Some(true) => result.push(br(depth)),
+				Some(false) => {}
+				None => result.push(br_if(depth)),
+			},
+			br_table(labels) => match constant(result.last()) {
+				Some(Value::I32(index)) if !labels.is_empty() => {
+					result.pop();
+					result.push(br(labels[(index as u32 as usize).min(labels.len() - 1)]));
+				}
+				_ => result.push(br_table(labels)),
+			},
+			drop if constant(result.last()).is_some() => {
+				result.pop();
+			}
+			instr => {
+				let instr = fold_tail(&mut result, &instr).unwrap_or(instr);
+				result.push(instr);
+			}
+		}
+		if matches!(result.last(), Some(unreachable | br(_) | br_table(_) | return_result)) {
+			break;
+		}
+	}
+	result
+}
+
+fn push_block(result: &mut Vec<Instr>, typ: BlockType, body: Vec<Instr>) {
+	if typ != BlockType::Empty || !body.is_empty() {
+		result.push(Instr::block(typ, body));
+	}
+}
+
+fn constant(instr: Option<&Instr>) -> Option<Value> {
+	match instr? {
+		Instr::i32_const(x) => Some(Value::I32(*x)),
+		Instr::i64_const(x) => Some(Value::I64(*x)),
+		Instr::f32_const(x) => Some(Value::F32(*x)),
+		Instr::f64_const(x) => Some(Value::F64(*x)),
+		Instr::v128_const(x) => Some(Value::V128(*x)),
+		_ => None,
+	}
+}
+
+fn condition(result: &mut Vec<Instr>) -> Option<bool> {
+	let Some(Value::I32(x)) = constant(result.last()) else {
+		return None;
+	};
+	result.pop();
+	Some(x != 0)
+}
+
+// Replaces `instr` and the constants it consumes with its result, unless it would trap.
+fn fold_tail(result: &mut Vec<Instr>, instr: &Instr) -> Option<Instr> {
+	let (params, results) = instr.signature().split_once('>')?;
+	let count = params.split_whitespace().count();
+	if count == 0 || count > result.len() || results.split_whitespace().count() != 1 {
+		return None;
+	}
+	let start = result.len() - count;
+	let args: Option<Vec<_>> = result[start..].iter().map(|instr| constant(Some(instr))).collect();
+	let folded = match fold(instr, &args?)? {
+		Value::I32(x) => Instr::i32_const(x),
+		Value::I64(x) => Instr::i64_const(x),
+		Value::F32(x) => Instr::f32_const(x),
+		Value::F64(x) => Instr::f64_const(x),
+		Value::V128(x) => Instr::v128_const(x),
+		_ => return None,
+	};
+	result.truncate(start);
+	Some(folded)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::interpreter::Instance;
+	use crate::wasm::{DataType, FunctionType};
+	use std::collections::HashMap;
+	use DataType::*;
+	use Instr::*;
+
+	fn function(body: Vec<Instr>) -> Wasm {
+		let signature = FunctionType { params: vec![I32], resuls: vec![I32] };
+		Wasm {
+			exports: HashMap::from([("main".to_string(), 0)]),
+			functions: vec![Function { signature, locals: vec![], body }],
+			..Default::default()
+		}
+	}
+
+	fn optimized(body: Vec<Instr>) -> Vec<Instr> {
+		let mut wasm = function(body);
+		wasm.validate().unwrap();
+		wasm.optimize();
+		wasm.validate().unwrap();
+		wasm.functions.remove(0).body
+	}
+
+	#[test]
+	fn folds_constants() {
+		let body =
+			vec![i32_const(6), i32_const(7), i32_mul, i32_const(2), i32_sub, local_get(0), i32_add];
+		assert_eq!(optimized(body), vec![i32_const(40), local_get(0), i32_add]);
+		let body = vec![i64_const(3), i64_const(4), i64_lt_s, f64_const(1.5), f64_neg, drop];
+		assert_eq!(optimized(body), vec![i32_const(1)]);
+		let body = vec![f32_const(2.5), f32_const(0.5), f32_add, i32_trunc_f32_s, i32_eqz];
+		assert_eq!(optimized(body), vec![i32_const(0)]);
+	}
+
+	#[test]
+	fn keeps_trapping_instructions() {
+		let body = vec![i32_const(1), i32_const(0), i32_div_u];
+		assert_eq!(optimized(body.clone()), body);
+		let body = vec![f32_const(f32::NAN), i32_trunc_f32_u];
+		assert!(matches!(optimized(body)[..], [f32_const(x), i32_trunc_f32_u] if x.is_nan()));
+		let body = vec![i32_const(8), i32_load(2, 0)];
+		let mut wasm = function(body.clone());
+		wasm.memories.push(crate::wasm::Limits { min: 1, max: None });
+		wasm.optimize();
+		assert_eq!(wasm.functions[0].body, body);
+	}
+
+	#[test]
+	fn simplifies_branches() {
+		let body = vec![
+			i32_const(1),
+			if_else(BlockType::Empty, vec![nop], vec![unreachable]),
+			block(
+				BlockType::Empty,
+				vec![i32_const(0), br_if(0), i32_const(2), br_if(0), unreachable],
+			),
+			i32_const(9),
+		];
+		assert_eq!(
+			optimized(body),
+			vec![
+				block(BlockType::Empty, vec![nop]),
+				block(BlockType::Empty, vec![br(0)]),
+				i32_const(9)
+			]
+		);
+		let body = vec![
+			block(
+				BlockType::Empty,
+				vec![block(
+					BlockType::Empty,
+					vec![i32_const(0), i32_const(1), i32_sub, br_table(vec![0, 1])],
+				)],
+			),
+			i32_const(0),
+			if_else(BlockType::Value(I32), vec![i32_const(1)], vec![i32_const(2)]),
+		];
+		assert_eq!(
+			optimized(body),
+			vec![
+				block(BlockType::Empty, vec![block(BlockType::Empty, vec![br(1)])]),
+				block(BlockType::Value(I32), vec![i32_const(2)])
+			]
+		);
+	}
+
+	#[test]
+	fn removes_dead_code_and_reports_savings() {
+		let body = vec![
+			local_get(0),
+			if_else(
+				BlockType::Empty,
+				vec![i32_const(1), return_result, i32_const(2), drop],
+				vec![],
+			),
+			i32_const(3),
+			i32_const(4),
+			i32_add,
+			return_result,
+			i32_const(1024),
+			drop,
+			nop,
+		];
+		let mut wasm = function(body);
+		let mut instance = Instance::new(&wasm).unwrap();
+		let expected: Vec<_> = (0..2).map(|x| instance.invoke("main", &[Value::I32(x)])).collect();
+		assert_eq!(wasm.optimize(), vec![11]);
+		wasm.validate().unwrap();
+		assert_eq!(
+			wasm.functions[0].body,
+			vec![
+				local_get(0),
+				if_else(BlockType::Empty, vec![i32_const(1), return_result], vec![]),
+				i32_const(7),
+				return_result
+			]
+		);
+		let mut instance = Instance::new(&wasm).unwrap();
+		let actual: Vec<_> = (0..2).map(|x| instance.invoke("main", &[Value::I32(x)])).collect();
+		assert_eq!(actual, expected);
+	}
+
+	#[test]
+	fn removes_dead_functions() {
+		let mut wasm = Wasm::from_wat(
+			r#"
+			(module
+				(import "env" "log" (func $log (param i32)))
+				(table 1 funcref)
+				(elem (i32.const 0) $callback)
+				(func $unused (result i32)
+					call $helper)
+				(func $helper (result i32)
+					i32.const 2)
+				(func $callback (param i32) (result i32)
+					local.get 0
+					call $helper
+					i32.add)
+				(func $main (export "main") (param $x i32) (result i32)
+					local.get $x
+					call $log
+					local.get $x
+					i32.const 0
+					call_indirect (param i32) (result i32))
+				(func $recursive (result i32)
+					call $recursive))
+			"#,
+		)
+		.unwrap();
+		assert_eq!(wasm.remove_dead_functions(), vec![1, 5]);
+		wasm.validate().unwrap();
+		assert_eq!(wasm.functions.len(), 3);
+		assert_eq!(wasm.exports["main"], 3);
+		assert_eq!(wasm.elements[0].functions, vec![2]);
+		assert_eq!(wasm.functions[1].body[1], call(1));
+		let names: Vec<_> = wasm.names.functions.values().map(String::as_str).collect();
+		assert_eq!(names, ["log", "helper", "callback", "main"]);
+		assert_eq!(wasm.names.locals[&3][&0], "x");
+		let mut imports = crate::wasm::interpreter::Imports::new();
+		imports.function("env", "log", &[I32], &[], |_, _| Ok(vec![]));
+		let mut instance = Instance::with_imports(&wasm, imports).unwrap();
+		assert_eq!(instance.invoke("main", &[Value::I32(5)]), Ok(vec![Value::I32(7)]));
+	}
+}
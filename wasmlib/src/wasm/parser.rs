@@ -0,0 +1,1455 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::instruction::Instr;
+use super::memory::PAGE_SIZE;
+use super::validator::memarg;
+use super::{BlockType, Data, DataMode, DataType, Element, ElementMode, Function, FunctionType};
+use super::{Global, GlobalType, Limits, TableType, Wasm};
+
+#[derive(PartialEq)]
+pub struct ParseError {
+	pub line: usize,
+	pub column: usize,
+	pub message: String,
+}
+
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}:{}: {}", self.line, self.column, self.message)
+	}
+}
+
+impl fmt::Debug for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+type Parse<T = ()> = Result<T, ParseError>;
+
+#[derive(Clone, Copy)]
+struct Position {
+	line: usize,
+	column: usize,
+}
+
+fn error<T>(at: Position, message: impl Into<String>) -> Parse<T> {
+	Err(ParseError { line: at.line, column: at.column, message: message.into() })
+}
+
+impl Wasm {
+	pub fn from_wat(text: &str) -> Parse<Wasm> {
+		let nodes = tree(text)?;
+		let end = nodes.last().map_or(Position { line: 1, column: 1 }, |node| node.at);
+		let mut fields = Cursor { nodes: &nodes, end };
+		let mut module = None;
+		if let [node] = &nodes[..] {
+			if let Some(mut cursor) = node.list("module") {
+				module = cursor.id().map(|(id, _)| id[1..].to_string());
+				fields = cursor;
+			}
+		}
+		let mut parser = Parser::default();
+		parser.wasm.names.module = module;
+		parser.declare(fields.clone())?;
+		parser.define(fields)?;
+		Ok(parser.wasm)
+	}
+}
+
+enum Kind {
+	List(Vec<Node>, Position),
+	Atom(String),
+	Text(Vec<u8>),
+}
+
+struct Node {
+	kind: Kind,
+	at: Position,
+}
+
+impl Node {
+	fn list(&self, head: &str) -> Option<Cursor<'_>> {
+		match &self.kind {
+			Kind::List(nodes, end) => match nodes.first().map(|node| &node.kind) {
+				Some(Kind::Atom(word)) if word == head => {
+					Some(Cursor { nodes: &nodes[1..], end: *end })
+				}
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+
+	fn describe(&self) -> String {
+		match &self.kind {
+			Kind::List(..) => "(".to_string(),
+			Kind::Atom(word) => word.clone(),
+			Kind::Text(_) => "string".to_string(),
+		}
+	}
+}
+
+fn tree(text: &str) -> Parse<Vec<Node>> {
+	let mut chars = text.chars().peekable();
+	let mut at = Position { line: 1, column: 1 };
+	let mut stack: Vec<(Vec<Node>, Position)> = vec![(vec![], at)];
+	let advance = |c: char, at: &mut Position| match c {
+		'\n' => *at = Position { line: at.line + 1, column: 1 },
+		_ => at.column += 1,
+	};
+	while let Some(c) = chars.next() {
+		let start = at;
+		advance(c, &mut at);
+		match c {
+			c if c.is_whitespace() => {}
+			';' if chars.peek() == Some(&';') => {
+				while let Some(c) = chars.next_if(|&c| c != '\n') {
+					advance(c, &mut at);
+				}
+			}
+			'(' if chars.next_if_eq(&';').is_some() => {
+				advance(';', &mut at);
+				let mut depth = 1;
+				while depth > 0 {
+					let Some(c) = chars.next() else {
+						return error(start, "Unterminated block comment");
+					};
+					advance(c, &mut at);
+					let nested = match c {
+						'(' => chars.next_if_eq(&';').map(|c| (c, 1)),
+						';' => chars.next_if_eq(&')').map(|c| (c, -1)),
+						_ => None,
+					};
+					if let Some((c, delta)) = nested {
+						advance(c, &mut at);
+						depth += delta;
+					}
+				}
+			}
+			'(' => stack.push((vec![], start)),
+			')' => {
+				let Some((nodes, open)) = stack.pop().filter(|_| !stack.is_empty()) else {
+					return error(start, "Unexpected )");
+				};
+				let node = Node { kind: Kind::List(nodes, start), at: open };
+				stack.last_mut().unwrap().0.push(node);
+			}
+			'"' => {
+				let mut bytes = vec![];
+				loop {
+					let Some(c) = chars.next() else {
+						return error(start, "Unterminated string");
+					};
+					let escape = at;
+					advance(c, &mut at);
+					match c {
+						'"' => break,
+						'\\' => {
+							let Some(c) = chars.next() else {
+								return error(start, "Unterminated string");
+							};
+							advance(c, &mut at);
+							let byte = match c {
+								't' => b'\t',
+								'n' => b'\n',
+								'r' => b'\r',
+								'"' | '\'' | '\\' => c as u8,
+								'u' => {
+									let mut digits = String::new();
+									let open = chars.next_if_eq(&'{').is_some();
+									while let Some(c) = chars.next_if(|&c| open && c != '}') {
+										advance(c, &mut at);
+										digits.push(c);
+									}
+									let closed = chars.next_if_eq(&'}').is_some();
+									at.column += open as usize + closed as usize;
+									let c = u32::from_str_radix(&digits.replace('_', ""), 16)
+										.ok()
+										.and_then(char::from_u32)
+										.filter(|_| closed);
+									let Some(c) = c else {
+										return error(escape, "Invalid unicode escape");
+									};
+									bytes.extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+									continue;
+								}
+								c if c.is_ascii_hexdigit() => {
+									let Some(low) = chars.next_if(char::is_ascii_hexdigit) else {
+										return error(escape, "Invalid escape sequence");
+									};
+									advance(low, &mut at);
+									(c.to_digit(16).unwrap() * 16 + low.to_digit(16).unwrap()) as u8
+								}
+								_ => return error(escape, "Invalid escape sequence"),
+							};
+							bytes.push(byte);
+						}
+						c => bytes.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+					}
+				}
+				stack.last_mut().unwrap().0.push(Node { kind: Kind::Text(bytes), at: start });
+			}
+			c => {
+				let mut word = c.to_string();
+				while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && !"()\";".contains(c)) {
+					advance(c, &mut at);
+					word.push(c);
+				}
+				stack.last_mut().unwrap().0.push(Node { kind: Kind::Atom(word), at: start });
+			}
+		}
+	}
+	if stack.len() > 1 {
+		return error(stack.pop().unwrap().1, "Unclosed (");
+	}
+	Ok(stack.pop().unwrap().0)
+}
+
+#[derive(Clone)]
+struct Cursor<'a> {
+	nodes: &'a [Node],
+	end: Position,
+}
+
+impl<'a> Cursor<'a> {
+	fn peek(&self) -> Option<&'a Node> {
+		self.nodes.first()
+	}
+
+	fn at(&self) -> Position {
+		self.peek().map_or(self.end, |node| node.at)
+	}
+
+	fn next(&mut self) -> Parse<&'a Node> {
+		let Some((node, rest)) = self.nodes.split_first() else {
+			return error(self.end, "Unexpected )");
+		};
+		self.nodes = rest;
+		Ok(node)
+	}
+
+	fn done(&self) -> Parse {
+		match self.peek() {
+			Some(node) => error(node.at, format!("Unexpected {}", node.describe())),
+			None => Ok(()),
+		}
+	}
+
+	fn peek_atom(&self) -> Option<&'a str> {
+		match &self.peek()?.kind {
+			Kind::Atom(word) => Some(word),
+			_ => None,
+		}
+	}
+
+	fn atom(&mut self, what: &str) -> Parse<(&'a str, Position)> {
+		match self.peek_atom() {
+			Some(word) => Ok((word, self.next()?.at)),
+			None => error(self.at(), format!("Expected {}", what)),
+		}
+	}
+
+	fn keyword(&mut self, keyword: &str) -> bool {
+		let found = self.peek_atom() == Some(keyword);
+		if found {
+			self.nodes = &self.nodes[1..];
+		}
+		found
+	}
+
+	fn id(&mut self) -> Option<(&'a str, Position)> {
+		let word = self.peek_atom().filter(|word| word.starts_with('$'))?;
+		Some((word, self.next().ok()?.at))
+	}
+
+	fn list(&mut self, head: &str) -> Option<Cursor<'a>> {
+		let cursor = self.peek()?.list(head)?;
+		self.nodes = &self.nodes[1..];
+		Some(cursor)
+	}
+
+	fn text(&mut self) -> Parse<Option<&'a [u8]>> {
+		match self.peek().map(|node| &node.kind) {
+			Some(Kind::Text(bytes)) => {
+				self.next()?;
+				Ok(Some(bytes))
+			}
+			_ => Ok(None),
+		}
+	}
+
+	fn name(&mut self) -> Parse<String> {
+		let at = self.at();
+		let Some(bytes) = self.text()? else {
+			return error(at, "Expected string");
+		};
+		String::from_utf8(bytes.to_vec()).or_else(|_| error(at, "Malformed UTF-8 encoding"))
+	}
+
+	fn u32(&mut self) -> Parse<u32> {
+		let (word, at) = self.atom("integer")?;
+		match integer(word) {
+			Some((false, value)) if value <= u32::MAX as u64 => Ok(value as u32),
+			_ => error(at, format!("Invalid integer: {}", word)),
+		}
+	}
+
+	fn is_index(&self) -> bool {
+		self.peek_atom()
+			.is_some_and(|word| word.starts_with(|c: char| c == '$' || c.is_ascii_digit()))
+	}
+
+	fn indices(&self) -> usize {
+		let mut cursor = self.clone();
+		let mut count = 0;
+		while cursor.is_index() {
+			cursor.nodes = &cursor.nodes[1..];
+			count += 1;
+		}
+		count
+	}
+}
+
+#[derive(Default)]
+struct Parser {
+	wasm: Wasm,
+	types: HashMap<String, usize>,
+	functions: HashMap<String, usize>,
+	tables: HashMap<String, usize>,
+	memories: HashMap<String, usize>,
+	globals: HashMap<String, usize>,
+	elements: HashMap<String, usize>,
+	data: HashMap<String, usize>,
+}
+
+type Ids<'a> = Vec<Option<(&'a str, Position)>>;
+
+#[derive(Default)]
+struct Scope {
+	locals: HashMap<String, usize>,
+	labels: Vec<Option<String>>,
+}
+
+const KINDS: [&str; 4] = ["func", "table", "memory", "global"];
+
+impl Parser {
+	fn declare(&mut self, mut fields: Cursor) -> Parse {
+		let mut counts = [(0, 0); 4];
+		let mut other = [0, 0];
+		while let Some(node) = fields.peek() {
+			let Some((head, _)) = node.head() else {
+				return error(node.at, format!("Unexpected {}", node.describe()));
+			};
+			let mut field = node.list(head).unwrap();
+			fields.next()?;
+			let (kind, imported) = match head {
+				"type" => {
+					let id = field.id();
+					let Some(mut typ) = field.list("func") else {
+						return error(field.at(), "Expected function type");
+					};
+					let (signature, _) = signature(&mut typ)?;
+					typ.done()?;
+					field.done()?;
+					if let Some((id, at)) = id {
+						insert(&mut self.types, id, self.wasm.types.len(), at)?;
+					}
+					self.wasm.types.push(signature);
+					continue;
+				}
+				"import" => {
+					field.name()?;
+					field.name()?;
+					let at = field.at();
+					let desc = field.peek().and_then(Node::head).map(|(head, _)| head);
+					let Some(kind) = KINDS.iter().position(|&kind| Some(kind) == desc) else {
+						return error(at, "Expected import description");
+					};
+					field = field.list(KINDS[kind]).unwrap();
+					(kind, true)
+				}
+				"elem" | "data" => {
+					let (ids, index) = match head {
+						"elem" => (&mut self.elements, &mut other[0]),
+						_ => (&mut self.data, &mut other[1]),
+					};
+					if let Some((id, at)) = field.id() {
+						insert(ids, id, *index, at)?;
+					}
+					*index += 1;
+					continue;
+				}
+				"export" | "start" => continue,
+				head => match KINDS.iter().position(|&kind| kind == head) {
+					Some(kind) => {
+						let mut rest = field.clone();
+						rest.id();
+						while rest.list("export").is_some() {}
+						(kind, rest.list("import").is_some())
+					}
+					None => return error(node.at, format!("Unknown module field: {}", head)),
+				},
+			};
+			let (imports, definitions) = &mut counts[kind];
+			if imported && *definitions > 0 {
+				return error(node.at, format!("Import after {} definition", KINDS[kind]));
+			}
+			if let Some((id, at)) = field.id() {
+				let ids = match kind {
+					0 => &mut self.functions,
+					1 => &mut self.tables,
+					2 => &mut self.memories,
+					_ => &mut self.globals,
+				};
+				insert(ids, id, *imports + *definitions, at)?;
+			}
+			match imported {
+				true => *imports += 1,
+				false => *definitions += 1,
+			}
+		}
+		Ok(())
+	}
+
+	fn define(&mut self, mut fields: Cursor) -> Parse {
+		while let Some(node) = fields.peek() {
+			fields.next()?;
+			let (head, _) = node.head().unwrap();
+			let mut field = node.list(head).unwrap();
+			match head {
+				"type" => continue,
+				"import" => {
+					let module = field.name()?;
+					let name = field.name()?;
+					let head = field.peek().and_then(Node::head).unwrap().0;
+					let mut desc = field.list(head).unwrap();
+					let id = desc.id();
+					self.import(head, id, &mut desc, module, name)?;
+					desc.done()?;
+				}
+				"func" => self.function(&mut field)?,
+				"table" => self.table(&mut field)?,
+				"memory" => self.memory(&mut field)?,
+				"global" => self.global(&mut field)?,
+				"export" => {
+					let at = field.at();
+					let name = field.name()?;
+					let head = field.peek().and_then(Node::head).map(|(head, _)| head);
+					let Some(kind) = head.filter(|head| KINDS.contains(head)) else {
+						return error(field.at(), "Expected export description");
+					};
+					let mut desc = field.list(kind).unwrap();
+					let index = self.index(&mut desc, kind)?;
+					desc.done()?;
+					self.export(kind, name, index, at)?;
+				}
+				"start" => {
+					self.wasm.start_function = Some(self.index(&mut field, "func")?);
+				}
+				"elem" => self.element(&mut field)?,
+				"data" => self.data(&mut field)?,
+				_ => unreachable!(),
+			}
+			field.done()?;
+		}
+		Ok(())
+	}
+
+	fn import(
+		&mut self,
+		kind: &str,
+		id: Option<(&str, Position)>,
+		field: &mut Cursor,
+		module: String,
+		name: String,
+	) -> Parse {
+		match kind {
+			"func" => {
+				let (_, typ, _) = self.type_use(field)?;
+				if let Some((id, _)) = id {
+					self.wasm.names.functions.insert(self.wasm.imports.len(), id[1..].to_string());
+				}
+				self.wasm.imports.push((typ, module, name));
+			}
+			"table" => self.wasm.table_imports.push((table_type(field)?, module, name)),
+			"memory" => self.wasm.memory_imports.push((limits(field)?, module, name)),
+			_ => self.wasm.global_imports.push((global_type(field)?, module, name)),
+		}
+		Ok(())
+	}
+
+	fn exports(&mut self, field: &mut Cursor, kind: &str, index: usize) -> Parse {
+		while let Some(mut export) = field.list("export") {
+			let at = export.at();
+			let name = export.name()?;
+			export.done()?;
+			self.export(kind, name, index, at)?;
+		}
+		Ok(())
+	}
+
+	fn export(&mut self, kind: &str, name: String, index: usize, at: Position) -> Parse {
+		let wasm = &mut self.wasm;
+		let exports =
+			[&wasm.exports, &wasm.table_exports, &wasm.memory_exports, &wasm.global_exports];
+		if exports.iter().any(|exports| exports.contains_key(&name)) {
+			return error(at, format!("Duplicate export: {}", name));
+		}
+		let exports = match kind {
+			"func" => &mut wasm.exports,
+			"table" => &mut wasm.table_exports,
+			"memory" => &mut wasm.memory_exports,
+			_ => &mut wasm.global_exports,
+		};
+		exports.insert(name, index);
+		Ok(())
+	}
+
+	fn inline_import(&mut self, field: &mut Cursor) -> Parse<Option<(String, String)>> {
+		let Some(mut import) = field.list("import") else {
+			return Ok(None);
+		};
+		let names = (import.name()?, import.name()?);
+		import.done()?;
+		Ok(Some(names))
+	}
+
+	fn function(&mut self, field: &mut Cursor) -> Parse {
+		let index = self.wasm.imports.len() + self.wasm.functions.len();
+		let id = field.id();
+		self.exports(field, "func", index)?;
+		if let Some((module, name)) = self.inline_import(field)? {
+			return self.import("func", id, field, module, name);
+		}
+		if let Some((id, _)) = id {
+			self.wasm.names.functions.insert(index, id[1..].to_string());
+		}
+		let (_, signature, mut ids) = self.type_use(field)?;
+		let mut locals = vec![];
+		while let Some(mut local) = field.list("local") {
+			if let Some(id) = local.id() {
+				ids.push(Some(id));
+				locals.push(value_type(&mut local)?);
+				local.done()?;
+				continue;
+			}
+			while local.peek().is_some() {
+				ids.push(None);
+				locals.push(value_type(&mut local)?);
+			}
+		}
+		let mut scope = Scope::default();
+		for (i, id) in ids.iter().enumerate() {
+			if let Some((id, at)) = id {
+				insert(&mut scope.locals, id, i, *at)?;
+				let names = self.wasm.names.locals.entry(index).or_default();
+				names.insert(i, id[1..].to_string());
+			}
+		}
+		let body = self.instructions(field, &mut scope)?;
+		self.wasm.functions.push(Function { signature, locals, body });
+		Ok(())
+	}
+
+	fn table(&mut self, field: &mut Cursor) -> Parse {
+		let index = self.wasm.table_imports.len() + self.wasm.tables.len();
+		let id = field.id();
+		self.exports(field, "table", index)?;
+		if let Some((module, name)) = self.inline_import(field)? {
+			return self.import("table", id, field, module, name);
+		}
+		if field.keyword("funcref") {
+			let Some(mut elements) = field.list("elem") else {
+				return error(field.at(), "Expected elem");
+			};
+			let mut functions = vec![];
+			while elements.peek().is_some() {
+				functions.push(self.index(&mut elements, "func")?);
+			}
+			let size = functions.len() as u32;
+			let mode = ElementMode::Active(index, vec![Instr::i32_const(0)]);
+			self.wasm.elements.push(Element { mode, functions });
+			let limits = Limits { min: size, max: Some(size) };
+			self.wasm.tables.push(TableType { typ: DataType::FuncRef, limits });
+			return Ok(());
+		}
+		self.wasm.tables.push(table_type(field)?);
+		Ok(())
+	}
+
+	fn memory(&mut self, field: &mut Cursor) -> Parse {
+		let index = self.wasm.memory_imports.len() + self.wasm.memories.len();
+		let id = field.id();
+		self.exports(field, "memory", index)?;
+		if let Some((module, name)) = self.inline_import(field)? {
+			return self.import("memory", id, field, module, name);
+		}
+		if let Some(mut data) = field.list("data") {
+			let mut bytes = vec![];
+			while let Some(text) = data.text()? {
+				bytes.extend(text);
+			}
+			data.done()?;
+			let pages = bytes.len().div_ceil(PAGE_SIZE) as u32;
+			self.wasm.memories.push(Limits { min: pages, max: Some(pages) });
+			let mode = DataMode::Active(index, vec![Instr::i32_const(0)]);
+			self.wasm.data.push(Data { mode, bytes });
+			return Ok(());
+		}
+		self.wasm.memories.push(limits(field)?);
+		Ok(())
+	}
+
+	fn global(&mut self, field: &mut Cursor) -> Parse {
+		let index = self.wasm.global_imports.len() + self.wasm.globals.len();
+		let id = field.id();
+		self.exports(field, "global", index)?;
+		if let Some((module, name)) = self.inline_import(field)? {
+			return self.import("global", id, field, module, name);
+		}
+		let typ = global_type(field)?;
+		let init = self.instructions(field, &mut Scope::default())?;
+		self.wasm.globals.push(Global { typ, init });
+		Ok(())
+	}
+
+	fn element(&mut self, field: &mut Cursor) -> Parse {
+		field.id();
+		let mut abbreviated = false;
+		let mode = if field.keyword("declare") {
+			ElementMode::Declarative
+		} else if let Some(mut table) = field.list("table") {
+			let index = self.index(&mut table, "table")?;
+			table.done()?;
+			ElementMode::Active(index, self.offset(field)?)
+		} else if field.peek().is_some_and(|node| matches!(node.kind, Kind::List(..))) {
+			abbreviated = true;
+			ElementMode::Active(0, self.offset(field)?)
+		} else {
+			ElementMode::Passive
+		};
+		if !field.keyword("func") && !abbreviated {
+			return error(field.at(), "Expected func");
+		}
+		let mut functions = vec![];
+		while field.peek().is_some() {
+			functions.push(self.index(field, "func")?);
+		}
+		self.wasm.elements.push(Element { mode, functions });
+		Ok(())
+	}
+
+	fn data(&mut self, field: &mut Cursor) -> Parse {
+		field.id();
+		let mode = if let Some(mut memory) = field.list("memory") {
+			let index = self.index(&mut memory, "memory")?;
+			memory.done()?;
+			DataMode::Active(index, self.offset(field)?)
+		} else if field.peek().is_some_and(|node| matches!(node.kind, Kind::List(..))) {
+			DataMode::Active(0, self.offset(field)?)
+		} else {
+			DataMode::Passive
+		};
+		let mut bytes = vec![];
+		while let Some(text) = field.text()? {
+			bytes.extend(text);
+		}
+		self.wasm.data.push(Data { mode, bytes });
+		Ok(())
+	}
+
+	fn offset(&mut self, field: &mut Cursor) -> Parse<Vec<Instr>> {
+		if let Some(mut offset) = field.list("offset") {
+			let expr = self.instructions(&mut offset, &mut Scope::default())?;
+			offset.done()?;
+			return Ok(expr);
+		}
+		let mut body = vec![];
+		self.folded(field.next()?, &mut Scope::default(), &mut body)?;
+		Ok(body)
+	}
+
+	fn type_use<'a>(
+		&self,
+		field: &mut Cursor<'a>,
+	) -> Parse<(Option<usize>, FunctionType, Ids<'a>)> {
+		let Some(mut reference) = field.list("type") else {
+			let (typ, ids) = signature(field)?;
+			return Ok((None, typ, ids));
+		};
+		let index = self.index(&mut reference, "type")?;
+		reference.done()?;
+		let typ = self.wasm.types[index].clone();
+		let at = field.at();
+		let (signature, mut ids) = signature(field)?;
+		if signature.params.is_empty() && signature.resuls.is_empty() {
+			ids.resize(typ.params.len(), None);
+		} else if signature != typ {
+			return error(at, format!("Type use does not match type {}", index));
+		}
+		Ok((Some(index), typ, ids))
+	}
+
+	fn block_type(&mut self, field: &mut Cursor) -> Parse<BlockType> {
+		let (index, signature, ids) = self.type_use(field)?;
+		if let Some(Some((_, at))) = ids.iter().find(|id| id.is_some()) {
+			return error(*at, "Unexpected identifier in block parameters");
+		}
+		Ok(match (index, &signature.params[..], &signature.resuls[..]) {
+			(Some(index), ..) => BlockType::Type(index),
+			(None, [], []) => BlockType::Empty,
+			(None, [], [typ]) => BlockType::Value(typ.clone()),
+			_ => BlockType::Type(self.wasm.type_index(signature)),
+		})
+	}
+
+	fn index(&self, field: &mut Cursor, kind: &str) -> Parse<usize> {
+		let (word, at) = field.atom(&format!("{} index", kind))?;
+		let ids = match kind {
+			"type" => &self.types,
+			"func" => &self.functions,
+			"table" => &self.tables,
+			"memory" => &self.memories,
+			"global" => &self.globals,
+			"elem" => &self.elements,
+			_ => &self.data,
+		};
+		let index = match word.strip_prefix('$') {
+			Some(_) => ids.get(word).copied(),
+			None => {
+				integer(word).filter(|(negative, _)| !negative).map(|(_, value)| value as usize)
+			}
+		};
+		match index.filter(|&index| kind != "type" || index < self.wasm.types.len()) {
+			Some(index) => Ok(index),
+			None => error(at, format!("Unknown {}: {}", kind, word)),
+		}
+	}
+
+	fn instructions(&mut self, field: &mut Cursor, scope: &mut Scope) -> Parse<Vec<Instr>> {
+		let mut body = vec![];
+		while let Some(node) = field.peek() {
+			match &node.kind {
+				Kind::List(..) => {
+					field.next()?;
+					self.folded(node, scope, &mut body)?;
+				}
+				Kind::Atom(word) if word == "end" || word == "else" => break,
+				Kind::Atom(_) => self.flat(field, scope, &mut body)?,
+				Kind::Text(_) => return error(node.at, "Unexpected string"),
+			}
+		}
+		Ok(body)
+	}
+
+	fn flat(&mut self, field: &mut Cursor, scope: &mut Scope, body: &mut Vec<Instr>) -> Parse {
+		let (word, at) = field.atom("instruction")?;
+		let instr = match word {
+			"block" | "loop" | "if" => {
+				let label = field.id();
+				let typ = self.block_type(field)?;
+				scope.labels.push(label.map(|(id, _)| id.to_string()));
+				let then = self.instructions(field, scope)?;
+				let mut otherwise = vec![];
+				if word == "if" && field.keyword("else") {
+					end_label(field, label)?;
+					otherwise = self.instructions(field, scope)?;
+				}
+				if !field.keyword("end") {
+					return error(field.at(), format!("Expected end of {}", word));
+				}
+				end_label(field, label)?;
+				scope.labels.pop();
+				match word {
+					"block" => Instr::block(typ, then),
+					"loop" => Instr::looped(typ, then),
+					_ => Instr::if_else(typ, then, otherwise),
+				}
+			}
+			word => self.plain(word, at, field, scope)?,
+		};
+		body.push(instr);
+		Ok(())
+	}
+
+	fn folded(&mut self, node: &Node, scope: &mut Scope, body: &mut Vec<Instr>) -> Parse {
+		let Some((word, at)) = node.head() else {
+			return error(node.at, "Expected instruction");
+		};
+		let mut field = node.list(word).unwrap();
+		let instr = match word {
+			"block" | "loop" => {
+				let label = field.id();
+				let typ = self.block_type(&mut field)?;
+				scope.labels.push(label.map(|(id, _)| id.to_string()));
+				let block = self.instructions(&mut field, scope)?;
+				scope.labels.pop();
+				match word {
+					"block" => Instr::block(typ, block),
+					_ => Instr::looped(typ, block),
+				}
+			}
+			"if" => {
+				let label = field.id();
+				let typ = self.block_type(&mut field)?;
+				while let Some(node) = field.peek().filter(|node| node.list("then").is_none()) {
+					field.next()?;
+					self.folded(node, scope, body)?;
+				}
+				scope.labels.push(label.map(|(id, _)| id.to_string()));
+				let Some(mut then) = field.list("then") else {
+					return error(field.at(), "Expected then");
+				};
+				let then = self.instructions(&mut then, scope)?;
+				let mut otherwise = vec![];
+				if let Some(mut block) = field.list("else") {
+					otherwise = self.instructions(&mut block, scope)?;
+					block.done()?;
+				}
+				scope.labels.pop();
+				Instr::if_else(typ, then, otherwise)
+			}
+			word => {
+				let instr = self.plain(word, at, &mut field, scope)?;
+				while let Some(node) = field.peek() {
+					if !matches!(node.kind, Kind::List(..)) {
+						return error(node.at, format!("Unexpected {}", node.describe()));
+					}
+					field.next()?;
+					self.folded(node, scope, body)?;
+				}
+				instr
+			}
+		};
+		field.done()?;
+		body.push(instr);
+		Ok(())
+	}
+
+	fn plain(
+		&mut self,
+		word: &str,
+		at: Position,
+		field: &mut Cursor,
+		scope: &mut Scope,
+	) -> Parse<Instr> {
+		use Instr::*;
+		let mut instr = match Instr::from_name(word) {
+			Some(block(..) | looped(..) | if_else(..) | then_end | end) => {
+				return error(at, format!("Unexpected {}", word))
+			}
+			Some(instr) => instr,
+			None => return error(at, format!("Unknown instruction: {}", word)),
+		};
+		match &mut instr {
+			br(label) | br_if(label) => *label = self.label(field, scope)?,
+			br_table(labels) => {
+				labels.push(self.label(field, scope)?);
+				while field.is_index() {
+					labels.push(self.label(field, scope)?);
+				}
+			}
+			call(function) => *function = self.index(field, "func")?,
+			call_indirect(typ, table) => {
+				if field.is_index() {
+					*table = self.index(field, "table")?;
+				}
+				let (index, signature, ids) = self.type_use(field)?;
+				if let Some(Some((_, at))) = ids.iter().find(|id| id.is_some()) {
+					return error(*at, "Unexpected identifier in call_indirect parameters");
+				}
+				*typ = index.unwrap_or_else(|| self.wasm.type_index(signature));
+			}
+			local_get(index) | local_set(index) | local_tee(index) => {
+				let (word, at) = field.atom("local index")?;
+				let local = match word.strip_prefix('$') {
+					Some(_) => scope.locals.get(word).copied(),
+					None => {
+						integer(word).filter(|(negative, _)| !negative).map(|(_, v)| v as usize)
+					}
+				};
+				let Some(local) = local.filter(|&local| local <= u32::MAX as usize) else {
+					return error(at, format!("Unknown local: {}", word));
+				};
+				*index = local as u32;
+			}
+			global_get(index) | global_set(index) => *index = self.index(field, "global")? as u32,
+			memory_init(data, memory) => {
+				if field.indices() > 1 {
+					*memory = self.index(field, "memory")?;
+				}
+				*data = self.index(field, "data")?;
+			}
+			table_init(element, table) => {
+				if field.indices() > 1 {
+					*table = self.index(field, "table")?;
+				}
+				*element = self.index(field, "elem")?;
+			}
+			data_drop(data) => *data = self.index(field, "data")?,
+			elem_drop(element) => *element = self.index(field, "elem")?,
+			memory_copy(to, from) if field.is_index() => {
+				*to = self.index(field, "memory")?;
+				*from = self.index(field, "memory")?;
+			}
+			table_copy(to, from) if field.is_index() => {
+				*to = self.index(field, "table")?;
+				*from = self.index(field, "table")?;
+			}
+			memory_fill(memory) if field.is_index() => *memory = self.index(field, "memory")?,
+			table_get(table) | table_set(table) | table_grow(table) | table_size(table)
+			| table_fill(table)
+				if field.is_index() =>
+			{
+				*table = self.index(field, "table")?
+			}
+			select => {
+				let mut types = vec![];
+				while let Some(mut result) = field.list("result") {
+					while result.peek().is_some() {
+						types.push(value_type(&mut result)?);
+					}
+				}
+				if !types.is_empty() {
+					instr = select_t(types);
+				}
+			}
+			ref_null(typ) => {
+				let (word, at) = field.atom("heap type")?;
+				*typ = match word {
+					"func" => DataType::FuncRef,
+					"extern" => DataType::ExternRef,
+					_ => return error(at, format!("Unknown heap type: {}", word)),
+				};
+			}
+			ref_func(function) => *function = self.index(field, "func")?,
+			i32_const(value) => *value = sized_integer(field, "i32", 32)? as i32,
+			i64_const(value) => *value = sized_integer(field, "i64", 64)? as i64,
+			f32_const(value) => {
+				let (word, at) = field.atom("f32")?;
+				let Some(bits) = float(word, 23, 8) else {
+					return error(at, format!("Invalid f32: {}", word));
+				};
+				*value = f32::from_bits(bits as u32);
+			}
+			f64_const(value) => {
+				let (word, at) = field.atom("f64")?;
+				let Some(bits) = float(word, 52, 11) else {
+					return error(at, format!("Invalid f64: {}", word));
+				};
+				*value = f64::from_bits(bits);
+			}
+			v128_const(value) => {
+				let (shape, at) = field.atom("vector shape")?;
+				let (count, bits) = match shape {
+					"i8x16" => (16, 8),
+					"i16x8" => (8, 16),
+					"i32x4" | "f32x4" => (4, 32),
+					"i64x2" | "f64x2" => (2, 64),
+					_ => return error(at, format!("Unknown vector shape: {}", shape)),
+				};
+				*value = 0;
+				for i in 0..count {
+					let lane = match shape {
+						"f32x4" | "f64x2" => {
+							let (word, at) = field.atom(shape)?;
+							let bits = match bits {
+								32 => float(word, 23, 8),
+								_ => float(word, 52, 11),
+							};
+							let Some(bits) = bits else {
+								return error(at, format!("Invalid {}: {}", shape, word));
+							};
+							bits
+						}
+						_ => sized_integer(field, shape, bits)?,
+					};
+					*value |= (lane as u128) << (i * bits);
+				}
+			}
+			i8x16_shuffle(lanes) => {
+				for lane in lanes.iter_mut() {
+					*lane = sized_integer(field, "lane index", 8)? as u8;
+				}
+			}
+			instr => {
+				if let Some((_, _, natural)) = memarg(instr) {
+					let (align, offset) = instr.memarg_mut().unwrap();
+					*align = natural;
+					if let Some(word) = field.peek_atom().filter(|word| word.starts_with("offset="))
+					{
+						let at = field.next()?.at;
+						*offset = match integer(&word[7..]) {
+							Some((false, value)) if value <= u32::MAX as u64 => value as u32,
+							_ => return error(at, format!("Invalid offset: {}", &word[7..])),
+						};
+					}
+					if let Some(word) = field.peek_atom().filter(|word| word.starts_with("align="))
+					{
+						let at = field.next()?.at;
+						*align = match integer(&word[6..]) {
+							Some((false, value)) if value.is_power_of_two() => {
+								value.trailing_zeros()
+							}
+							_ => return error(at, format!("Invalid alignment: {}", &word[6..])),
+						};
+					}
+				}
+				if let Some(lane) = instr.lane_mut() {
+					let (word, at) = field.atom("lane index")?;
+					*lane = match integer(word) {
+						Some((false, value)) if value <= u8::MAX as u64 => value as u8,
+						_ => return error(at, format!("Invalid lane index: {}", word)),
+					};
+				}
+			}
+		}
+		Ok(instr)
+	}
+
+	fn label(&self, field: &mut Cursor, scope: &Scope) -> Parse<usize> {
+		let (word, at) = field.atom("label")?;
+		let depth = match word.strip_prefix('$') {
+			Some(_) => scope.labels.iter().rev().position(|label| label.as_deref() == Some(word)),
+			None => {
+				integer(word).filter(|(negative, _)| !negative).map(|(_, value)| value as usize)
+			}
+		};
+		depth.map_or_else(|| error(at, format!("Unknown label: {}", word)), Ok)
+	}
+}
+
+impl Node {
+	fn head(&self) -> Option<(&str, Position)> {
+		match &self.kind {
+			Kind::List(nodes, _) => match &nodes.first()?.kind {
+				Kind::Atom(word) => Some((word, nodes[0].at)),
+				_ => None,
+			},
+			_ => None,
+		}
+	}
+}
+
+fn insert(ids: &mut HashMap<String, usize>, id: &str, index: usize, at: Position) -> Parse {
+	match ids.insert(id.to_string(), index) {
+		Some(_) => error(at, format!("Duplicate identifier: {}", id)),
+		None => Ok(()),
+	}
+}
+
+fn end_label(field: &mut Cursor, label: Option<(&str, Position)>) -> Parse {
+	match field.id() {
+		Some((id, at)) if label.map(|(label, _)| label) != Some(id) => {
+			error(at, format!("Mismatching label: {}", id))
+		}
+		_ => Ok(()),
+	}
+}
+
+fn value_type(field: &mut Cursor) -> Parse<DataType> {
+	let (word, at) = field.atom("value type")?;
+	match word {
+		"i32" => Ok(DataType::I32),
+		"i64" => Ok(DataType::I64),
+		"f32" => Ok(DataType::F32),
+		"f64" => Ok(DataType::F64),
+		"v128" => Ok(DataType::V128),
+		"funcref" => Ok(DataType::FuncRef),
+		"externref" => Ok(DataType::ExternRef),
+		_ => error(at, format!("Unknown value type: {}", word)),
+	}
+}
+
+fn limits(field: &mut Cursor) -> Parse<Limits> {
+	let min = field.u32()?;
+	let max = match field
+		.peek_atom()
+		.is_some_and(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+	{
+		true => Some(field.u32()?),
+		false => None,
+	};
+	Ok(Limits { min, max })
+}
+
+fn table_type(field: &mut Cursor) -> Parse<TableType> {
+	let limits = limits(field)?;
+	let at = field.at();
+	match value_type(field)? {
+		typ if typ.is_reference() => Ok(TableType { typ, limits }),
+		typ => error(at, format!("Invalid table element type: {}", typ)),
+	}
+}
+
+fn global_type(field: &mut Cursor) -> Parse<GlobalType> {
+	if let Some(mut mutable) = field.list("mut") {
+		let typ = value_type(&mut mutable)?;
+		mutable.done()?;
+		return Ok(GlobalType { typ, mutable: true });
+	}
+	Ok(GlobalType { typ: value_type(field)?, mutable: false })
+}
+
+fn sized_integer(field: &mut Cursor, what: &str, bits: u32) -> Parse<u64> {
+	let (word, at) = field.atom(what)?;
+	let mask = u64::MAX >> (64 - bits);
+	match integer(word) {
+		Some((true, value)) if value <= 1 << (bits - 1) => Ok(value.wrapping_neg() & mask),
+		Some((false, value)) if value <= mask => Ok(value),
+		_ => error(at, format!("Invalid {}: {}", what, word)),
+	}
+}
+
+fn integer(word: &str) -> Option<(bool, u64)> {
+	let (negative, digits) = match word.as_bytes().first()? {
+		b'-' => (true, &word[1..]),
+		b'+' => (false, &word[1..]),
+		_ => (false, word),
+	};
+	let (digits, radix) = match digits.strip_prefix("0x") {
+		Some(digits) => (digits, 16),
+		None => (digits, 10),
+	};
+	if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+		return None;
+	}
+	let value = u64::from_str_radix(&digits.replace('_', ""), radix).ok()?;
+	Some((negative, value))
+}
+
+fn float(word: &str, mantissa: u32, exponent: u32) -> Option<u64> {
+	let (negative, text) = match word.as_bytes().first()? {
+		b'-' => (true, &word[1..]),
+		b'+' => (false, &word[1..]),
+		_ => (false, word),
+	};
+	let sign = (negative as u64) << (mantissa + exponent);
+	let infinity = ((1 << exponent) - 1) << mantissa;
+	if text == "inf" {
+		return Some(sign | infinity);
+	}
+	if text == "nan" {
+		return Some(sign | infinity | 1 << (mantissa - 1));
+	}
+	if let Some(payload) = text.strip_prefix("nan:0x") {
+		let payload = u64::from_str_radix(&payload.replace('_', ""), 16).ok()?;
+		if payload == 0 || payload >= 1 << mantissa {
+			return None;
+		}
+		return Some(sign | infinity | payload);
+	}
+	if !text.starts_with(|c: char| c.is_ascii_digit()) || text.contains("__") {
+		return None;
+	}
+	let text = text.replace('_', "");
+	let value = match text.strip_prefix("0x") {
+		Some(hex) => hex_float(hex)?,
+		None if text.chars().all(|c| c.is_ascii_digit() || ".eE+-".contains(c)) => {
+			text.parse::<f64>().ok()?
+		}
+		None => return None,
+	};
+	let bits = match mantissa {
+		23 => match text.strip_prefix("0x") {
+			Some(_) => (value as f32).to_bits() as u64,
+			None => text.parse::<f32>().ok()?.to_bits() as u64,
+		},
+		_ => value.to_bits(),
+	};
+	Some(sign | bits)
+}
+
+fn hex_float(text: &str) -> Option<f64> {
+	let (significand, exponent) = match text.split_once(['p', 'P']) {
+		Some((significand, exponent)) => (significand, exponent.parse::<i32>().ok()?),
+		None => (text, 0),
+	};
+	let (whole, fraction) = significand.split_once('.').unwrap_or((significand, ""));
+	if whole.is_empty() {
+		return None;
+	}
+	let mut value = 0.0f64;
+	let mut scale = exponent;
+	for digit in whole.chars().chain(fraction.chars()) {
+		value = value * 16.0 + digit.to_digit(16)? as f64;
+	}
+	scale -= 4 * fraction.len() as i32;
+	while scale > 0 {
+		value *= 2.0;
+		scale -= 1;
+	}
+	while scale < 0 {
+		value /= 2.0;
+		scale += 1;
+	}
+	Some(value)
+}
+
+fn signature<'a>(field: &mut Cursor<'a>) -> Parse<(FunctionType, Ids<'a>)> {
+	let mut typ = FunctionType { params: vec![], resuls: vec![] };
+	let mut ids = vec![];
+	while let Some(mut params) = field.list("param") {
+		if let Some(id) = params.id() {
+			ids.push(Some(id));
+			typ.params.push(value_type(&mut params)?);
+			params.done()?;
+			continue;
+		}
+		while params.peek().is_some() {
+			ids.push(None);
+			typ.params.push(value_type(&mut params)?);
+		}
+	}
+	while let Some(mut results) = field.list("result") {
+		while results.peek().is_some() {
+			typ.resuls.push(value_type(&mut results)?);
+		}
+	}
+	Ok((typ, ids))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::interpreter::{Instance, Value};
+	use crate::wasm::printer::Style;
+
+	const FACTORIAL: &str = r#"
+		(module $math
+			(type $binary (func (param i32 i32) (result i32)))
+			(import "env" "log" (func $log (param i32)))
+			(memory (export "memory") 1)
+			(global $calls (mut i32) (i32.const 0))
+			(table 2 funcref)
+			(elem (i32.const 0) $fac $mul)
+			(data (i32.const 16) "\01\02" "ab\u{2603}")
+			;; recursive factorial, mixing flat and folded forms
+			(func $fac (export "fac") (param $n i32) (result i32)
+				(local $tmp i64)
+				(global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+				(if (result i32) (i32.lt_s (local.get $n) (i32.const 2))
+					(then i32.const 1)
+					(else
+						local.get $n
+						(call $fac (i32.sub (local.get $n) (i32.const 1)))
+						(call_indirect (type $binary) (i32.const 1)))))
+			(func $mul (type $binary) (i32.mul (local.get 0) (local.get 1)))
+			(func (export "sum") (param i32) (result i32) (local i32)
+				block $done
+					loop $next
+						local.get 0
+						i32.eqz
+						br_if $done
+						(local.set 1 (i32.add (local.get 1) (local.get 0)))
+						(local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+						br $next
+					end $next
+				end
+				local.get 1
+				(; (nested (; block ;) comment) ;)
+				(i64.load offset=8 align=4 (i32.const 0))
+				drop)
+			(export "calls" (global $calls)))
+	"#;
+
+	#[test]
+	fn parses_module() {
+		let wasm = Wasm::from_wat(FACTORIAL).unwrap();
+		assert_eq!(wasm.names.module.as_deref(), Some("math"));
+		assert_eq!(wasm.names.functions.get(&1).map(String::as_str), Some("fac"));
+		assert_eq!(wasm.names.locals[&1].get(&1).map(String::as_str), Some("tmp"));
+		assert_eq!(wasm.exports["fac"], 1);
+		assert_eq!(wasm.exports["sum"], 3);
+		assert_eq!(wasm.memory_exports["memory"], 0);
+		assert_eq!(wasm.global_exports["calls"], 0);
+		assert_eq!(wasm.elements[0].functions, vec![1, 2]);
+		assert_eq!(wasm.data[0].bytes, b"\x01\x02ab\xE2\x98\x83");
+		assert_eq!(wasm.functions[2].body[3], Instr::i64_load(2, 8));
+		assert_eq!(wasm.functions[2].body[2], Instr::i32_const(0));
+		wasm.validate().unwrap();
+	}
+
+	#[test]
+	fn runs_parsed_module() {
+		let text = FACTORIAL.replace(r#"(import "env" "log" (func $log (param i32)))"#, "");
+		let wasm = Wasm::from_wat(&text).unwrap();
+		let mut instance = Instance::new(&wasm).unwrap();
+		assert_eq!(instance.invoke("fac", &[Value::I32(5)]), Ok(vec![Value::I32(120)]));
+		assert_eq!(instance.invoke("sum", &[Value::I32(4)]), Ok(vec![Value::I32(10)]));
+		assert_eq!(instance.global("calls"), Some(Value::I32(5)));
+	}
+
+	#[test]
+	fn round_trips_through_printer() {
+		let wasm = Wasm::from_wat(FACTORIAL).unwrap();
+		for style in [Style::Flat, Style::Folded] {
+			let text = wasm.to_wat(style);
+			assert!(Wasm::from_wat(&text).unwrap() == wasm, "{}", text);
+		}
+	}
+
+	#[test]
+	fn parses_constants() {
+		let wasm = Wasm::from_wat(
+			"(func (result f64) i32.const -0x8000_0000 drop i64.const 18446744073709551615 drop
+				f32.const -nan:0x200000 drop f32.const 0x1.8p1 drop f64.const 1e-7 drop
+				f64.const -inf)",
+		)
+		.unwrap();
+		let body = &wasm.functions[0].body;
+		assert_eq!(body[0], Instr::i32_const(i32::MIN));
+		assert_eq!(body[2], Instr::i64_const(-1));
+		assert!(matches!(body[4], Instr::f32_const(x) if x.to_bits() == 0xFFA00000));
+		assert_eq!(body[6], Instr::f32_const(3.0));
+		assert_eq!(body[8], Instr::f64_const(1e-7));
+		assert_eq!(body[10], Instr::f64_const(f64::NEG_INFINITY));
+	}
+
+	#[test]
+	fn parses_bulk_instructions() {
+		let wasm = Wasm::from_wat(
+			r#"(module
+				(table $t 2 funcref) (table $u 2 funcref) (memory 1) (elem $e func 0) (data $d "ab")
+				(func
+					(memory.init $d (i32.const 0) (i32.const 0) (i32.const 2)) (data.drop $d)
+					(memory.copy (i32.const 1) (i32.const 0) (i32.const 1))
+					(memory.fill (i32.const 0) (i32.const 0) (i32.const 1))
+					(table.init $u $e (i32.const 0) (i32.const 0) (i32.const 1)) (elem.drop 0)
+					(table.copy $t $u (i32.const 0) (i32.const 1) (i32.const 1))
+					(drop (table.size $u))))"#,
+		)
+		.unwrap();
+		use Instr::*;
+		let body: Vec<_> =
+			wasm.functions[0].body.iter().filter(|i| !matches!(i, i32_const(_))).cloned().collect();
+		assert_eq!(
+			body,
+			vec![
+				memory_init(0, 0),
+				data_drop(0),
+				memory_copy(0, 0),
+				memory_fill(0),
+				table_init(0, 1),
+				elem_drop(0),
+				table_copy(0, 1),
+				table_size(1),
+				drop
+			]
+		);
+		wasm.validate().unwrap();
+		for style in [Style::Flat, Style::Folded] {
+			let text = wasm.to_wat(style);
+			assert!(Wasm::from_wat(&text).unwrap() == wasm, "{}", text);
+		}
+	}
+
+	#[test]
+	fn parses_vectors() {
+		let wasm = Wasm::from_wat(
+			"(memory 1)
+			(func (param v128) (result v128)
+				(v128.const i8x16 -1 0 1 2 3 4 5 6 7 8 9 10 11 12 13 255)
+				(v128.const f32x4 1.0 -0 inf 0x1p-1)
+				i8x16.shuffle 0 1 2 3 4 5 6 7 16 17 18 19 20 21 22 31
+				(v128.load16_lane offset=8 7 (i32.const 0) (local.get 0))
+				i64x2.add)",
+		)
+		.unwrap();
+		use Instr::*;
+		let body = &wasm.functions[0].body;
+		assert_eq!(body[0], v128_const(0xFF0D0C0B0A09080706050403020100FF));
+		assert_eq!(body[1], v128_const(0x3F000000_7F800000_80000000_3F800000));
+		assert_eq!(
+			body[2],
+			i8x16_shuffle([0, 1, 2, 3, 4, 5, 6, 7, 16, 17, 18, 19, 20, 21, 22, 31])
+		);
+		assert_eq!(body[5], v128_load16_lane(1, 8, 7));
+		wasm.validate().unwrap();
+		for style in [Style::Flat, Style::Folded] {
+			let text = wasm.to_wat(style);
+			assert!(Wasm::from_wat(&text).unwrap() == wasm, "{}", text);
+		}
+		let error = |text| Wasm::from_wat(text).err().unwrap().to_string();
+		assert_eq!(error("(func v128.const i8x16 256)"), "1:24: Invalid i8x16: 256");
+		assert_eq!(error("(func v128.const i8x8 0)"), "1:18: Unknown vector shape: i8x8");
+	}
+
+	#[test]
+	fn parses_references() {
+		let wasm = Wasm::from_wat(
+			"(table $refs 1 externref)
+			(elem declare func $f)
+			(func $f (param externref) (result i32)
+				(table.set $refs (i32.const 0) (local.get 0))
+				(select (result funcref) (ref.func $f) (ref.null func) (i32.const 1))
+				ref.is_null)",
+		)
+		.unwrap();
+		use Instr::*;
+		assert!(
+			wasm.tables[0]
+				== TableType { typ: DataType::ExternRef, limits: Limits { min: 1, max: None } }
+		);
+		let body = &wasm.functions[0].body;
+		assert_eq!(body[2], table_set(0));
+		assert_eq!(body[6], select_t(vec![DataType::FuncRef]));
+		wasm.validate().unwrap();
+		for style in [Style::Flat, Style::Folded] {
+			let text = wasm.to_wat(style);
+			assert!(Wasm::from_wat(&text).unwrap() == wasm, "{}", text);
+		}
+	}
+
+	#[test]
+	fn parses_block_types() {
+		let wasm = Wasm::from_wat(
+			"(type $pair (func (param i32) (result i32 i32)))
+			(func (param i32 i32) (result i32 i32)
+				local.get 0
+				block (type $pair) (local.get 1) end
+				local.get 1
+				if (param i32 i32) (result i32 i32) else drop drop (local.get 1) (local.get 0) end
+				(drop (block (result i32) (i32.const 1)))
+				(loop (nop)))",
+		)
+		.unwrap();
+		use Instr::*;
+		let body = &wasm.functions[0].body;
+		assert_eq!(body[1], block(BlockType::Type(0), vec![local_get(1)]));
+		assert!(matches!(body[3], if_else(BlockType::Type(1), _, _)));
+		assert_eq!(body[4], block(BlockType::Value(DataType::I32), vec![i32_const(1)]));
+		assert_eq!(body[6], looped(BlockType::Empty, vec![nop]));
+		assert!(wasm.types[1] == wasm.functions[0].signature);
+		wasm.validate().unwrap();
+		for style in [Style::Flat, Style::Folded] {
+			let text = wasm.to_wat(style);
+			assert!(Wasm::from_wat(&text).unwrap() == wasm, "{}", text);
+		}
+		let error = Wasm::from_wat("(func block (param $x i32) end)").err().unwrap();
+		assert_eq!(error.to_string(), "1:20: Unexpected identifier in block parameters");
+	}
+
+	#[test]
+	fn reports_positions() {
+		let error = |text| Wasm::from_wat(text).err().unwrap().to_string();
+		assert_eq!(
+			error("(module\n  (func i32.const 1 i32.frob))"),
+			"2:21: Unknown instruction: i32.frob"
+		);
+		assert_eq!(error("(func (local.get $x))"), "1:18: Unknown local: $x");
+		assert_eq!(error("(func block br $outer end)"), "1:16: Unknown label: $outer");
+		assert_eq!(error("(func i32.const 4294967296)"), "1:17: Invalid i32: 4294967296");
+		assert_eq!(error("(func (block nop)\n end)"), "2:2: Unexpected end");
+		assert_eq!(error("(func $f) (func $f)"), "1:17: Duplicate identifier: $f");
+		assert_eq!(
+			error("(func) (import \"a\" \"b\" (func))"),
+			"1:8: Import after func definition"
+		);
+		assert_eq!(error("(module (func)"), "1:1: Unclosed (");
+		assert_eq!(error("(data \"\\q\")"), "1:8: Invalid escape sequence");
+		assert_eq!(error("(func (param $a i32) (param $a i32))"), "1:29: Duplicate identifier: $a");
+	}
+}
@@ -0,0 +1,572 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use super::instruction::Instr;
+use super::validator::{lane, memarg};
+use super::{
+	BlockType, DataMode, DataType, ElementMode, FunctionType, GlobalType, Limits, TableType, Wasm,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Style {
+	Flat,
+	Folded,
+}
+
+impl Wasm {
+	pub fn to_wat(&self, style: Style) -> String {
+		Printer::new(self, style).module().join("\n") + "\n"
+	}
+}
+
+impl fmt::Display for Wasm {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.to_wat(Style::Flat))
+	}
+}
+
+impl fmt::Display for Instr {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let wasm = Wasm::default();
+		let lines = Printer::new(&wasm, Style::Flat).body(std::slice::from_ref(self));
+		f.write_str(&lines.join("\n"))
+	}
+}
+
+struct Printer<'a> {
+	wasm: &'a Wasm,
+	style: Style,
+	functions: Vec<Option<String>>,
+	locals: Vec<Option<String>>,
+}
+
+struct Item {
+	lines: Vec<String>,
+	results: usize,
+}
+
+impl<'a> Printer<'a> {
+	fn new(wasm: &'a Wasm, style: Style) -> Printer<'a> {
+		let count = wasm.imports.len() + wasm.functions.len();
+		let functions = identifiers(&wasm.names.functions, count);
+		Printer { wasm, style, functions, locals: vec![] }
+	}
+
+	fn module(&mut self) -> Vec<String> {
+		let wasm = self.wasm;
+		let mut fields = vec![];
+		for (i, typ) in wasm.types.iter().enumerate() {
+			fields.push(vec![format!("(type (;{};) (func{}))", i, signature(typ, &[]))]);
+		}
+		for (i, (typ, module, name)) in wasm.imports.iter().enumerate() {
+			let desc =
+				format!("(func{} (;{};){})", self.id(&self.functions[i]), i, signature(typ, &[]));
+			fields.push(vec![import(module, name, desc)]);
+		}
+		for (i, (typ, module, name)) in wasm.table_imports.iter().enumerate() {
+			fields.push(vec![import(module, name, format!("(table (;{};) {})", i, table(typ)))]);
+		}
+		for (i, (limits, module, name)) in wasm.memory_imports.iter().enumerate() {
+			fields.push(vec![import(
+				module,
+				name,
+				format!("(memory (;{};) {})", i, limits_text(limits)),
+			)]);
+		}
+		for (i, (typ, module, name)) in wasm.global_imports.iter().enumerate() {
+			fields.push(vec![import(module, name, format!("(global (;{};) {})", i, global(typ)))]);
+		}
+		for i in 0..wasm.functions.len() {
+			fields.push(self.function(wasm.imports.len() + i));
+		}
+		for (i, typ) in wasm.tables.iter().enumerate() {
+			let index = wasm.table_imports.len() + i;
+			fields.push(vec![format!("(table (;{};) {})", index, table(typ))]);
+		}
+		for (i, limits) in wasm.memories.iter().enumerate() {
+			let index = wasm.memory_imports.len() + i;
+			fields.push(vec![format!("(memory (;{};) {})", index, limits_text(limits))]);
+		}
+		for (i, item) in wasm.globals.iter().enumerate() {
+			let index = wasm.global_imports.len() + i;
+			let init = self.expression(&item.init);
+			fields.push(vec![format!("(global (;{};) {} {})", index, global(&item.typ), init)]);
+		}
+		let mut exports = vec![];
+		exports.extend(wasm.exports.iter().map(|(name, &index)| (name, "func", index)));
+		exports.extend(wasm.table_exports.iter().map(|(name, &index)| (name, "table", index)));
+		exports.extend(wasm.memory_exports.iter().map(|(name, &index)| (name, "memory", index)));
+		exports.extend(wasm.global_exports.iter().map(|(name, &index)| (name, "global", index)));
+		exports.sort();
+		for (name, kind, index) in exports {
+			let index = match kind {
+				"func" => self.function_ref(index),
+				_ => index.to_string(),
+			};
+			fields.push(vec![format!("(export {} ({} {}))", string(name.as_bytes()), kind, index)]);
+		}
+		if let Some(start) = wasm.start_function {
+			fields.push(vec![format!("(start {})", self.function_ref(start))]);
+		}
+		for (i, element) in wasm.elements.iter().enumerate() {
+			let mode = match &element.mode {
+				ElementMode::Passive => String::new(),
+				ElementMode::Active(0, offset) => format!(" {}", self.expression(offset)),
+				ElementMode::Active(table, offset) => {
+					format!(" (table {}) {}", table, self.expression(offset))
+				}
+				ElementMode::Declarative => " declare".to_string(),
+			};
+			let functions = element.functions.iter().map(|&f| format!(" {}", self.function_ref(f)));
+			fields.push(vec![format!(
+				"(elem (;{};){} func{})",
+				i,
+				mode,
+				functions.collect::<String>()
+			)]);
+		}
+		for (i, data) in wasm.data.iter().enumerate() {
+			let mode = match &data.mode {
+				DataMode::Passive => String::new(),
+				DataMode::Active(0, offset) => format!(" {}", self.expression(offset)),
+				DataMode::Active(memory, offset) => {
+					format!(" (memory {}) {}", memory, self.expression(offset))
+				}
+			};
+			fields.push(vec![format!("(data (;{};){} {})", i, mode, string(&data.bytes))]);
+		}
+		let header = match &wasm.names.module {
+			Some(name) => format!("(module {}", identifier(name)),
+			None => "(module".to_string(),
+		};
+		enclose(header, fields.concat())
+	}
+
+	fn function(&mut self, index: usize) -> Vec<String> {
+		let wasm = self.wasm;
+		let function = &wasm.functions[index - wasm.imports.len()];
+		let count = function.signature.params.len() + function.locals.len();
+		self.locals = match wasm.names.locals.get(&index) {
+			Some(names) => identifiers(names, count),
+			None => vec![None; count],
+		};
+		let (params, locals) = self.locals.split_at(function.signature.params.len());
+		let id = self.id(&self.functions[index]);
+		let header = format!("(func{} (;{};){}", id, index, signature(&function.signature, params));
+		let mut lines = vec![];
+		if !function.locals.is_empty() {
+			lines.push(declarations("local", &function.locals, locals).join(" "));
+		}
+		lines.extend(self.body(&function.body));
+		enclose(header, lines)
+	}
+
+	fn body(&self, body: &[Instr]) -> Vec<String> {
+		match self.style {
+			Style::Flat => body.iter().flat_map(|instr| self.flat(instr)).collect(),
+			Style::Folded => self.folded(body),
+		}
+	}
+
+	fn flat(&self, instr: &Instr) -> Vec<String> {
+		use Instr::*;
+		let mut lines = vec![self.plain(instr)];
+		match instr {
+			block(_, body) | looped(_, body) => lines.extend(indent(self.body(body))),
+			if_else(_, then, otherwise) => {
+				lines.extend(indent(self.body(then)));
+				if !otherwise.is_empty() {
+					lines.push("else".to_string());
+					lines.extend(indent(self.body(otherwise)));
+				}
+			}
+			_ => return lines,
+		}
+		lines.push("end".to_string());
+		lines
+	}
+
+	fn folded(&self, body: &[Instr]) -> Vec<String> {
+		use Instr::*;
+		let mut items: Vec<Item> = vec![];
+		for instr in body {
+			let (params, results) = self.arity(instr);
+			let split = items.len().saturating_sub(params);
+			let foldable = params > 0 && items.len() >= params;
+			let children = match foldable && items[split..].iter().all(|item| item.results == 1) {
+				true => items.split_off(split).into_iter().map(|item| item.lines).collect(),
+				false => vec![],
+			};
+			let lines = match instr {
+				block(_, body) | looped(_, body) => {
+					enclose(format!("({}", self.plain(instr)), self.body(body))
+				}
+				if_else(_, then, otherwise) => {
+					let mut lines = children.concat();
+					lines.extend(enclose("(then".to_string(), self.body(then)));
+					if !otherwise.is_empty() {
+						lines.extend(enclose("(else".to_string(), self.body(otherwise)));
+					}
+					enclose(format!("({}", self.plain(instr)), lines)
+				}
+				_ if children.iter().all(|lines| lines.len() == 1) => {
+					let mut line = format!("({}", self.plain(instr));
+					for child in children {
+						line += " ";
+						line += &child[0];
+					}
+					vec![line + ")"]
+				}
+				_ => enclose(format!("({}", self.plain(instr)), children.concat()),
+			};
+			items.push(Item { lines, results });
+		}
+		items.into_iter().flat_map(|item| item.lines).collect()
+	}
+
+	fn arity(&self, instr: &Instr) -> (usize, usize) {
+		use Instr::*;
+		let typed = |typ: Option<&FunctionType>, extra| match typ {
+			Some(typ) => (typ.params.len() + extra, typ.resuls.len()),
+			None => (extra, 0),
+		};
+		match instr {
+			block(typ, _) | looped(typ, _) => (0, typed(self.wasm.block_type(typ).as_ref(), 0).1),
+			if_else(typ, _, _) => typed(self.wasm.block_type(typ).as_ref(), 1),
+			br_if(_) | br_table(_) => (1, 0),
+			call(index) => typed(self.wasm.function_type(*index), 0),
+			call_indirect(typ, _) => typed(self.wasm.types.get(*typ), 1),
+			_ => {
+				let (params, results) = instr.signature().split_once('>').unwrap_or_default();
+				(params.split_whitespace().count(), results.split_whitespace().count())
+			}
+		}
+	}
+
+	fn plain(&self, instr: &Instr) -> String {
+		use Instr::*;
+		let immediates = match instr {
+			block(typ, _) | looped(typ, _) | if_else(typ, _, _) => match typ {
+				BlockType::Empty => String::new(),
+				BlockType::Value(typ) => format!(" (result {})", typ),
+				BlockType::Type(index) => format!(" (type {})", index),
+			},
+			br(depth) | br_if(depth) => format!(" {}", depth),
+			br_table(labels) => labels.iter().map(|label| format!(" {}", label)).collect(),
+			call(index) => format!(" {}", self.function_ref(*index)),
+			call_indirect(typ, 0) => format!(" (type {})", typ),
+			call_indirect(typ, table) => format!(" {} (type {})", table, typ),
+			local_get(index) | local_set(index) | local_tee(index) => {
+				let index = *index as usize;
+				match self.locals.get(index) {
+					Some(Some(id)) => format!(" {}", id),
+					_ => format!(" {}", index),
+				}
+			}
+			global_get(index) | global_set(index) => format!(" {}", index),
+			memory_init(data, 0) | table_init(data, 0) => format!(" {}", data),
+			memory_init(data, index) | table_init(data, index) => format!(" {} {}", index, data),
+			data_drop(index) | elem_drop(index) => format!(" {}", index),
+			memory_copy(0, 0) | table_copy(0, 0) => String::new(),
+			memory_copy(to, from) | table_copy(to, from) => format!(" {} {}", to, from),
+			memory_fill(0) | table_get(0) | table_set(0) | table_grow(0) | table_size(0)
+			| table_fill(0) => String::new(),
+			memory_fill(index) | table_get(index) | table_set(index) | table_grow(index)
+			| table_size(index) | table_fill(index) => format!(" {}", index),
+			select_t(types) => types.iter().map(|typ| format!(" (result {})", typ)).collect(),
+			ref_null(DataType::ExternRef) => " extern".to_string(),
+			ref_null(_) => " func".to_string(),
+			ref_func(index) => format!(" {}", self.function_ref(*index)),
+			i32_const(value) => format!(" {}", value),
+			i64_const(value) => format!(" {}", value),
+			f32_const(value) => {
+				format!(" {}", float(format!("{:?}", value), value.to_bits() as u64, 23))
+			}
+			f64_const(value) => format!(" {}", float(format!("{:?}", value), value.to_bits(), 52)),
+			v128_const(value) => {
+				let bytes = value.to_le_bytes();
+				let lanes = bytes
+					.chunks(4)
+					.map(|lane| format!(" {:#010x}", u32::from_le_bytes(lane.try_into().unwrap())));
+				format!(" i32x4{}", lanes.collect::<String>())
+			}
+			i8x16_shuffle(lanes) => lanes.iter().map(|lane| format!(" {}", lane)).collect(),
+			instr => {
+				let mut text = String::new();
+				if let Some((align, offset, natural)) = memarg(instr) {
+					if offset != 0 {
+						text += &format!(" offset={}", offset);
+					}
+					if align != natural {
+						text += &format!(" align={}", 1u64 << align.min(63));
+					}
+				}
+				if let Some((lane, _)) = lane(instr) {
+					text += &format!(" {}", lane);
+				}
+				text
+			}
+		};
+		format!("{}{}", instr.name(), immediates)
+	}
+
+	fn expression(&self, expr: &[Instr]) -> String {
+		self.folded(expr).join(" ")
+	}
+
+	fn function_ref(&self, index: usize) -> String {
+		match self.functions.get(index) {
+			Some(Some(id)) => id.clone(),
+			_ => index.to_string(),
+		}
+	}
+
+	fn id(&self, id: &Option<String>) -> String {
+		id.as_ref().map_or(String::new(), |id| format!(" {}", id))
+	}
+}
+
+fn identifiers(names: &BTreeMap<usize, String>, count: usize) -> Vec<Option<String>> {
+	let mut ids = vec![None; count];
+	let mut used = HashSet::new();
+	for (&index, name) in names.range(..count) {
+		let mut id = identifier(name);
+		if !used.insert(id.clone()) {
+			id = format!("{}.{}", id, index);
+			used.insert(id.clone());
+		}
+		ids[index] = Some(id);
+	}
+	ids
+}
+
+fn identifier(name: &str) -> String {
+	let valid = |c: char| c.is_ascii_graphic() && !"\",;()[]{}".contains(c);
+	let id: String = name.chars().map(|c| if valid(c) { c } else { '_' }).collect();
+	format!("${}", if id.is_empty() { "_" } else { &id })
+}
+
+fn signature(typ: &FunctionType, ids: &[Option<String>]) -> String {
+	let mut text = String::new();
+	for declaration in declarations("param", &typ.params, ids) {
+		text += " ";
+		text += &declaration;
+	}
+	if !typ.resuls.is_empty() {
+		let types: Vec<_> = typ.resuls.iter().map(DataType::to_string).collect();
+		text += &format!(" (result {})", types.join(" "));
+	}
+	text
+}
+
+fn declarations(keyword: &str, types: &[DataType], ids: &[Option<String>]) -> Vec<String> {
+	let mut declarations = vec![];
+	let mut unnamed = vec![];
+	for (i, typ) in types.iter().enumerate() {
+		match ids.get(i) {
+			Some(Some(id)) => {
+				if !unnamed.is_empty() {
+					declarations.push(format!("({} {})", keyword, unnamed.join(" ")));
+					unnamed.clear();
+				}
+				declarations.push(format!("({} {} {})", keyword, id, typ));
+			}
+			_ => unnamed.push(typ.to_string()),
+		}
+	}
+	if !unnamed.is_empty() {
+		declarations.push(format!("({} {})", keyword, unnamed.join(" ")));
+	}
+	declarations
+}
+
+fn import(module: &str, name: &str, desc: String) -> String {
+	format!("(import {} {} {})", string(module.as_bytes()), string(name.as_bytes()), desc)
+}
+
+fn limits_text(limits: &Limits) -> String {
+	match limits.max {
+		Some(max) => format!("{} {}", limits.min, max),
+		None => limits.min.to_string(),
+	}
+}
+
+fn table(typ: &TableType) -> String {
+	format!("{} {}", limits_text(&typ.limits), typ.typ)
+}
+
+fn global(typ: &GlobalType) -> String {
+	match typ.mutable {
+		true => format!("(mut {})", typ.typ),
+		false => typ.typ.to_string(),
+	}
+}
+
+fn float(text: String, bits: u64, mantissa: u32) -> String {
+	if text != "NaN" {
+		return text;
+	}
+	let sign = if bits >> (mantissa + if mantissa == 23 { 8 } else { 11 }) != 0 { "-" } else { "" };
+	match bits & ((1 << mantissa) - 1) {
+		payload if payload == 1 << (mantissa - 1) => format!("{}nan", sign),
+		payload => format!("{}nan:{:#x}", sign, payload),
+	}
+}
+
+fn string(bytes: &[u8]) -> String {
+	let mut text = "\"".to_string();
+	for &byte in bytes {
+		match byte {
+			b'"' | b'\\' => text += &format!("\\{}", byte as char),
+			0x20..=0x7E => text.push(byte as char),
+			_ => text += &format!("\\{:02x}", byte),
+		}
+	}
+	text + "\""
+}
+
+fn indent(lines: Vec<String>) -> Vec<String> {
+	lines.into_iter().map(|line| format!("  {}", line)).collect()
+}
+
+fn enclose(header: String, lines: Vec<String>) -> Vec<String> {
+	if lines.is_empty() {
+		return vec![header + ")"];
+	}
+	let mut lines = indent(lines);
+	lines.insert(0, header);
+	lines.last_mut().unwrap().push(')');
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::{Data, Element, Function, Global, Names};
+	use DataType::*;
+	use Instr::*;
+
+	fn module() -> Wasm {
+		let log = FunctionType { params: vec![I32], resuls: vec![] };
+		let body = vec![
+			local_get(0),
+			if_else(
+				BlockType::Value(I32),
+				vec![local_get(0), i32_const(1), i32_sub, call(1), local_get(0), i32_mul],
+				vec![i32_const(1)],
+			),
+			local_tee(1),
+			call(0),
+			block(
+				BlockType::Empty,
+				vec![local_get(1), br_if(0), i32_const(8), f64_load(3, 16), drop],
+			),
+			local_get(1),
+		];
+		let signature = FunctionType { params: vec![I32], resuls: vec![I32] };
+		let mut names = Names { module: Some("fact".to_string()), ..Default::default() };
+		names.functions.insert(0, "log".to_string());
+		names.functions.insert(1, "fac torial".to_string());
+		names.locals.insert(1, [(0, "n".to_string())].into());
+		Wasm {
+			imports: vec![(log, "env".to_string(), "log".to_string())],
+			exports: [("fact".to_string(), 1)].into(),
+			functions: vec![Function { signature, locals: vec![I32], body }],
+			memories: vec![Limits { min: 1, max: Some(2) }],
+			data: vec![Data {
+				mode: DataMode::Active(0, vec![i32_const(8)]),
+				bytes: b"\0\"hi\"\n".to_vec(),
+			}],
+			globals: vec![Global {
+				typ: GlobalType { typ: F32, mutable: true },
+				init: vec![f32_const(f32::NEG_INFINITY)],
+			}],
+			tables: vec![TableType { typ: FuncRef, limits: Limits { min: 1, max: None } }],
+			elements: vec![Element {
+				mode: ElementMode::Active(0, vec![i32_const(0)]),
+				functions: vec![1],
+			}],
+			names,
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn prints_flat() {
+		let expected = r#"(module $fact
+  (import "env" "log" (func $log (;0;) (param i32)))
+  (func $fac_torial (;1;) (param $n i32) (result i32)
+    (local i32)
+    local.get $n
+    if (result i32)
+      local.get $n
+      i32.const 1
+      i32.sub
+      call $fac_torial
+      local.get $n
+      i32.mul
+    else
+      i32.const 1
+    end
+    local.tee 1
+    call $log
+    block
+      local.get 1
+      br_if 0
+      i32.const 8
+      f64.load offset=16
+      drop
+    end
+    local.get 1)
+  (table (;0;) 1 funcref)
+  (memory (;0;) 1 2)
+  (global (;0;) (mut f32) (f32.const -inf))
+  (export "fact" (func $fac_torial))
+  (elem (;0;) (i32.const 0) func $fac_torial)
+  (data (;0;) (i32.const 8) "\00\"hi\"\0a"))
+"#;
+		assert_eq!(module().to_wat(Style::Flat), expected);
+	}
+
+	#[test]
+	fn prints_folded() {
+		let expected = r#"  (func $fac_torial (;1;) (param $n i32) (result i32)
+    (local i32)
+    (call $log
+      (local.tee 1
+        (if (result i32)
+          (local.get $n)
+          (then
+            (i32.mul (call $fac_torial (i32.sub (local.get $n) (i32.const 1))) (local.get $n)))
+          (else
+            (i32.const 1)))))
+    (block
+      (br_if 0 (local.get 1))
+      (drop (f64.load offset=16 (i32.const 8))))
+    (local.get 1))
+"#;
+		assert!(module().to_wat(Style::Folded).contains(expected));
+	}
+
+	#[test]
+	fn prints_instructions() {
+		assert_eq!(f32_const(f32::from_bits(0xFFC00000)).to_string(), "f32.const -nan");
+		assert_eq!(f64_const(f64::from_bits(0x7FF0000000000001)).to_string(), "f64.const nan:0x1");
+		assert_eq!(f64_const(1e-7).to_string(), "f64.const 1e-7");
+		assert_eq!(i32_store8(1, 0).to_string(), "i32.store8 align=2");
+		assert_eq!(call_indirect(2, 1).to_string(), "call_indirect 1 (type 2)");
+		assert_eq!(br_table(vec![1, 0, 2]).to_string(), "br_table 1 0 2");
+		assert_eq!(looped(BlockType::Empty, vec![br(0)]).to_string(), "loop\n  br 0\nend");
+	}
+
+	#[test]
+	fn disambiguates_names() {
+		let mut wasm = module();
+		wasm.names.functions.insert(0, "fac torial".to_string());
+		wasm.names.locals.insert(1, [(0, "".to_string()), (1, "x;".to_string())].into());
+		let wat = wasm.to_wat(Style::Flat);
+		assert!(wat.contains("(func $fac_torial (;0;) (param i32))"));
+		assert!(wat.contains("(func $fac_torial.1 (;1;) (param $_ i32) (result i32)"));
+		assert!(wat.contains("(local $x_ i32)"));
+	}
+}
@@ -0,0 +1,679 @@
+use std::fmt;
+
+use super::cfg::{self, stack_effect, Cfg};
+use super::instruction::Instr;
+use super::validator::{memarg, parse_type};
+use super::{BlockType, DataType, Function, FunctionType, Wasm};
+
+/// A function in SSA form. Values are numbered across the whole function and defined exactly
+/// once, by an instruction or as a block parameter; block 0 is the entry and takes the
+/// function's parameters.
+pub struct Ssa {
+	pub signature: FunctionType,
+	pub types: Vec<DataType>,
+	pub blocks: Vec<Block>,
+}
+
+pub struct Block {
+	pub params: Vec<usize>,
+	pub insts: Vec<Inst>,
+	pub terminator: Terminator,
+}
+
+pub struct Inst {
+	pub op: Instr,
+	pub args: Vec<usize>,
+	pub results: Vec<usize>,
+	pub effect: Effect,
+}
+
+/// What an instruction does besides computing its results. Each effect implies the ones
+/// before it, so only `Pure` instructions can be freely moved or removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Effect {
+	Pure,
+	Trap,
+	Read,
+	Write,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Target {
+	pub block: usize,
+	pub args: Vec<usize>,
+}
+
+pub enum Terminator {
+	Jump(Target),
+	/// Continues at the first target unless the `i32` value is zero.
+	Branch(usize, Target, Target),
+	/// Continues at the target the `i32` value indexes, or at the last one.
+	Table(usize, Vec<Target>),
+	Return(Vec<usize>),
+	Unreachable,
+}
+
+impl Terminator {
+	pub fn targets(&self) -> Vec<&Target> {
+		match self {
+			Terminator::Jump(target) => vec![target],
+			Terminator::Branch(_, then, otherwise) => vec![then, otherwise],
+			Terminator::Table(_, targets) => targets.iter().collect(),
+			Terminator::Return(_) | Terminator::Unreachable => vec![],
+		}
+	}
+
+	fn targets_mut(&mut self) -> Vec<&mut Target> {
+		match self {
+			Terminator::Jump(target) => vec![target],
+			Terminator::Branch(_, then, otherwise) => vec![then, otherwise],
+			Terminator::Table(_, targets) => targets.iter_mut().collect(),
+			Terminator::Return(_) | Terminator::Unreachable => vec![],
+		}
+	}
+
+	/// The values used by the terminator itself, not counting block arguments.
+	fn operands_mut(&mut self) -> Vec<&mut usize> {
+		match self {
+			Terminator::Branch(condition, _, _) => vec![condition],
+			Terminator::Table(index, _) => vec![index],
+			Terminator::Return(values) => values.iter_mut().collect(),
+			Terminator::Jump(_) | Terminator::Unreachable => vec![],
+		}
+	}
+}
+
+fn effect(instr: &Instr) -> Effect {
+	use Instr::*;
+	match instr {
+		call(_) | call_indirect(..) | global_set(_) | table_set(_) | table_grow(_)
+		| table_fill(_) | table_copy(..) | table_init(..) | elem_drop(_) | memory_grow
+		| memory_init(..) | memory_copy(..) | memory_fill(_) | data_drop(_) => Effect::Write,
+		global_get(_) | table_get(_) | table_size(_) | memory_size => Effect::Read,
+		i32_div_s | i32_div_u | i32_rem_s | i32_rem_u | i64_div_s | i64_div_u | i64_rem_s
+		| i64_rem_u | i32_trunc_f32_s | i32_trunc_f32_u | i32_trunc_f64_s | i32_trunc_f64_u
+		| i64_trunc_f32_s | i64_trunc_f32_u | i64_trunc_f64_s | i64_trunc_f64_u => Effect::Trap,
+		instr if memarg(instr).is_none() => Effect::Pure,
+		instr if instr.signature().ends_with('>') => Effect::Write,
+		_ => Effect::Read,
+	}
+}
+
+fn zero(typ: &DataType) -> Instr {
+	match typ {
+		DataType::I32 => Instr::i32_const(0),
+		DataType::I64 => Instr::i64_const(0),
+		DataType::F32 => Instr::f32_const(0.0),
+		DataType::F64 => Instr::f64_const(0.0),
+		DataType::V128 => Instr::v128_const(0),
+		DataType::FuncRef | DataType::ExternRef => Instr::ref_null(typ.clone()),
+	}
+}
+
+struct Builder<'a> {
+	wasm: &'a Wasm,
+	cfg: Cfg,
+	locals: Vec<DataType>,
+	params: Vec<Option<Vec<DataType>>>,
+	ssa: Ssa,
+}
+
+impl Builder<'_> {
+	fn value(&mut self, typ: DataType) -> usize {
+		self.ssa.types.push(typ);
+		self.ssa.types.len() - 1
+	}
+
+	fn results(&self, instr: &Instr, args: &[usize]) -> Vec<DataType> {
+		use Instr::*;
+		match instr {
+			call(index) => self.wasm.function_type(*index).unwrap().resuls.clone(),
+			call_indirect(typ, _) => self.wasm.types[*typ].resuls.clone(),
+			global_get(index) => vec![self.wasm.global_type(*index as usize).unwrap().typ.clone()],
+			table_get(index) => vec![self.wasm.table_type(*index).unwrap().typ.clone()],
+			ref_null(typ) => vec![typ.clone()],
+			ref_func(_) => vec![DataType::FuncRef],
+			select => vec![self.ssa.types[args[0]].clone()],
+			select_t(types) => types.clone(),
+			instr => {
+				let (_, results) = instr.signature().split_once('>').unwrap();
+				results.split_whitespace().map(|name| parse_type(name).unwrap()).collect()
+			}
+		}
+	}
+
+	fn target(&mut self, block: usize, locals: &[usize], stack: &[usize]) -> Target {
+		let (height, arity) = (self.cfg.blocks[block].height, self.cfg.blocks[block].arity);
+		let mut args = locals.to_vec();
+		args.extend(&stack[..height - arity]);
+		args.extend(&stack[stack.len() - arity..]);
+		if self.params[block].is_none() {
+			self.params[block] =
+				Some(args.iter().map(|&arg| self.ssa.types[arg].clone()).collect());
+		}
+		Target { block, args }
+	}
+
+	fn block(&mut self, index: usize) {
+		use Instr::*;
+		let mut insts = vec![];
+		let (params, mut locals, mut stack) = if index == 0 {
+			let params = self.ssa.signature.params.clone();
+			let params: Vec<_> = params.into_iter().map(|typ| self.value(typ)).collect();
+			let mut locals = params.clone();
+			for typ in self.locals.clone().into_iter().skip(params.len()) {
+				let value = self.value(typ.clone());
+				insts.push(Inst {
+					op: zero(&typ),
+					args: vec![],
+					results: vec![value],
+					effect: Effect::Pure,
+				});
+				locals.push(value);
+			}
+			(params, locals, vec![])
+		} else {
+			let types = self.params[index].clone().unwrap();
+			let params: Vec<_> = types.into_iter().map(|typ| self.value(typ)).collect();
+			let (locals, stack) = params.split_at(self.locals.len());
+			(params.clone(), locals.to_vec(), stack.to_vec())
+		};
+		for instr in std::mem::take(&mut self.cfg.blocks[index].body) {
+			match instr {
+				local_get(local) => stack.push(locals[local as usize]),
+				local_set(local) => locals[local as usize] = stack.pop().unwrap(),
+				local_tee(local) => locals[local as usize] = *stack.last().unwrap(),
+				drop => _ = stack.pop(),
+				nop => {}
+				instr => {
+					let (pops, _) = stack_effect(self.wasm, &instr);
+					let args = stack.split_off(stack.len() - pops);
+					let types = self.results(&instr, &args);
+					let results: Vec<_> = types.into_iter().map(|typ| self.value(typ)).collect();
+					stack.extend(&results);
+					let effect = effect(&instr);
+					insts.push(Inst { op: instr, args, results, effect });
+				}
+			}
+		}
+		let terminator = match self.cfg.blocks[index].terminator.clone() {
+			cfg::Terminator::Jump(target) => Terminator::Jump(self.target(target, &locals, &stack)),
+			cfg::Terminator::Branch(then, otherwise) => {
+				let condition = stack.pop().unwrap();
+				let then = self.target(then, &locals, &stack);
+				Terminator::Branch(condition, then, self.target(otherwise, &locals, &stack))
+			}
+			cfg::Terminator::Table(targets) => {
+				let index = stack.pop().unwrap();
+				let targets =
+					targets.into_iter().map(|t| self.target(t, &locals, &stack)).collect();
+				Terminator::Table(index, targets)
+			}
+			cfg::Terminator::Return => {
+				let results = self.ssa.signature.resuls.len();
+				Terminator::Return(stack[stack.len() - results..].to_vec())
+			}
+			cfg::Terminator::Unreachable => Terminator::Unreachable,
+		};
+		self.ssa.blocks[index] = Block { params, insts, terminator };
+	}
+}
+
+impl Function {
+	/// Converts a valid function body to SSA form. Every block takes the current value of each
+	/// local and of each stack slot as parameters, and those that always receive the same
+	/// value are then removed.
+	pub fn to_ssa(&self, wasm: &Wasm) -> Ssa {
+		let cfg = self.cfg(wasm);
+		let mut locals = self.signature.params.clone();
+		locals.extend(self.locals.iter().cloned());
+		let blocks = (0..cfg.blocks.len())
+			.map(|_| Block { params: vec![], insts: vec![], terminator: Terminator::Unreachable })
+			.collect();
+		let signature = self.signature.clone();
+		let params = vec![None; cfg.blocks.len()];
+		let mut builder =
+			Builder { wasm, cfg, locals, params, ssa: Ssa { signature, types: vec![], blocks } };
+		for block in builder.cfg.postorder().into_iter().rev() {
+			builder.block(block);
+		}
+		let mut ssa = builder.ssa;
+		ssa.remove_redundant_params();
+		ssa
+	}
+}
+
+impl Ssa {
+	pub fn successors(&self) -> Vec<Vec<usize>> {
+		let targets = |block: &Block| block.terminator.targets().iter().map(|t| t.block).collect();
+		self.blocks.iter().map(targets).collect()
+	}
+
+	// A parameter is redundant when every jump passes it either itself or one other value.
+	fn remove_redundant_params(&mut self) {
+		let mut alias: Vec<_> = (0..self.types.len()).collect();
+		let find = |alias: &[usize], mut value: usize| {
+			while alias[value] != value {
+				value = alias[value];
+			}
+			value
+		};
+		let mut incoming = vec![vec![]; self.blocks.len()];
+		for block in self.blocks.iter() {
+			for target in block.terminator.targets() {
+				incoming[target.block].push(&target.args);
+			}
+		}
+		let mut changed = true;
+		while changed {
+			changed = false;
+			for (block, incoming) in self.blocks.iter().zip(&incoming).skip(1) {
+				for (i, &param) in block.params.iter().enumerate() {
+					if alias[param] != param {
+						continue;
+					}
+					let mut values = incoming.iter().map(|args| find(&alias, args[i]));
+					let mut values = values.by_ref().filter(|&value| value != param);
+					let Some(value) = values.next() else {
+						continue;
+					};
+					if values.all(|other| other == value) {
+						alias[param] = value;
+						changed = true;
+					}
+				}
+			}
+		}
+		for block in self.blocks.iter_mut() {
+			for inst in block.insts.iter_mut() {
+				for arg in inst.args.iter_mut() {
+					*arg = find(&alias, *arg);
+				}
+			}
+			for operand in block.terminator.operands_mut() {
+				*operand = find(&alias, *operand);
+			}
+			for target in block.terminator.targets_mut() {
+				for arg in target.args.iter_mut() {
+					*arg = find(&alias, *arg);
+				}
+			}
+		}
+		let keep: Vec<Vec<_>> = (self.blocks.iter())
+			.map(|block| block.params.iter().map(|&param| alias[param] == param).collect())
+			.collect();
+		for block in self.blocks.iter_mut() {
+			block.params.retain(|&param| alias[param] == param);
+			for target in block.terminator.targets_mut() {
+				let mut keep = keep[target.block].iter();
+				target.args.retain(|_| *keep.next().unwrap());
+			}
+		}
+	}
+
+	/// Converts back to a structured function body, placing blocks along the dominator tree
+	/// and giving each value its own local before coalescing them.
+	pub fn to_function(&self) -> Function {
+		let successors = self.successors();
+		let order: Vec<_> = cfg::postorder(&successors).into_iter().rev().collect();
+		let mut position = vec![usize::MAX; self.blocks.len()];
+		for (i, &block) in order.iter().enumerate() {
+			position[block] = i;
+		}
+		let (mut forward, mut header) =
+			(vec![0; self.blocks.len()], vec![false; self.blocks.len()]);
+		for &block in order.iter() {
+			for &target in successors[block].iter() {
+				match position[target] <= position[block] {
+					true => header[target] = true,
+					false => forward[target] += 1,
+				}
+			}
+		}
+		let merge: Vec<_> = forward.iter().map(|&count| count > 1).collect();
+		let dominators = cfg::dominators(&successors);
+		let mut children = vec![vec![]; self.blocks.len()];
+		for &block in order.iter() {
+			if let Some(parent) = dominators.immediate[block] {
+				if merge[block] && parent != block {
+					children[parent].push(block);
+				}
+			}
+		}
+
+		let params = self.signature.params.len();
+		let mut locals = vec![None; self.types.len()];
+		for (i, &param) in self.blocks[0].params.iter().enumerate() {
+			locals[param] = Some(i as u32);
+		}
+		let mut types = vec![];
+		let locals = (locals.into_iter().zip(&self.types))
+			.map(|(local, typ)| {
+				local.unwrap_or_else(|| {
+					types.push(typ.clone());
+					(params + types.len() - 1) as u32
+				})
+			})
+			.collect();
+		let stackifier = Stackifier { ssa: self, locals, position, merge, header, children };
+		let mut body = stackifier.tree(0, &mut vec![]);
+		if !self.signature.resuls.is_empty() {
+			body.push(Instr::unreachable);
+		}
+		let mut function = Function { signature: self.signature.clone(), locals: types, body };
+		function.optimize_locals();
+		function
+	}
+}
+
+#[derive(PartialEq)]
+enum Context {
+	If,
+	Block(usize),
+	Loop(usize),
+	Other,
+}
+
+// Structures the blocks as in "Beyond Relooper": each block's code is followed by the blocks it
+// immediately dominates that have several forward predecessors, each of which closes a `block`
+// that jumps to it, and blocks that are targets of back edges are wrapped in a `loop`.
+struct Stackifier<'a> {
+	ssa: &'a Ssa,
+	locals: Vec<u32>,
+	position: Vec<usize>,
+	merge: Vec<bool>,
+	header: Vec<bool>,
+	children: Vec<Vec<usize>>,
+}
+
+impl Stackifier<'_> {
+	fn get(&self, value: usize) -> Instr {
+		Instr::local_get(self.locals[value])
+	}
+
+	fn tree(&self, block: usize, context: &mut Vec<Context>) -> Vec<Instr> {
+		if !self.header[block] {
+			return self.within(block, &self.children[block], context);
+		}
+		context.push(Context::Loop(block));
+		let body = self.within(block, &self.children[block], context);
+		context.pop();
+		vec![Instr::looped(BlockType::Empty, body)]
+	}
+
+	fn within(&self, block: usize, merges: &[usize], context: &mut Vec<Context>) -> Vec<Instr> {
+		let Some((&last, merges)) = merges.split_last() else {
+			return self.code(block, context);
+		};
+		context.push(Context::Block(last));
+		let body = self.within(block, merges, context);
+		context.pop();
+		let mut code = vec![Instr::block(BlockType::Empty, body)];
+		code.extend(self.tree(last, context));
+		code
+	}
+
+	fn code(&self, index: usize, context: &mut Vec<Context>) -> Vec<Instr> {
+		let block = &self.ssa.blocks[index];
+		let mut code = vec![];
+		for inst in block.insts.iter() {
+			code.extend(inst.args.iter().map(|&arg| self.get(arg)));
+			code.push(inst.op.clone());
+			code.extend(inst.results.iter().rev().map(|&v| Instr::local_set(self.locals[v])));
+		}
+		match &block.terminator {
+			Terminator::Jump(target) => code.extend(self.branch(index, target, context)),
+			Terminator::Branch(condition, then, otherwise) => {
+				code.push(self.get(*condition));
+				context.push(Context::If);
+				let then = self.branch(index, then, context);
+				let otherwise = self.branch(index, otherwise, context);
+				context.pop();
+				code.push(Instr::if_else(BlockType::Empty, then, otherwise));
+			}
+			Terminator::Table(value, targets) => {
+				let mut distinct: Vec<&Target> = vec![];
+				for target in targets.iter() {
+					if !distinct.contains(&target) {
+						distinct.push(target);
+					}
+				}
+				let labels = (targets.iter())
+					.map(|target| distinct.iter().position(|other| *other == target).unwrap())
+					.collect();
+				let mut inner = vec![self.get(*value), Instr::br_table(labels)];
+				for (i, target) in distinct.iter().enumerate() {
+					let outer = distinct.len() - 1 - i;
+					context.extend((0..outer).map(|_| Context::Other));
+					let mut wrapped = vec![Instr::block(BlockType::Empty, inner)];
+					wrapped.extend(self.branch(index, target, context));
+					context.truncate(context.len() - outer);
+					inner = wrapped;
+				}
+				code.extend(inner);
+			}
+			Terminator::Return(values) => {
+				code.extend(values.iter().map(|&value| self.get(value)));
+				code.push(Instr::return_result);
+			}
+			Terminator::Unreachable => code.push(Instr::unreachable),
+		}
+		code
+	}
+
+	fn branch(&self, source: usize, target: &Target, context: &mut Vec<Context>) -> Vec<Instr> {
+		let params = &self.ssa.blocks[target.block].params;
+		let copies: Vec<_> =
+			target.args.iter().zip(params).filter(|(arg, param)| arg != param).collect();
+		let mut code: Vec<_> = copies.iter().map(|(&arg, _)| self.get(arg)).collect();
+		code.extend(copies.iter().rev().map(|(_, &param)| Instr::local_set(self.locals[param])));
+		let label = |wanted: Context| context.iter().rev().position(|c| *c == wanted).unwrap();
+		if self.position[target.block] <= self.position[source] {
+			code.push(Instr::br(label(Context::Loop(target.block))));
+		} else if self.merge[target.block] {
+			code.push(Instr::br(label(Context::Block(target.block))));
+		} else {
+			code.extend(self.tree(target.block, context));
+		}
+		code
+	}
+}
+
+impl fmt::Display for Ssa {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let values = |values: &[usize]| {
+			values.iter().map(|value| format!("v{}", value)).collect::<Vec<_>>().join(", ")
+		};
+		let target = |target: &Target| format!("b{}({})", target.block, values(&target.args));
+		for (i, block) in self.blocks.iter().enumerate() {
+			let params: Vec<_> = (block.params.iter())
+				.map(|&param| format!("v{}: {}", param, self.types[param]))
+				.collect();
+			writeln!(f, "b{}({}):", i, params.join(", "))?;
+			for inst in block.insts.iter() {
+				write!(f, "\t")?;
+				if !inst.results.is_empty() {
+					write!(f, "{} = ", values(&inst.results))?;
+				}
+				write!(f, "{}", inst.op)?;
+				if !inst.args.is_empty() {
+					write!(f, " {}", values(&inst.args))?;
+				}
+				writeln!(f)?;
+			}
+			match &block.terminator {
+				Terminator::Jump(to) => writeln!(f, "\tjump {}", target(to))?,
+				Terminator::Branch(condition, then, otherwise) => {
+					writeln!(f, "\tbranch v{}, {}, {}", condition, target(then), target(otherwise))?
+				}
+				Terminator::Table(index, targets) => {
+					let targets: Vec<_> = targets.iter().map(target).collect();
+					writeln!(f, "\ttable v{}, {}", index, targets.join(", "))?
+				}
+				Terminator::Return(results) if results.is_empty() => writeln!(f, "\treturn")?,
+				Terminator::Return(results) => writeln!(f, "\treturn {}", values(results))?,
+				Terminator::Unreachable => writeln!(f, "\tunreachable")?,
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::interpreter::{Instance, Value};
+
+	const FUNCTIONS: &str = r#"
+		(module
+			(memory 1)
+			(func $fib (export "fib") (param i32) (result i32) (local i32 i32)
+				i32.const 1
+				local.set 2
+				block
+					loop
+						local.get 0
+						i32.eqz
+						br_if 1
+						local.get 1
+						local.get 2
+						local.tee 1
+						i32.add
+						local.set 2
+						local.get 0
+						i32.const 1
+						i32.sub
+						local.set 0
+						br 0
+					end
+				end
+				local.get 1)
+			(func (export "switch") (param i32) (result i32)
+				block
+					block
+						block
+							local.get 0
+							br_table 0 1 2 1
+						end
+						i32.const 10
+						return
+					end
+					i32.const 20
+					local.get 0
+					i32.const 3
+					i32.eq
+					br_if 1
+					drop
+					i32.const 21
+					return
+				end
+				i32.const 30)
+			(func (export "pairs") (param i32 i32) (result i32 i32)
+				local.get 0
+				local.get 1
+				local.get 0
+				block (param i32 i32) (result i32 i32)
+					if (param i32) (result i32 i32)
+						i32.const 1
+						i32.add
+						local.get 1
+					else
+						local.get 1
+						i32.const 0
+						local.get 0
+						select
+					end
+				end
+				i32.add
+				i32.mul
+				local.get 0)
+			(func (export "store") (param i32 i32) (result i32)
+				(local i64)
+				loop
+					local.get 1
+					local.get 0
+					i32.store offset=4
+					local.get 0
+					i32.const 4
+					i32.add
+					local.tee 0
+					i32.const 40
+					i32.lt_u
+					br_if 0
+				end
+				local.get 0
+				i32.load
+				local.get 2
+				i64.eqz
+				i32.add
+				local.get 1
+				call $fib
+				i32.add))
+	"#;
+
+	fn round_trip(wasm: &Wasm) -> Wasm {
+		wasm.validate().unwrap();
+		let mut result = wasm.clone();
+		for function in result.functions.iter_mut() {
+			*function = function.to_ssa(wasm).to_function();
+		}
+		result.validate().unwrap();
+		result
+	}
+
+	#[test]
+	fn round_trips_functions() {
+		let wasm = Wasm::from_wat(FUNCTIONS).unwrap();
+		let after = round_trip(&wasm);
+		let mut before = Instance::new(&wasm).unwrap();
+		let mut after = Instance::new(&after).unwrap();
+		let calls: &[(&str, &[i32])] = &[
+			("fib", &[0]),
+			("fib", &[10]),
+			("switch", &[0]),
+			("switch", &[1]),
+			("switch", &[2]),
+			("switch", &[3]),
+			("switch", &[7]),
+			("pairs", &[0, 5]),
+			("pairs", &[3, 0]),
+			("pairs", &[3, 7]),
+			("store", &[0, 9]),
+			("store", &[36, 3]),
+		];
+		for (name, args) in calls {
+			let args: Vec<_> = args.iter().map(|&arg| Value::I32(arg)).collect();
+			assert_eq!(after.invoke(name, &args), before.invoke(name, &args), "{}", name);
+		}
+	}
+
+	#[test]
+	fn removes_redundant_params() {
+		let wasm = Wasm::from_wat(
+			r#"
+			(module
+				(func (param i32) (result i32) (local i32)
+					block
+						local.get 0
+						br_if 0
+						i32.const 5
+						local.set 1
+					end
+					local.get 1
+					local.get 0
+					i32.div_u))
+		"#,
+		)
+		.unwrap();
+		let ssa = wasm.functions[0].to_ssa(&wasm);
+		let expected = "\
+			b0(v0: i32):\n\tv1 = i32.const 0\n\tbranch v0, b2(v1), b3()\n\
+			b1():\n\treturn v7\n\
+			b2(v6: i32):\n\tv7 = i32.div_u v6, v0\n\tjump b1()\n\
+			b3():\n\tv4 = i32.const 5\n\tjump b2(v4)\n";
+		assert_eq!(ssa.to_string(), expected);
+		assert_eq!(ssa.blocks[2].insts[0].effect, Effect::Trap);
+		let function = ssa.to_function();
+		assert_eq!(function.locals.len(), 1);
+	}
+}
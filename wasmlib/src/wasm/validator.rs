@@ -0,0 +1,883 @@
+use std::fmt;
+
+use super::instruction::Instr;
+use super::memory::MAX_PAGES;
+use super::{BlockType, DataMode, DataType, ElementMode, Function, FunctionType, GlobalType, Wasm};
+
+pub struct ValidationError {
+	pub function: Option<usize>,
+	pub path: Vec<usize>,
+	pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let path: Vec<_> = self.path.iter().map(|i| i.to_string()).collect();
+		match (self.function, path.is_empty()) {
+			(None, _) => write!(f, "{}", self.message),
+			(Some(function), true) => write!(f, "function {}: {}", function, self.message),
+			(Some(function), false) => {
+				write!(f, "function {}, instruction {}: {}", function, path.join("."), self.message)
+			}
+		}
+	}
+}
+
+impl fmt::Debug for ValidationError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+impl Wasm {
+	pub fn validate(&self) -> Result<(), ValidationError> {
+		self.validate_module().map_err(|message| ValidationError {
+			function: None,
+			path: vec![],
+			message,
+		})?;
+		for (i, function) in self.functions.iter().enumerate() {
+			let index = self.imports.len() + i;
+			let mut validator = Validator::new(self, function);
+			validator.function(function).map_err(|message| ValidationError {
+				function: Some(index),
+				path: validator.path.clone(),
+				message,
+			})?;
+		}
+		if let Some(start) = self.start_function {
+			let Some(typ) = self.function_type(start) else {
+				let message = format!("Unknown start function: {}", start);
+				return Err(ValidationError { function: None, path: vec![], message });
+			};
+			if !typ.params.is_empty() || !typ.resuls.is_empty() {
+				let message = "Start function must not take or return values".to_string();
+				return Err(ValidationError { function: Some(start), path: vec![], message });
+			}
+		}
+		Ok(())
+	}
+
+	fn validate_module(&self) -> Check {
+		let imported = self.memory_imports.iter().map(|(limits, _, _)| limits);
+		let memories: Vec<_> = imported.chain(self.memories.iter()).collect();
+		if memories.len() > 1 {
+			return Err("Multiple memories are not supported".to_string());
+		}
+		for limits in memories {
+			if limits.min > MAX_PAGES || limits.max.is_some_and(|max| max > MAX_PAGES) {
+				return Err(format!("Memory size must be at most {} pages", MAX_PAGES));
+			}
+			if limits.max.is_some_and(|max| max < limits.min) {
+				return Err("Memory maximum must not be smaller than minimum".to_string());
+			}
+		}
+		let imported = self.table_imports.iter().map(|(typ, _, _)| typ);
+		for typ in imported.chain(self.tables.iter()) {
+			if typ.limits.max.is_some_and(|max| max < typ.limits.min) {
+				return Err("Table maximum must not be smaller than minimum".to_string());
+			}
+		}
+		let exports = [
+			("function", &self.exports, self.imports.len() + self.functions.len()),
+			("table", &self.table_exports, self.table_imports.len() + self.tables.len()),
+			("memory", &self.memory_exports, self.memory_imports.len() + self.memories.len()),
+			("global", &self.global_exports, self.global_imports.len() + self.globals.len()),
+		];
+		for (kind, exports, count) in exports {
+			let mut names: Vec<_> = exports.iter().filter(|(_, &index)| index >= count).collect();
+			names.sort();
+			if let Some((name, index)) = names.first() {
+				return Err(format!("Export {}: Unknown {}: {}", name, kind, index));
+			}
+		}
+		for (i, element) in self.elements.iter().enumerate() {
+			if let ElementMode::Active(table, offset) = &element.mode {
+				match self.table_type(*table) {
+					Some(typ) if typ.typ == DataType::FuncRef => {}
+					Some(typ) => {
+						return Err(format!(
+							"Element segment {}: Expected funcref table but found {}",
+							i, typ.typ
+						))
+					}
+					None => return Err(format!("Element segment {}: Unknown table: {}", i, table)),
+				}
+				self.validate_constant(offset, DataType::I32)
+					.map_err(|message| format!("Element segment {}: {}", i, message))?;
+			}
+			for &function in element.functions.iter() {
+				if function >= self.imports.len() + self.functions.len() {
+					return Err(format!("Element segment {}: Unknown function: {}", i, function));
+				}
+			}
+		}
+		for (i, global) in self.globals.iter().enumerate() {
+			let index = self.global_imports.len() + i;
+			self.validate_constant(&global.init, global.typ.typ.clone())
+				.map_err(|message| format!("Global {}: {}", index, message))?;
+		}
+		for (i, data) in self.data.iter().enumerate() {
+			if let DataMode::Active(memory, offset) = &data.mode {
+				if *memory >= self.memory_imports.len() + self.memories.len() {
+					return Err(format!("Data segment {}: Unknown memory: {}", i, memory));
+				}
+				self.validate_constant(offset, DataType::I32)
+					.map_err(|message| format!("Data segment {}: {}", i, message))?;
+			}
+		}
+		Ok(())
+	}
+
+	fn validate_constant(&self, expr: &[Instr], typ: DataType) -> Check {
+		for instr in expr {
+			match instr {
+				Instr::i32_const(_) | Instr::i64_const(_) | Instr::f32_const(_) => {}
+				Instr::f64_const(_) | Instr::ref_null(_) | Instr::ref_func(_) => {}
+				Instr::global_get(index) => match self.global_imports.get(*index as usize) {
+					Some((typ, _, _)) if !typ.mutable => {}
+					_ => {
+						return Err(
+							"Constant expression may only read immutable imports".to_string()
+						)
+					}
+				},
+				_ => return Err("Constant expression required".to_string()),
+			}
+		}
+		let mut validator =
+			Validator { wasm: self, locals: vec![], stack: vec![], frames: vec![], path: vec![] };
+		validator.block(vec![], &[], vec![typ], expr, 0)
+	}
+
+	fn declares(&self, function: usize) -> bool {
+		self.elements.iter().any(|element| element.functions.contains(&function))
+			|| self.exports.values().any(|&index| index == function)
+			|| self.globals.iter().any(|global| global.init.contains(&Instr::ref_func(function)))
+	}
+}
+
+struct Frame {
+	labels: Vec<DataType>,
+	results: Vec<DataType>,
+	height: usize,
+	unreachable: bool,
+}
+
+struct Validator<'a> {
+	wasm: &'a Wasm,
+	locals: Vec<DataType>,
+	stack: Vec<Option<DataType>>,
+	frames: Vec<Frame>,
+	path: Vec<usize>,
+}
+
+type Check<T = ()> = Result<T, String>;
+
+impl<'a> Validator<'a> {
+	fn new(wasm: &'a Wasm, function: &Function) -> Validator<'a> {
+		let mut locals = function.signature.params.clone();
+		locals.extend(function.locals.iter().cloned());
+		Validator { wasm, locals, stack: vec![], frames: vec![], path: vec![] }
+	}
+
+	fn function(&mut self, function: &Function) -> Check {
+		let results = function.signature.resuls.clone();
+		self.block(results.clone(), &[], results, &function.body, 0)
+	}
+
+	fn block(
+		&mut self,
+		labels: Vec<DataType>,
+		params: &[DataType],
+		results: Vec<DataType>,
+		body: &[Instr],
+		first: usize,
+	) -> Check {
+		let height = self.stack.len();
+		self.frames.push(Frame { labels, results, height, unreachable: false });
+		self.push_values(params);
+		for (i, instr) in body.iter().enumerate() {
+			self.path.push(first + i);
+			self.instr(instr)?;
+			self.path.pop();
+		}
+		let results = self.frames.last().unwrap().results.clone();
+		self.pop_values(&results)?;
+		if self.stack.len() != height {
+			return Err(format!(
+				"Block leaves {} extra values on the stack",
+				self.stack.len() - height
+			));
+		}
+		self.frames.pop();
+		Ok(())
+	}
+
+	fn instr(&mut self, instr: &Instr) -> Check {
+		use DataType::*;
+		use Instr::*;
+		match instr {
+			unreachable => self.set_unreachable(),
+			nop => {}
+			block(typ, body) => {
+				let FunctionType { params, resuls } = self.block_type(typ)?;
+				self.pop_values(&params)?;
+				self.block(resuls.clone(), &params, resuls.clone(), body, 0)?;
+				self.push_values(&resuls);
+			}
+			looped(typ, body) => {
+				let FunctionType { params, resuls } = self.block_type(typ)?;
+				self.pop_values(&params)?;
+				self.block(params.clone(), &params, resuls.clone(), body, 0)?;
+				self.push_values(&resuls);
+			}
+			if_else(typ, then, otherwise) => {
+				self.pop(Some(I32))?;
+				let FunctionType { params, resuls } = self.block_type(typ)?;
+				if otherwise.is_empty() && params != resuls {
+					return Err("If without else must return its parameters".to_string());
+				}
+				self.pop_values(&params)?;
+				self.block(resuls.clone(), &params, resuls.clone(), then, 0)?;
+				self.block(resuls.clone(), &params, resuls.clone(), otherwise, then.len())?;
+				self.push_values(&resuls);
+			}
+			then_end | end => return Err("Unexpected end of block".to_string()),
+			br(label) => {
+				let labels = self.label(*label)?;
+				self.pop_values(&labels)?;
+				self.set_unreachable();
+			}
+			br_if(label) => {
+				self.pop(Some(I32))?;
+				let labels = self.label(*label)?;
+				self.pop_values(&labels)?;
+				self.push_values(&labels);
+			}
+			br_table(labels) => {
+				self.pop(Some(I32))?;
+				let Some(&default) = labels.last() else {
+					return Err("br_table without default label".to_string());
+				};
+				let default = self.label(default)?;
+				for &label in labels.iter() {
+					if self.label(label)? != default {
+						return Err(format!("Branch table label {} has different types", label));
+					}
+				}
+				self.pop_values(&default)?;
+				self.set_unreachable();
+			}
+			return_result => {
+				let results = self.frames[0].results.clone();
+				self.pop_values(&results)?;
+				self.set_unreachable();
+			}
+			call(index) => {
+				let typ = self.wasm.function_type(*index);
+				let typ = typ.ok_or_else(|| format!("Unknown function: {}", index))?;
+				self.pop_values(&typ.params)?;
+				self.push_values(&typ.resuls);
+			}
+			call_indirect(typ, table) => {
+				self.funcref_table(*table)?;
+				let typ =
+					self.wasm.types.get(*typ).ok_or_else(|| format!("Unknown type: {}", typ))?;
+				self.pop(Some(I32))?;
+				self.pop_values(&typ.params)?;
+				self.push_values(&typ.resuls);
+			}
+			local_get(index) => {
+				let typ = self.local(*index)?;
+				self.push(Some(typ));
+			}
+			local_set(index) => {
+				let typ = self.local(*index)?;
+				self.pop(Some(typ))?;
+			}
+			local_tee(index) => {
+				let typ = self.local(*index)?;
+				self.pop(Some(typ.clone()))?;
+				self.push(Some(typ));
+			}
+			global_get(index) => {
+				let typ = self.global(*index)?;
+				self.push(Some(typ.typ));
+			}
+			global_set(index) => {
+				let typ = self.global(*index)?;
+				if !typ.mutable {
+					return Err(format!("Global {} is immutable", index));
+				}
+				self.pop(Some(typ.typ))?;
+			}
+			select => {
+				self.pop(Some(I32))?;
+				let first = self.pop(None)?;
+				let second = self.pop(None)?;
+				if [&first, &second]
+					.iter()
+					.any(|typ| typ.as_ref().is_some_and(DataType::is_reference))
+				{
+					return Err("Untyped select requires numeric operands".to_string());
+				}
+				match (&first, &second) {
+					(Some(a), Some(b)) if a != b => {
+						return Err(format!("Select operands differ: {} and {}", b, a))
+					}
+					_ => self.push(first.or(second)),
+				}
+			}
+			select_t(types) => {
+				let [typ] = &types[..] else {
+					return Err("Typed select must have exactly one type".to_string());
+				};
+				self.pop(Some(I32))?;
+				self.pop(Some(typ.clone()))?;
+				self.pop(Some(typ.clone()))?;
+				self.push(Some(typ.clone()));
+			}
+			ref_null(typ) => {
+				if !typ.is_reference() {
+					return Err(format!("Invalid reference type: {}", typ));
+				}
+				self.push(Some(typ.clone()));
+			}
+			ref_is_null => {
+				if let Some(typ) = self.pop(None)?.filter(|typ| !typ.is_reference()) {
+					return Err(format!("Expected reference but found {}", typ));
+				}
+				self.push(Some(I32));
+			}
+			ref_func(index) => {
+				if self.wasm.function_type(*index).is_none() {
+					return Err(format!("Unknown function: {}", index));
+				}
+				if !self.wasm.declares(*index) {
+					return Err(format!("Undeclared function reference: {}", index));
+				}
+				self.push(Some(FuncRef));
+			}
+			table_get(table) => {
+				let typ = self.table(*table)?;
+				self.pop(Some(I32))?;
+				self.push(Some(typ));
+			}
+			table_set(table) => {
+				let typ = self.table(*table)?;
+				self.pop(Some(typ))?;
+				self.pop(Some(I32))?;
+			}
+			table_grow(table) => {
+				let typ = self.table(*table)?;
+				self.pop(Some(I32))?;
+				self.pop(Some(typ))?;
+				self.push(Some(I32));
+			}
+			table_fill(table) => {
+				let typ = self.table(*table)?;
+				self.pop(Some(I32))?;
+				self.pop(Some(typ))?;
+				self.pop(Some(I32))?;
+			}
+			memory_size | memory_grow => {
+				self.memory(0)?;
+				self.signature(instr)?;
+			}
+			memory_init(data, memory) => {
+				self.memory(*memory)?;
+				self.data(*data)?;
+				self.signature(instr)?;
+			}
+			data_drop(data) => self.data(*data)?,
+			memory_copy(destination, source) => {
+				self.memory(*destination)?;
+				self.memory(*source)?;
+				self.signature(instr)?;
+			}
+			memory_fill(memory) => {
+				self.memory(*memory)?;
+				self.signature(instr)?;
+			}
+			table_init(element, table) => {
+				self.funcref_table(*table)?;
+				self.element(*element)?;
+				self.signature(instr)?;
+			}
+			elem_drop(element) => self.element(*element)?,
+			table_copy(destination, source) => {
+				let (to, from) = (self.table(*destination)?, self.table(*source)?);
+				if to != from {
+					return Err(format!("Table types differ: {} and {}", to, from));
+				}
+				self.signature(instr)?;
+			}
+			table_size(table) => {
+				self.table(*table)?;
+				self.signature(instr)?;
+			}
+			i8x16_shuffle(lanes) => {
+				if let Some(lane) = lanes.iter().find(|&&lane| lane >= 32) {
+					return Err(format!("Invalid lane index: {}", lane));
+				}
+				self.signature(instr)?;
+			}
+			instr => {
+				if let Some((align, _, natural)) = memarg(instr) {
+					self.memory(0)?;
+					if align > natural {
+						return Err("Alignment must not be larger than natural".to_string());
+					}
+				}
+				if let Some((lane, lanes)) = lane(instr) {
+					if lane >= lanes {
+						return Err(format!("Invalid lane index: {}", lane));
+					}
+				}
+				self.signature(instr)?;
+			}
+		}
+		Ok(())
+	}
+
+	fn signature(&mut self, instr: &Instr) -> Check {
+		let (params, results) = instr.signature().split_once('>').unwrap();
+		for param in params.split_whitespace().rev() {
+			self.pop(parse_type(param))?;
+		}
+		for result in results.split_whitespace() {
+			self.push(parse_type(result));
+		}
+		Ok(())
+	}
+
+	fn memory(&self, index: usize) -> Check {
+		match index < self.wasm.memory_imports.len() + self.wasm.memories.len() {
+			true => Ok(()),
+			false => Err(format!("Unknown memory: {}", index)),
+		}
+	}
+
+	fn table(&self, index: usize) -> Check<DataType> {
+		let typ = self.wasm.table_type(index).map(|typ| typ.typ.clone());
+		typ.ok_or_else(|| format!("Unknown table: {}", index))
+	}
+
+	fn funcref_table(&self, index: usize) -> Check {
+		match self.table(index)? {
+			DataType::FuncRef => Ok(()),
+			typ => Err(format!("Expected funcref table but found {}", typ)),
+		}
+	}
+
+	fn data(&self, index: usize) -> Check {
+		match index < self.wasm.data.len() {
+			true => Ok(()),
+			false => Err(format!("Unknown data segment: {}", index)),
+		}
+	}
+
+	fn element(&self, index: usize) -> Check {
+		match index < self.wasm.elements.len() {
+			true => Ok(()),
+			false => Err(format!("Unknown element segment: {}", index)),
+		}
+	}
+
+	fn global(&self, index: u32) -> Check<GlobalType> {
+		let typ = self.wasm.global_type(index as usize);
+		typ.cloned().ok_or_else(|| format!("Unknown global: {}", index))
+	}
+
+	fn block_type(&self, typ: &BlockType) -> Check<FunctionType> {
+		match typ {
+			BlockType::Type(index) if *index >= self.wasm.types.len() => {
+				Err(format!("Unknown type: {}", index))
+			}
+			typ => Ok(self.wasm.block_type(typ).unwrap()),
+		}
+	}
+
+	fn label(&self, depth: usize) -> Check<Vec<DataType>> {
+		match self.frames.len().checked_sub(depth + 1) {
+			Some(i) => Ok(self.frames[i].labels.clone()),
+			None => Err(format!("Unknown label: {}", depth)),
+		}
+	}
+
+	fn local(&self, index: u32) -> Check<DataType> {
+		let typ = self.locals.get(index as usize);
+		typ.cloned().ok_or_else(|| format!("Unknown local: {}", index))
+	}
+
+	fn set_unreachable(&mut self) {
+		let frame = self.frames.last_mut().unwrap();
+		self.stack.truncate(frame.height);
+		frame.unreachable = true;
+	}
+
+	fn push(&mut self, typ: Option<DataType>) {
+		self.stack.push(typ);
+	}
+
+	fn push_values(&mut self, types: &[DataType]) {
+		self.stack.extend(types.iter().cloned().map(Some));
+	}
+
+	fn pop(&mut self, expected: Option<DataType>) -> Check<Option<DataType>> {
+		let frame = self.frames.last().unwrap();
+		if self.stack.len() == frame.height {
+			return match frame.unreachable {
+				true => Ok(expected),
+				false => Err(match expected {
+					Some(typ) => format!("Expected {} but the stack is empty", typ),
+					None => "Expected a value but the stack is empty".to_string(),
+				}),
+			};
+		}
+		match (self.stack.pop().unwrap(), expected) {
+			(Some(actual), Some(expected)) if actual != expected => {
+				Err(format!("Expected {} but found {}", expected, actual))
+			}
+			(actual, expected) => Ok(actual.or(expected)),
+		}
+	}
+
+	fn pop_values(&mut self, types: &[DataType]) -> Check {
+		for typ in types.iter().rev() {
+			self.pop(Some(typ.clone()))?;
+		}
+		Ok(())
+	}
+}
+
+pub(super) fn memarg(instr: &Instr) -> Option<(u32, u32, u32)> {
+	use Instr::*;
+	Some(match instr {
+		i32_load8_s(a, o) | i32_load8_u(a, o) | i64_load8_s(a, o) => (*a, *o, 0),
+		i64_load8_u(a, o) | i32_store8(a, o) | i64_store8(a, o) => (*a, *o, 0),
+		i32_load16_s(a, o) | i32_load16_u(a, o) | i64_load16_s(a, o) => (*a, *o, 1),
+		i64_load16_u(a, o) | i32_store16(a, o) | i64_store16(a, o) => (*a, *o, 1),
+		i32_load(a, o) | f32_load(a, o) | i64_load32_s(a, o) => (*a, *o, 2),
+		i64_load32_u(a, o) | i32_store(a, o) | f32_store(a, o) => (*a, *o, 2),
+		i64_store32(a, o) => (*a, *o, 2),
+		i64_load(a, o) | f64_load(a, o) | i64_store(a, o) => (*a, *o, 3),
+		f64_store(a, o) => (*a, *o, 3),
+		v128_load8_splat(a, o) | v128_load8_lane(a, o, _) | v128_store8_lane(a, o, _) => {
+			(*a, *o, 0)
+		}
+		v128_load16_splat(a, o) | v128_load16_lane(a, o, _) => (*a, *o, 1),
+		v128_store16_lane(a, o, _) => (*a, *o, 1),
+		v128_load32_splat(a, o) | v128_load32_zero(a, o) | v128_load32_lane(a, o, _) => (*a, *o, 2),
+		v128_store32_lane(a, o, _) => (*a, *o, 2),
+		v128_load8x8_s(a, o) | v128_load8x8_u(a, o) | v128_load16x4_s(a, o) => (*a, *o, 3),
+		v128_load16x4_u(a, o) | v128_load32x2_s(a, o) | v128_load32x2_u(a, o) => (*a, *o, 3),
+		v128_load64_splat(a, o) | v128_load64_zero(a, o) | v128_load64_lane(a, o, _) => (*a, *o, 3),
+		v128_store64_lane(a, o, _) => (*a, *o, 3),
+		v128_load(a, o) | v128_store(a, o) => (*a, *o, 4),
+		_ => return None,
+	})
+}
+
+pub(super) fn lane(instr: &Instr) -> Option<(u8, u8)> {
+	use Instr::*;
+	Some(match instr {
+		i8x16_extract_lane_s(l) | i8x16_extract_lane_u(l) | i8x16_replace_lane(l) => (*l, 16),
+		v128_load8_lane(_, _, l) | v128_store8_lane(_, _, l) => (*l, 16),
+		i16x8_extract_lane_s(l) | i16x8_extract_lane_u(l) | i16x8_replace_lane(l) => (*l, 8),
+		v128_load16_lane(_, _, l) | v128_store16_lane(_, _, l) => (*l, 8),
+		i32x4_extract_lane(l) | i32x4_replace_lane(l) | f32x4_extract_lane(l) => (*l, 4),
+		f32x4_replace_lane(l) | v128_load32_lane(_, _, l) | v128_store32_lane(_, _, l) => (*l, 4),
+		i64x2_extract_lane(l) | i64x2_replace_lane(l) | f64x2_extract_lane(l) => (*l, 2),
+		f64x2_replace_lane(l) | v128_load64_lane(_, _, l) | v128_store64_lane(_, _, l) => (*l, 2),
+		_ => return None,
+	})
+}
+
+pub(super) fn parse_type(name: &str) -> Option<DataType> {
+	match name {
+		"i32" => Some(DataType::I32),
+		"i64" => Some(DataType::I64),
+		"f32" => Some(DataType::F32),
+		"f64" => Some(DataType::F64),
+		"v128" => Some(DataType::V128),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::wasm::{Data, Element, FunctionType, Global, Limits, TableType};
+	use DataType::*;
+	use Instr::*;
+
+	fn module(params: Vec<DataType>, resuls: Vec<DataType>, body: Vec<Instr>) -> Wasm {
+		let signature = FunctionType { params, resuls };
+		let log = FunctionType { params: vec![I64], resuls: vec![] };
+		Wasm {
+			imports: vec![(log, "env".to_string(), "log".to_string())],
+			functions: vec![Function { signature, locals: vec![F32], body }],
+			..Default::default()
+		}
+	}
+
+	fn error(wasm: Wasm) -> String {
+		wasm.validate().unwrap_err().to_string()
+	}
+
+	#[test]
+	fn accepts_valid_functions() {
+		let body = vec![
+			block(
+				BlockType::Value(I32),
+				vec![local_get(0), local_get(0), br_if(0), i32_const(1), i32_add],
+			),
+			if_else(BlockType::Value(I64), vec![i64_const(1)], vec![unreachable]),
+			call(0),
+			local_get(1),
+			local_get(1),
+			f32_lt,
+			looped(BlockType::Empty, vec![br(0)]),
+		];
+		module(vec![I32], vec![I32], body).validate().unwrap();
+	}
+
+	#[test]
+	fn checks_block_types() {
+		let with_types = |body| {
+			let mut wasm = module(vec![I32], vec![I32, I64], body);
+			wasm.types.push(FunctionType { params: vec![I32], resuls: vec![I32, I64] });
+			wasm.types.push(FunctionType { params: vec![I32], resuls: vec![I32] });
+			wasm
+		};
+		let body = vec![
+			local_get(0),
+			looped(
+				BlockType::Type(1),
+				vec![i32_const(1), i32_sub, local_tee(0), local_get(0), br_if(0)],
+			),
+			local_get(0),
+			if_else(BlockType::Type(1), vec![], vec![]),
+			block(BlockType::Type(0), vec![i64_const(2), br(0)]),
+		];
+		with_types(body).validate().unwrap();
+		let body = vec![local_get(0), block(BlockType::Type(0), vec![i64_const(2), i32_const(3)])];
+		assert_eq!(
+			error(with_types(body)),
+			"function 1, instruction 1: Expected i64 but found i32"
+		);
+		let body = vec![local_get(0), local_get(0), if_else(BlockType::Type(0), vec![], vec![])];
+		assert_eq!(
+			error(with_types(body)),
+			"function 1, instruction 2: If without else must return its parameters"
+		);
+		let body = vec![block(BlockType::Type(1), vec![])];
+		assert_eq!(
+			error(with_types(body)),
+			"function 1, instruction 0: Expected i32 but the stack is empty"
+		);
+		let body = vec![block(BlockType::Type(2), vec![])];
+		assert_eq!(error(with_types(body)), "function 1, instruction 0: Unknown type: 2");
+	}
+
+	#[test]
+	fn reports_first_error() {
+		let body = vec![nop, block(BlockType::Empty, vec![i32_const(1), i64_const(2), i32_add])];
+		assert_eq!(
+			error(module(vec![], vec![], body)),
+			"function 1, instruction 1.2: Expected i32 but found i64"
+		);
+		let body = vec![if_else(BlockType::Empty, vec![], vec![nop, br(2)])];
+		assert_eq!(
+			error(module(vec![I32], vec![], body)),
+			"function 1, instruction 0: Expected i32 but the stack is empty"
+		);
+		let body = vec![local_get(0), if_else(BlockType::Empty, vec![], vec![nop, br(2)])];
+		assert_eq!(
+			error(module(vec![I32], vec![], body)),
+			"function 1, instruction 1.1: Unknown label: 2"
+		);
+		let body = vec![i32_const(1), call(0)];
+		assert_eq!(
+			error(module(vec![], vec![], body)),
+			"function 1, instruction 1: Expected i64 but found i32"
+		);
+		let body = vec![local_get(1)];
+		assert_eq!(
+			error(module(vec![], vec![], body)),
+			"function 1, instruction 0: Unknown local: 1"
+		);
+		let body = vec![i32_const(1), i32_const(2)];
+		assert_eq!(
+			error(module(vec![], vec![I32], body)),
+			"function 1: Block leaves 1 extra values on the stack"
+		);
+		let body = vec![i32_const(0), br_table(vec![])];
+		assert_eq!(
+			error(module(vec![], vec![], body)),
+			"function 1, instruction 1: br_table without default label"
+		);
+	}
+
+	#[test]
+	fn checks_start_function() {
+		let mut wasm = module(vec![], vec![], vec![]);
+		wasm.start_function = Some(1);
+		wasm.validate().unwrap();
+		wasm.start_function = Some(0);
+		assert_eq!(
+			error(wasm.clone()),
+			"function 0: Start function must not take or return values"
+		);
+		wasm.start_function = Some(5);
+		assert_eq!(error(wasm), "Unknown start function: 5");
+	}
+
+	#[test]
+	fn checks_exports() {
+		let mut wasm = module(vec![], vec![], vec![]);
+		wasm.exports.insert("f".to_string(), 1);
+		wasm.validate().unwrap();
+		wasm.exports.insert("g".to_string(), 9);
+		assert_eq!(error(wasm.clone()), "Export g: Unknown function: 9");
+		wasm.exports.remove("g");
+		wasm.global_exports.insert("x".to_string(), 0);
+		assert_eq!(error(wasm.clone()), "Export x: Unknown global: 0");
+		wasm.global_exports.clear();
+		wasm.memory_exports.insert("memory".to_string(), 0);
+		assert_eq!(error(wasm), "Export memory: Unknown memory: 0");
+	}
+
+	#[test]
+	fn checks_memory() {
+		let body = vec![i32_const(0), i64_load(3, 0), drop];
+		assert_eq!(
+			error(module(vec![], vec![], body.clone())),
+			"function 1, instruction 1: Unknown memory: 0"
+		);
+		let mut wasm = module(vec![], vec![], body);
+		wasm.memories.push(Limits { min: 1, max: None });
+		wasm.validate().unwrap();
+		wasm.functions[0].body[1] = i64_load(4, 0);
+		assert_eq!(
+			error(wasm),
+			"function 1, instruction 1: Alignment must not be larger than natural"
+		);
+
+		let mut wasm = module(vec![], vec![], vec![]);
+		wasm.memories.push(Limits { min: 2, max: Some(1) });
+		assert_eq!(error(wasm), "Memory maximum must not be smaller than minimum");
+		let mut wasm = module(vec![], vec![], vec![]);
+		wasm.memories.push(Limits { min: 1, max: None });
+		wasm.data.push(Data { mode: DataMode::Active(0, vec![i64_const(0)]), bytes: vec![] });
+		assert_eq!(error(wasm), "Data segment 0: Expected i32 but found i64");
+	}
+
+	#[test]
+	fn checks_globals() {
+		let mut wasm =
+			module(vec![], vec![], vec![global_get(1), i32_const(1), i32_add, global_set(1)]);
+		let typ = GlobalType { typ: I32, mutable: false };
+		wasm.global_imports.push((typ.clone(), "env".to_string(), "base".to_string()));
+		wasm.globals.push(Global { typ: typ.clone(), init: vec![global_get(0)] });
+		assert_eq!(error(wasm.clone()), "function 1, instruction 3: Global 1 is immutable");
+		wasm.globals[0].typ.mutable = true;
+		wasm.validate().unwrap();
+		wasm.globals.push(Global { typ, init: vec![global_get(1)] });
+		assert_eq!(
+			error(wasm.clone()),
+			"Global 2: Constant expression may only read immutable imports"
+		);
+		wasm.globals[1].init = vec![i64_const(0)];
+		assert_eq!(error(wasm), "Global 2: Expected i32 but found i64");
+	}
+
+	#[test]
+	fn checks_indirect_calls() {
+		let body = vec![f64_const(1.0), i32_const(0), call_indirect(0, 0)];
+		let mut wasm = module(vec![], vec![I32], body);
+		wasm.types.push(FunctionType { params: vec![F64], resuls: vec![I32] });
+		assert_eq!(error(wasm.clone()), "function 1, instruction 2: Unknown table: 0");
+		wasm.tables.push(TableType { typ: FuncRef, limits: Limits { min: 1, max: None } });
+		wasm.validate().unwrap();
+		wasm.functions[0].body[2] = call_indirect(1, 0);
+		assert_eq!(error(wasm.clone()), "function 1, instruction 2: Unknown type: 1");
+		wasm.functions[0].body[2] = call_indirect(0, 0);
+		let offset = vec![i32_const(0)];
+		wasm.elements.push(Element { mode: ElementMode::Active(0, offset), functions: vec![1, 2] });
+		assert_eq!(error(wasm), "Element segment 0: Unknown function: 2");
+	}
+
+	#[test]
+	fn checks_bulk_instructions() {
+		let args = vec![i32_const(0), i32_const(0), i32_const(0)];
+		let mut wasm = module(vec![], vec![], [args.clone(), vec![memory_init(0, 0)]].concat());
+		assert_eq!(error(wasm.clone()), "function 1, instruction 3: Unknown memory: 0");
+		wasm.memories.push(Limits { min: 1, max: None });
+		assert_eq!(error(wasm.clone()), "function 1, instruction 3: Unknown data segment: 0");
+		wasm.data.push(Data { mode: DataMode::Passive, bytes: vec![] });
+		wasm.validate().unwrap();
+		wasm.functions[0].body = [args, vec![table_copy(0, 1)]].concat();
+		assert_eq!(error(wasm.clone()), "function 1, instruction 3: Unknown table: 0");
+		wasm.functions[0].body = vec![elem_drop(0)];
+		assert_eq!(error(wasm), "function 1, instruction 0: Unknown element segment: 0");
+	}
+
+	#[test]
+	fn checks_vectors() {
+		let body = vec![i32_const(0), v128_load(4, 0), i32x4_extract_lane(3)];
+		let mut wasm = module(vec![], vec![I32], body);
+		wasm.memories.push(Limits { min: 1, max: None });
+		wasm.validate().unwrap();
+		wasm.functions[0].body[2] = i32x4_extract_lane(4);
+		assert_eq!(error(wasm.clone()), "function 1, instruction 2: Invalid lane index: 4");
+		wasm.functions[0].body[2] = i64x2_extract_lane(0);
+		assert_eq!(error(wasm.clone()), "function 1: Expected i32 but found i64");
+		wasm.functions[0].body[1] = v128_load(5, 0);
+		let message = "function 1, instruction 1: Alignment must not be larger than natural";
+		assert_eq!(error(wasm.clone()), message);
+		let mut lanes = [0; 16];
+		lanes[15] = 32;
+		let body = vec![v128_const(0), v128_const(1), i8x16_shuffle(lanes), v128_any_true];
+		wasm.functions[0].body = body;
+		assert_eq!(error(wasm), "function 1, instruction 2: Invalid lane index: 32");
+	}
+
+	#[test]
+	fn checks_references() {
+		let body = vec![ref_null(ExternRef), i32_const(0), table_grow(0)];
+		let mut wasm = module(vec![], vec![I32], body);
+		wasm.tables.push(TableType { typ: ExternRef, limits: Limits { min: 0, max: None } });
+		wasm.validate().unwrap();
+		wasm.functions[0].body[0] = ref_null(FuncRef);
+		assert_eq!(
+			error(wasm.clone()),
+			"function 1, instruction 2: Expected externref but found funcref"
+		);
+		wasm.functions[0].body = vec![ref_func(0), ref_is_null];
+		assert_eq!(
+			error(wasm.clone()),
+			"function 1, instruction 0: Undeclared function reference: 0"
+		);
+		wasm.exports.insert("main".to_string(), 0);
+		wasm.validate().unwrap();
+		let choose = |instr| {
+			vec![ref_null(FuncRef), ref_null(FuncRef), i32_const(1), instr, drop, i32_const(0)]
+		};
+		wasm.functions[0].body = choose(select);
+		assert_eq!(
+			error(wasm.clone()),
+			"function 1, instruction 3: Untyped select requires numeric operands"
+		);
+		wasm.functions[0].body = choose(select_t(vec![FuncRef]));
+		wasm.validate().unwrap();
+		wasm.functions[0].body = vec![i32_const(0), call_indirect(0, 0)];
+		wasm.types.push(FunctionType { params: vec![], resuls: vec![I32] });
+		assert_eq!(
+			error(wasm),
+			"function 1, instruction 1: Expected funcref table but found externref"
+		);
+	}
+}
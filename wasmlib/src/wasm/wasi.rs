@@ -0,0 +1,545 @@
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use super::interpreter::{Imports, Instance, Trap, Value};
+use super::memory::Memory;
+use super::{DataType, Wasm};
+
+const MODULE: &str = "wasi_snapshot_preview1";
+
+type Errno = i32;
+
+const ACCES: Errno = 2;
+const BADF: Errno = 8;
+const EXIST: Errno = 20;
+const FAULT: Errno = 21;
+const INVAL: Errno = 28;
+const IO: Errno = 29;
+const ISDIR: Errno = 31;
+const NOENT: Errno = 44;
+const NOTDIR: Errno = 54;
+const NOTCAPABLE: Errno = 76;
+
+const OFLAGS_CREAT: u32 = 1;
+const OFLAGS_DIRECTORY: u32 = 2;
+const OFLAGS_EXCL: u32 = 4;
+const OFLAGS_TRUNC: u32 = 8;
+const FDFLAGS_APPEND: u32 = 1;
+const RIGHTS_FD_WRITE: u32 = 1 << 6;
+
+enum Descriptor {
+	Reader(Box<dyn Read>),
+	Writer(Box<dyn Write>),
+	File(File),
+	Directory(String, PathBuf),
+}
+
+struct State {
+	args: Vec<String>,
+	environment: Vec<String>,
+	descriptors: Vec<Option<Descriptor>>,
+	preopens: usize,
+	start: Instant,
+}
+
+pub struct Wasi {
+	state: Rc<RefCell<State>>,
+}
+
+impl Default for Wasi {
+	fn default() -> Wasi {
+		let descriptors = vec![
+			Some(Descriptor::Reader(Box::new(io::stdin()))),
+			Some(Descriptor::Writer(Box::new(io::stdout()))),
+			Some(Descriptor::Writer(Box::new(io::stderr()))),
+		];
+		let state = State {
+			args: vec![],
+			environment: vec![],
+			descriptors,
+			preopens: 0,
+			start: Instant::now(),
+		};
+		Wasi { state: Rc::new(RefCell::new(state)) }
+	}
+}
+
+impl Wasi {
+	pub fn new() -> Wasi {
+		Wasi::default()
+	}
+
+	pub fn arg(&mut self, arg: &str) -> &mut Self {
+		self.state.borrow_mut().args.push(arg.to_string());
+		self
+	}
+
+	pub fn env(&mut self, key: &str, value: &str) -> &mut Self {
+		self.state.borrow_mut().environment.push(format!("{}={}", key, value));
+		self
+	}
+
+	pub fn stdin(&mut self, reader: impl Read + 'static) -> &mut Self {
+		self.state.borrow_mut().descriptors[0] = Some(Descriptor::Reader(Box::new(reader)));
+		self
+	}
+
+	pub fn stdout(&mut self, writer: impl Write + 'static) -> &mut Self {
+		self.state.borrow_mut().descriptors[1] = Some(Descriptor::Writer(Box::new(writer)));
+		self
+	}
+
+	pub fn stderr(&mut self, writer: impl Write + 'static) -> &mut Self {
+		self.state.borrow_mut().descriptors[2] = Some(Descriptor::Writer(Box::new(writer)));
+		self
+	}
+
+	/// Exposes `host` to the guest as the directory `guest`. Paths opened below it may not
+	/// leave it, neither through `..` nor through symbolic links.
+	pub fn preopen(&mut self, guest: &str, host: impl Into<PathBuf>) -> &mut Self {
+		let directory = Descriptor::Directory(guest.to_string(), host.into());
+		let mut state = self.state.borrow_mut();
+		let index = 3 + state.preopens;
+		state.descriptors.insert(index, Some(directory));
+		state.preopens += 1;
+		drop(state);
+		self
+	}
+
+	pub fn link(&self, imports: &mut Imports) {
+		use DataType::*;
+		let mut register = |name: &str, params: &[DataType], function: Syscall| {
+			let state = self.state.clone();
+			imports.function(MODULE, name, params, &[I32], move |memories, args| {
+				let memory = memories.first_mut().ok_or(Trap::MemoryOutOfBounds)?;
+				let args: Vec<_> = args.iter().map(word).collect();
+				let errno = function(&mut state.borrow_mut(), memory, &args).err().unwrap_or(0);
+				Ok(vec![Value::I32(errno)])
+			});
+		};
+		register("args_get", &[I32, I32], args_get);
+		register("args_sizes_get", &[I32, I32], args_sizes_get);
+		register("environ_get", &[I32, I32], environ_get);
+		register("environ_sizes_get", &[I32, I32], environ_sizes_get);
+		register("clock_time_get", &[I32, I64, I32], clock_time_get);
+		register("random_get", &[I32, I32], random_get);
+		register("fd_write", &[I32, I32, I32, I32], fd_write);
+		register("fd_read", &[I32, I32, I32, I32], fd_read);
+		register("fd_close", &[I32], fd_close);
+		register("fd_prestat_get", &[I32, I32], fd_prestat_get);
+		register("fd_prestat_dir_name", &[I32, I32, I32], fd_prestat_dir_name);
+		register("path_open", &[I32, I32, I32, I32, I32, I64, I64, I32, I32], path_open);
+		imports.function(MODULE, "proc_exit", &[I32], &[], |_, args| {
+			Err(Trap::Exit(word(&args[0]) as i32))
+		});
+	}
+
+	pub fn run(&self, wasm: &Wasm, mut imports: Imports) -> Result<i32, Trap> {
+		self.link(&mut imports);
+		let result = Instance::with_imports(wasm, imports)
+			.and_then(|mut instance| instance.invoke("_start", &[]));
+		match result {
+			Ok(_) => Ok(0),
+			Err(Trap::Exit(code)) => Ok(code),
+			Err(trap) => Err(trap),
+		}
+	}
+}
+
+type Syscall = fn(&mut State, &mut Memory, &[u32]) -> Result<(), Errno>;
+
+// 64-bit arguments (clock precision and rights) only matter in their low bits here.
+fn word(value: &Value) -> u32 {
+	match value {
+		Value::I32(x) => *x as u32,
+		Value::I64(x) => *x as u32,
+		_ => 0,
+	}
+}
+
+fn slice(memory: &Memory, address: u32, length: u32) -> Result<&[u8], Errno> {
+	let end = address as u64 + length as u64;
+	memory.bytes.get(address as usize..end as usize).ok_or(FAULT)
+}
+
+fn at(address: u32, offset: usize) -> Result<u32, Errno> {
+	u32::try_from(address as usize + offset).map_err(|_| FAULT)
+}
+
+fn store(memory: &mut Memory, address: u32, bytes: &[u8]) -> Result<(), Errno> {
+	memory.write(address, 0, bytes).map_err(|_| FAULT)
+}
+
+fn store_u32(memory: &mut Memory, address: u32, value: u32) -> Result<(), Errno> {
+	store(memory, address, &value.to_le_bytes())
+}
+
+fn iovecs(memory: &Memory, address: u32, count: u32) -> Result<Vec<(u32, u32)>, Errno> {
+	let entries = slice(memory, address, count.checked_mul(8).ok_or(FAULT)?)?;
+	let word = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+	Ok(entries.chunks(8).map(|entry| (word(&entry[..4]), word(&entry[4..]))).collect())
+}
+
+fn errno(error: io::Error) -> Errno {
+	match error.kind() {
+		io::ErrorKind::NotFound => NOENT,
+		io::ErrorKind::PermissionDenied => ACCES,
+		io::ErrorKind::AlreadyExists => EXIST,
+		io::ErrorKind::InvalidInput => INVAL,
+		io::ErrorKind::NotADirectory => NOTDIR,
+		io::ErrorKind::IsADirectory => ISDIR,
+		_ => IO,
+	}
+}
+
+fn strings(
+	memory: &mut Memory,
+	strings: &[String],
+	pointers: u32,
+	buffer: u32,
+) -> Result<(), Errno> {
+	let mut offset = buffer;
+	for (i, string) in strings.iter().enumerate() {
+		store_u32(memory, at(pointers, 4 * i)?, offset)?;
+		store(memory, offset, string.as_bytes())?;
+		store(memory, at(offset, string.len())?, &[0])?;
+		offset = at(offset, string.len() + 1)?;
+	}
+	Ok(())
+}
+
+fn sizes(memory: &mut Memory, strings: &[String], count: u32, size: u32) -> Result<(), Errno> {
+	store_u32(memory, count, strings.len() as u32)?;
+	store_u32(memory, size, strings.iter().map(|string| string.len() as u32 + 1).sum())
+}
+
+fn args_get(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	strings(memory, &state.args, args[0], args[1])
+}
+
+fn args_sizes_get(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	sizes(memory, &state.args, args[0], args[1])
+}
+
+fn environ_get(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	strings(memory, &state.environment, args[0], args[1])
+}
+
+fn environ_sizes_get(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	sizes(memory, &state.environment, args[0], args[1])
+}
+
+fn clock_time_get(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	let time = match args[0] {
+		0 => SystemTime::now().duration_since(UNIX_EPOCH).map_err(|_| IO)?,
+		1 => state.start.elapsed(),
+		_ => return Err(INVAL),
+	};
+	store(memory, args[2], &(time.as_nanos() as u64).to_le_bytes())
+}
+
+// Guests seed hash maps and generators from this, so it must come from the OS.
+fn random_get(_: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	let mut bytes = vec![0; slice(memory, args[0], args[1])?.len()];
+	File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut bytes)).map_err(errno)?;
+	store(memory, args[0], &bytes)
+}
+
+fn descriptor(state: &mut State, fd: u32) -> Result<&mut Descriptor, Errno> {
+	state.descriptors.get_mut(fd as usize).and_then(Option::as_mut).ok_or(BADF)
+}
+
+fn fd_write(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	let mut written = 0;
+	for (address, length) in iovecs(memory, args[1], args[2])? {
+		let bytes = slice(memory, address, length)?;
+		match descriptor(state, args[0])? {
+			Descriptor::Writer(writer) => writer.write_all(bytes).map_err(errno)?,
+			Descriptor::File(file) => file.write_all(bytes).map_err(errno)?,
+			Descriptor::Reader(_) => return Err(BADF),
+			Descriptor::Directory(..) => return Err(ISDIR),
+		}
+		written += length;
+	}
+	store_u32(memory, args[3], written)
+}
+
+fn fd_read(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	let mut read = 0;
+	for (address, length) in iovecs(memory, args[1], args[2])? {
+		slice(memory, address, length)?;
+		let buffer = &mut memory.bytes[address as usize..address as usize + length as usize];
+		let count = match descriptor(state, args[0])? {
+			Descriptor::Reader(reader) => reader.read(buffer).map_err(errno)?,
+			Descriptor::File(file) => file.read(buffer).map_err(errno)?,
+			Descriptor::Writer(_) => return Err(BADF),
+			Descriptor::Directory(..) => return Err(ISDIR),
+		};
+		read += count as u32;
+		if count < length as usize {
+			break;
+		}
+	}
+	store_u32(memory, args[3], read)
+}
+
+fn fd_close(state: &mut State, _: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	descriptor(state, args[0])?;
+	state.descriptors[args[0] as usize] = None;
+	Ok(())
+}
+
+fn preopen(state: &mut State, fd: u32) -> Result<&str, Errno> {
+	if !(3..3 + state.preopens as u32).contains(&fd) {
+		return Err(BADF);
+	}
+	match descriptor(state, fd)? {
+		Descriptor::Directory(name, _) => Ok(name),
+		_ => Err(BADF),
+	}
+}
+
+fn fd_prestat_get(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	let length = preopen(state, args[0])?.len() as u32;
+	store_u32(memory, args[1], 0)?;
+	store_u32(memory, at(args[1], 4)?, length)
+}
+
+fn fd_prestat_dir_name(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	let name = preopen(state, args[0])?;
+	let length = name.len().min(args[2] as usize);
+	store(memory, args[1], &name.as_bytes()[..length])
+}
+
+/// Joins `path` onto `root` without ever climbing above it. Symbolic links are refused, even
+/// dangling ones, since opening with `O_CREAT` would create their target wherever it points.
+fn resolve(root: &Path, path: &str) -> Result<PathBuf, Errno> {
+	let mut resolved = root.to_path_buf();
+	let mut depth = 0;
+	for component in Path::new(path).components() {
+		match component {
+			Component::Normal(name) => {
+				resolved.push(name);
+				depth += 1;
+				if resolved.symlink_metadata().is_ok_and(|metadata| metadata.is_symlink()) {
+					return Err(NOTCAPABLE);
+				}
+			}
+			Component::CurDir => {}
+			Component::ParentDir if depth > 0 => {
+				resolved.pop();
+				depth -= 1;
+			}
+			_ => return Err(NOTCAPABLE),
+		}
+	}
+	let root = root.canonicalize().map_err(errno)?;
+	let existing = resolved.ancestors().find_map(|ancestor| ancestor.canonicalize().ok());
+	match existing {
+		Some(existing) if existing.starts_with(&root) => Ok(resolved),
+		_ => Err(NOTCAPABLE),
+	}
+}
+
+fn path_open(state: &mut State, memory: &mut Memory, args: &[u32]) -> Result<(), Errno> {
+	let [fd, _, path, length, oflags, rights, _, fdflags, opened] = args.try_into().unwrap();
+	let path = std::str::from_utf8(slice(memory, path, length)?).map_err(|_| INVAL)?.to_string();
+	let root = match descriptor(state, fd)? {
+		Descriptor::Directory(_, root) => root.clone(),
+		_ => return Err(NOTDIR),
+	};
+	let resolved = resolve(&root, &path)?;
+	let descriptor = if oflags & OFLAGS_DIRECTORY != 0 || resolved.is_dir() {
+		if !resolved.is_dir() {
+			return Err(NOTDIR);
+		}
+		Descriptor::Directory(path, resolved)
+	} else {
+		let file = OpenOptions::new()
+			.read(true)
+			.write(rights & RIGHTS_FD_WRITE != 0)
+			.append(fdflags & FDFLAGS_APPEND != 0)
+			.create(oflags & OFLAGS_CREAT != 0)
+			.create_new(oflags & OFLAGS_CREAT != 0 && oflags & OFLAGS_EXCL != 0)
+			.truncate(oflags & OFLAGS_TRUNC != 0)
+			.open(resolved)
+			.map_err(errno)?;
+		Descriptor::File(file)
+	};
+	state.descriptors.push(Some(descriptor));
+	store_u32(memory, opened, state.descriptors.len() as u32 - 1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Clone, Default)]
+	struct Output(Rc<RefCell<Vec<u8>>>);
+
+	impl Write for Output {
+		fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+			self.0.borrow_mut().extend_from_slice(bytes);
+			Ok(bytes.len())
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	const IMPORTS: &str = r#"
+		(import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+		(import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+		(import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+	"#;
+
+	const PRINT: &str = r#"
+		(memory (export "memory") 1)
+		(func $print (param $address i32) (param $length i32) (result i32)
+			(i32.store (i32.const 0) (local.get $address))
+			(i32.store (i32.const 4) (local.get $length))
+			(call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+	"#;
+
+	fn run(wasi: &mut Wasi, text: &str) -> (Result<i32, Trap>, String) {
+		let wasm = Wasm::from_wat(&format!("{}{}{}", IMPORTS, text, PRINT)).unwrap();
+		wasm.validate().unwrap();
+		let output = Output::default();
+		wasi.stdout(output.clone());
+		let result = wasi.run(&wasm, Imports::new());
+		let output = String::from_utf8(output.0.take()).unwrap();
+		(result, output)
+	}
+
+	#[test]
+	fn writes_args_and_environment() {
+		let text = r#"
+			(import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (param i32 i32) (result i32)))
+			(import "wasi_snapshot_preview1" "args_get" (func $args_get (param i32 i32) (result i32)))
+			(import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (param i32 i32) (result i32)))
+			(import "wasi_snapshot_preview1" "environ_get" (func $environ_get (param i32 i32) (result i32)))
+			(data (i32.const 16) "hello\n")
+			(func (export "_start")
+				(drop (call $print (i32.const 16) (i32.const 6)))
+				(drop (call $args_sizes_get (i32.const 32) (i32.const 36)))
+				(drop (call $args_get (i32.const 64) (i32.const 128)))
+				(drop (call $print (i32.const 128) (i32.load (i32.const 36))))
+				(drop (call $environ_sizes_get (i32.const 32) (i32.const 36)))
+				(drop (call $environ_get (i32.const 64) (i32.const 256)))
+				(drop (call $print (i32.load (i32.const 68)) (i32.const 7)))
+				(call $proc_exit (i32.load (i32.const 32)))
+				unreachable)
+		"#;
+		let mut wasi = Wasi::new();
+		wasi.arg("main").arg("-v").env("A", "1").env("HOME", "/");
+		let (result, output) = run(&mut wasi, text);
+		assert_eq!(result, Ok(2));
+		assert_eq!(output, "hello\nmain\0-v\0HOME=/\0");
+	}
+
+	#[test]
+	fn reads_stdin_and_samples_clocks() {
+		let text = r#"
+			(import "wasi_snapshot_preview1" "clock_time_get" (func $clock_time_get (param i32 i64 i32) (result i32)))
+			(import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32 i32) (result i32)))
+			(func (export "_start")
+				(i32.store (i32.const 16) (i32.const 100))
+				(i32.store (i32.const 20) (i32.const 3))
+				(i32.store (i32.const 24) (i32.const 200))
+				(i32.store (i32.const 28) (i32.const 10))
+				(drop (call $fd_read (i32.const 0) (i32.const 16) (i32.const 2) (i32.const 32)))
+				(drop (call $print (i32.const 100) (i32.const 3)))
+				(drop (call $print (i32.const 200) (i32.sub (i32.load (i32.const 32)) (i32.const 3))))
+				(if (i32.ne (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 40)) (i32.const 0))
+					(then unreachable))
+				(if (i64.lt_u (i64.load (i32.const 40)) (i64.const 1600000000000000000))
+					(then unreachable))
+				(if (i32.ne (call $clock_time_get (i32.const 7) (i64.const 1) (i32.const 40)) (i32.const 28))
+					(then unreachable))
+				(drop (call $random_get (i32.const 300) (i32.const 32)))
+				(if (i64.eqz (i64.or (i64.load (i32.const 300)) (i64.load (i32.const 324))))
+					(then unreachable))
+				(if (i32.ne (call $random_get (i32.const 65530) (i32.const 16)) (i32.const 21))
+					(then unreachable)))
+		"#;
+		let mut wasi = Wasi::new();
+		wasi.stdin(&b"abcdefg"[..]);
+		let (result, output) = run(&mut wasi, text);
+		assert_eq!(result, Ok(0));
+		assert_eq!(output, "abcdefg");
+	}
+
+	#[test]
+	fn sandboxes_preopened_directories() {
+		let text = r#"
+			(import "wasi_snapshot_preview1" "fd_prestat_get" (func $fd_prestat_get (param i32 i32) (result i32)))
+			(import "wasi_snapshot_preview1" "fd_prestat_dir_name" (func $fd_prestat_dir_name (param i32 i32 i32) (result i32)))
+			(import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+			(import "wasi_snapshot_preview1" "fd_close" (func $fd_close (param i32) (result i32)))
+			(data (i32.const 100) "notes/../out.txt")
+			(data (i32.const 200) "../escape.txt")
+			(data (i32.const 300) "written\n")
+			(func $open (param $path i32) (param $length i32) (result i32)
+				(call $path_open (i32.const 3) (i32.const 0) (local.get $path) (local.get $length)
+					(i32.const 9) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 48)))
+			(func (export "_start")
+				(drop (call $fd_prestat_get (i32.const 3) (i32.const 16)))
+				(drop (call $fd_prestat_dir_name (i32.const 3) (i32.const 400) (i32.load (i32.const 20))))
+				(drop (call $print (i32.const 400) (i32.load (i32.const 20))))
+				(if (i32.ne (call $fd_prestat_get (i32.const 4) (i32.const 16)) (i32.const 8))
+					(then unreachable))
+				(if (i32.ne (call $open (i32.const 200) (i32.const 13)) (i32.const 76))
+					(then unreachable))
+				(if (call $open (i32.const 100) (i32.const 16))
+					(then unreachable))
+				(i32.store (i32.const 0) (i32.const 300))
+				(i32.store (i32.const 4) (i32.const 8))
+				(drop (call $fd_write (i32.load (i32.const 48)) (i32.const 0) (i32.const 1) (i32.const 8)))
+				(call $proc_exit (call $fd_close (i32.load (i32.const 48)))))
+		"#;
+		let root = std::env::temp_dir().join(format!("wasmlib-wasi-{}", std::process::id()));
+		std::fs::create_dir_all(root.join("notes")).unwrap();
+		let mut wasi = Wasi::new();
+		wasi.preopen("/sandbox", &root);
+		let (result, output) = run(&mut wasi, text);
+		let written = std::fs::read_to_string(root.join("out.txt"));
+		std::fs::remove_dir_all(&root).unwrap();
+		assert_eq!(result, Ok(0));
+		assert_eq!(output, "/sandbox");
+		assert_eq!(written.unwrap(), "written\n");
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn refuses_symbolic_links() {
+		let text = r#"
+			(import "wasi_snapshot_preview1" "path_open" (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+			(data (i32.const 100) "link")
+			(data (i32.const 200) "inner/file.txt")
+			(func (export "_start")
+				(call $proc_exit (i32.add
+					(i32.mul (i32.const 100)
+						(call $path_open (i32.const 3) (i32.const 0) (i32.const 100) (i32.const 4)
+							(i32.const 1) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 48)))
+					(call $path_open (i32.const 3) (i32.const 0) (i32.const 200) (i32.const 14)
+						(i32.const 1) (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 48)))))
+		"#;
+		let sandbox = std::env::temp_dir().join(format!("wasmlib-links-{}", std::process::id()));
+		let root = sandbox.join("root");
+		std::fs::create_dir_all(&root).unwrap();
+		std::os::unix::fs::symlink(sandbox.join("escaped.txt"), root.join("link")).unwrap();
+		std::os::unix::fs::symlink(&sandbox, root.join("inner")).unwrap();
+		let mut wasi = Wasi::new();
+		wasi.preopen("/", &root);
+		let (result, _) = run(&mut wasi, text);
+		let escaped = sandbox.join("escaped.txt").exists() || sandbox.join("file.txt").exists();
+		std::fs::remove_dir_all(&sandbox).unwrap();
+		assert_eq!(result, Ok(76 * 100 + 76));
+		assert!(!escaped);
+	}
+}